@@ -0,0 +1,76 @@
+defmodule ExPolars.BatchWriter do
+  @moduledoc """
+  Writes DataFrames one batch at a time to a single CSV or parquet file.
+
+  Every batch must have the same column names and dtypes as the first one, otherwise
+  `write/2` returns an `ExPolars.Error` of kind `:schema_mismatch`. The file is only complete
+  after `close/1`; a writer that is garbage collected without being closed is closed then.
+
+  Example
+  -------
+
+      {:ok, writer} = BatchWriter.parquet("out.parquet", compression: "zstd")
+      Enum.each(batches, &({:ok, {}} = BatchWriter.write(writer, &1)))
+      {:ok, {}} = BatchWriter.close(writer)
+  """
+
+  alias ExPolars.Native
+
+  defstruct [:inner]
+
+  @type t :: ExPolars.BatchWriter
+  @type df :: ExPolars.DataFrame
+
+  @spec csv(String.t(), keyword()) :: {:ok, t()} | {:error, term()}
+  @doc """
+  Create (or truncate) a CSV file. The header is written once, with the first batch.
+
+  Parameters
+  ----------
+  opts
+      `batch_size` (100_000), `has_headers` (true), `delimiter` (?,) and `compression`
+      ("infer"), as in `ExPolars.DataFrame.to_csv_file/6`.
+  """
+  def csv(filename, opts \\ []) do
+    Native.df_csv_writer(
+      filename,
+      Keyword.get(opts, :batch_size, 100_000),
+      Keyword.get(opts, :has_headers, true),
+      Keyword.get(opts, :delimiter, ?,),
+      Keyword.get(opts, :compression, "infer")
+    )
+  end
+
+  @spec parquet(String.t(), keyword()) :: {:ok, t()} | {:error, term()}
+  @doc """
+  Create (or truncate) a parquet file. Every batch is written as one row group.
+
+  Parameters
+  ----------
+  opts
+      `compression` ("snappy") and `statistics` (true), as in
+      `ExPolars.DataFrame.to_parquet/4`.
+  """
+  def parquet(filename, opts \\ []) do
+    Native.df_parquet_writer(
+      filename,
+      Keyword.get(opts, :compression, "snappy"),
+      Keyword.get(opts, :statistics, true)
+    )
+  end
+
+  @spec write(t() | {:ok, t()}, df() | {:ok, df()}) :: {:ok, {}} | {:error, term()}
+  @doc """
+  Append `df` to the file.
+  """
+  def write({:ok, writer}, df), do: write(writer, df)
+  def write(writer, {:ok, df}), do: write(writer, df)
+  defdelegate write(writer, df), to: Native, as: :df_write_batch
+
+  @spec close(t() | {:ok, t()}) :: {:ok, {}} | {:error, term()}
+  @doc """
+  Finalize and close the file. Writing to a closed writer is an error.
+  """
+  def close({:ok, writer}), do: close(writer)
+  defdelegate close(writer), to: Native, as: :df_close_writer
+end
@@ -0,0 +1,89 @@
+defmodule ExPolars.CsvBatchReader do
+  @moduledoc """
+  Reads a CSV file in batches of rows, so files larger than memory can be processed one
+  DataFrame at a time.
+
+  The dtypes are inferred from the first `infer_schema_length` rows of the first batch and
+  every later batch is parsed with them, they are never widened. A column that only holds
+  integers in those rows fails to parse in a later batch with a float (or gets a null for it
+  with `ignore_errors: true`); read a larger first batch or raise `infer_schema_length` to
+  avoid that.
+
+  Example
+  -------
+
+      "events.csv"
+      |> CsvBatchReader.stream(100_000, sep: ";", columns: ["user", "amount"])
+      |> Stream.map(&process_batch/1)
+      |> Stream.run()
+  """
+
+  alias ExPolars.Native
+
+  defstruct [:inner]
+
+  @type t :: ExPolars.CsvBatchReader
+  @type df :: ExPolars.DataFrame
+
+  @spec new(String.t(), keyword()) :: {:ok, t()} | {:error, term()}
+  @doc """
+  Open `filename` for batched reading. Nothing is read until the first `next_batch/2`.
+
+  Parameters
+  ----------
+  opts
+      The options of `ExPolars.DataFrame.read_csv/13`: `infer_schema_length` (100),
+      `batch_size` (64), `has_header` (true), `ignore_errors` (false), `skip_rows` (0),
+      `projection` (nil), `sep` (","), `columns` (nil), `encoding` ("utf8") and
+      `compression` ("infer"). Compressed files are decompressed while streaming.
+      `infer_schema_length` only applies to the first batch, see the module docs. As in
+      `read_csv`, `skip_rows` skips lines before the header, not records, so a quoted field
+      spanning several lines counts once per line.
+  """
+  def new(filename, opts \\ []) do
+    Native.df_read_csv_batched(
+      filename,
+      Keyword.get(opts, :infer_schema_length, 100),
+      Keyword.get(opts, :batch_size, 64),
+      Keyword.get(opts, :has_header, true),
+      Keyword.get(opts, :ignore_errors, false),
+      Keyword.get(opts, :skip_rows, 0),
+      Keyword.get(opts, :projection),
+      Keyword.get(opts, :sep, ","),
+      Keyword.get(opts, :columns),
+      Keyword.get(opts, :encoding, "utf8"),
+      Keyword.get(opts, :compression, "infer")
+    )
+  end
+
+  @spec next_batch(t() | {:ok, t()}, pos_integer()) :: {:ok, df() | nil} | {:error, term()}
+  @doc """
+  Read the next `n_rows` rows as a DataFrame. Returns `{:ok, nil}` once the file is exhausted.
+  """
+  def next_batch({:ok, reader}, n_rows), do: next_batch(reader, n_rows)
+  defdelegate next_batch(reader, n_rows), to: Native, as: :df_next_batch
+
+  @spec stream(String.t(), pos_integer(), keyword()) :: Enumerable.t()
+  @doc """
+  A stream of DataFrames of up to `n_rows` rows each. Takes the same options as `new/2` and
+  raises `ExPolars.Error` if the file can't be read.
+  """
+  def stream(filename, n_rows, opts \\ []) do
+    Stream.resource(
+      fn ->
+        case new(filename, opts) do
+          {:ok, reader} -> reader
+          {:error, error} -> raise error
+        end
+      end,
+      fn reader ->
+        case next_batch(reader, n_rows) do
+          {:ok, nil} -> {:halt, reader}
+          {:ok, df} -> {[df], reader}
+          {:error, error} -> raise error
+        end
+      end,
+      fn _reader -> :ok end
+    )
+  end
+end
@@ -2,6 +2,8 @@ defmodule ExPolars.DataFrame do
   alias ExPolars.Native
   alias ExPolars.Series, as: S
   alias ExPolars.Plot
+  alias ExPolars.LazyFrame
+  alias ExPolars.Expr
 
   @type t :: ExPolars.DataFrame
   @type s :: ExPolars.Series
@@ -20,8 +22,20 @@ defmodule ExPolars.DataFrame do
           String.t(),
           boolean(),
           list(String.t()) | nil,
+          String.t(),
           String.t()
         ) :: {:ok, t()} | {:error, term()}
+  @doc """
+  Read a CSV file. Gzip and zstd compressed files are decompressed transparently.
+
+  Parameters
+  ----------
+  compression
+      "gzip", "zstd" or "none", or "infer" (the default) to detect it from a `.gz`/`.zst`
+      extension or else from the first bytes of the file. The csv parser needs to seek, so a
+      compressed file is decompressed into memory as a whole before it's parsed; use
+      `ExPolars.CsvBatchReader` to read large compressed files with bounded memory.
+  """
   defdelegate read_csv(
                 filename,
                 infer_schema_length \\ 100,
@@ -34,16 +48,213 @@ defmodule ExPolars.DataFrame do
                 sep \\ ",",
                 rechunk \\ true,
                 columns \\ nil,
-                encoding \\ "utf8"
+                encoding \\ "utf8",
+                compression \\ "infer"
               ),
               to: Native,
               as: :df_read_csv
 
+  @spec read_csv_binary(
+          binary(),
+          integer(),
+          integer(),
+          boolean(),
+          boolean(),
+          integer() | nil,
+          integer(),
+          list(integer()) | nil,
+          String.t(),
+          boolean(),
+          list(String.t()) | nil,
+          String.t()
+        ) :: {:ok, t()} | {:error, term()}
+  @doc """
+  Same as `read_csv/13`, but parses uncompressed CSV data from a binary instead of a file.
+  """
+  defdelegate read_csv_binary(
+                data,
+                infer_schema_length \\ 100,
+                batch_size \\ 64,
+                has_header \\ true,
+                ignore_errors \\ false,
+                stop_after_n_rows \\ nil,
+                skip_rows \\ 0,
+                projection \\ nil,
+                sep \\ ",",
+                rechunk \\ true,
+                columns \\ nil,
+                encoding \\ "utf8"
+              ),
+              to: Native,
+              as: :df_read_csv_binary
+
   @spec read_parquet(String.t()) :: {:ok, t()} | {:error, term()}
   defdelegate read_parquet(filename), to: Native, as: :df_read_parquet
 
-  @spec read_json(String.t(), boolean()) :: {:ok, t()} | {:error, term()}
-  defdelegate read_json(filename, line_delimited_json \\ false), to: Native, as: :df_read_json
+  @spec read_parquet_binary(binary()) :: {:ok, t()} | {:error, term()}
+  defdelegate read_parquet_binary(data), to: Native, as: :df_read_parquet_binary
+
+  @spec scan_csv(String.t(), keyword()) :: {:ok, t()} | {:error, term()}
+  @doc """
+  Read the rows of a CSV file that match a filter, keeping only some columns. The filter and
+  the column selection are pushed down to the reader, so unused columns aren't parsed.
+
+  Parameters
+  ----------
+  opts
+      * `columns` - names of the columns to keep, all columns by default
+      * `filter` - an `ExPolars.Expr` predicate, e.g.
+        `and_(col("amount") > 100, col("country") == "NL")` with `ExPolars.Expr` imported
+      * `sep` (","), `has_header` (true), `ignore_errors` (false), `skip_rows` (0) and
+        `stop_after_n_rows` (nil), as in `read_csv/13`
+  """
+  def scan_csv(filename, opts \\ []) do
+    Native.df_scan_csv(
+      filename,
+      Keyword.get(opts, :columns),
+      Keyword.get(opts, :filter),
+      Keyword.get(opts, :sep, ","),
+      Keyword.get(opts, :has_header, true),
+      Keyword.get(opts, :ignore_errors, false),
+      Keyword.get(opts, :skip_rows, 0),
+      Keyword.get(opts, :stop_after_n_rows)
+    )
+  end
+
+  @spec scan_parquet(String.t(), keyword()) :: {:ok, t()} | {:error, term()}
+  @doc """
+  Read the rows of a parquet file that match a filter, keeping only some columns.
+
+  Only the selected columns and the columns used by the filter are decoded. Row groups whose
+  min/max statistics show that no row can match comparisons of a column with a literal
+  (`==`, `!=`, `<`, `<=`, `>`, `>=`, combined with `and`/`or`) are skipped entirely.
+
+  Parameters
+  ----------
+  opts
+      * `columns` - names of the columns to keep, all columns by default
+      * `filter` - an `ExPolars.Expr` predicate
+  """
+  def scan_parquet(filename, opts \\ []),
+    do: Native.df_scan_parquet(filename, Keyword.get(opts, :columns), Keyword.get(opts, :filter))
+
+  @spec read_dataset(String.t(), keyword()) :: {:ok, t()} | {:error, term()}
+  @doc """
+  Read a hive partitioned dataset: all parquet and csv files below `root`, where directories
+  named `key=value` give the values of partition columns, e.g.
+
+      events/date=2026-10-01/region=eu/part-0.parquet
+
+  The partition columns are added after the columns of the files. A partition column is an
+  integer, float, date32 or boolean column if all its values are plain numbers, `YYYY-MM-DD`
+  dates or booleans, otherwise a string column: zero-padded values like zip code "01234" or
+  values like "NaN" and "inf" aren't numbers, so they read back as written. Filters compare
+  date partitions as days since the epoch, like date32 columns in parquet files.
+  `__HIVE_DEFAULT_PARTITION__` is read as null. Files and directories starting with `.` or `_`
+  are ignored.
+
+  Parameters
+  ----------
+  opts
+      * `columns` - names of the columns to keep, all columns by default
+      * `filter` - an `ExPolars.Expr` predicate; partitions whose values can't match it are
+        skipped without being read, and for parquet files it is used as in `scan_parquet/2`
+  """
+  def read_dataset(root, opts \\ []),
+    do: Native.df_read_dataset(root, Keyword.get(opts, :columns), Keyword.get(opts, :filter))
+
+  @spec write_dataset(t() | {:ok, t()}, String.t(), list(String.t()), keyword()) ::
+          {:ok, list(String.t())} | {:error, term()}
+  @doc """
+  Write DataFrame as a hive partitioned dataset below `root`: one file
+  `root/key1=value1/key2=value2/part-0.<format>` per distinct combination of the `by` columns.
+  The `by` columns are only stored in the directory names.
+
+  Files already below `root` would be read back with the new ones by `read_dataset/2`, so
+  writing to a non-empty `root` is an error unless `mode: :overwrite` is given, which removes
+  everything below `root` first. Floats are written with a decimal point and date32 values as `YYYY-MM-DD`,
+  so `read_dataset/2` reads them back with their dtypes.
+
+  Returns the paths of the written files.
+
+  Parameters
+  ----------
+  opts
+      `format` ("parquet" or "csv", defaults to "parquet"), for parquet `compression`
+      ("snappy"), and `mode` (`:error` or `:overwrite`, defaults to `:error`).
+  """
+  def write_dataset(df, root, by, opts \\ [])
+  def write_dataset({:ok, df}, root, by, opts), do: write_dataset(df, root, by, opts)
+
+  def write_dataset(df, root, by, opts) do
+    Native.df_write_dataset(
+      df,
+      root,
+      by,
+      Keyword.get(opts, :format, "parquet"),
+      Keyword.get(opts, :compression, "snappy"),
+      opts |> Keyword.get(:mode, :error) |> Atom.to_string()
+    )
+  end
+
+  @spec read_json(String.t(), boolean(), keyword()) :: {:ok, t()} | {:error, term()}
+  @doc """
+  Read a json file holding an array of objects, or one object per line if `line_delimited_json`
+  is true. Any other top-level value is an error.
+
+  Parameters
+  ----------
+  opts
+      * `infer_schema_length` - number of rows the dtypes are inferred from, all rows if nil,
+        defaults to 100. Integers mixed with floats become "f64", other mixed values "str".
+        A key first seen after these rows is an error, as it has no column.
+      * `batch_size` - number of parsed rows converted to columns at once, defaults to 1024
+      * `schema` - a list of `{column, dtype}` as in `from_rows/2`; only these columns are
+        kept and nothing is inferred. "date32" and "date64" columns parse ISO 8601 strings.
+      * `nested` - "flatten" (the default) to turn nested objects into `parent.child`
+        columns, or "keep" to keep each nested object as a single column of json text. polars
+        has no struct dtype, so json text is the fallback for keeping the nesting; decode it
+        to get the objects back. Arrays become list columns.
+      * `compression` - as in `read_csv/13`, defaults to "infer"
+  """
+  def read_json(filename, line_delimited_json \\ false, opts \\ []) do
+    Native.df_read_json(
+      filename,
+      line_delimited_json,
+      Keyword.get(opts, :compression, "infer"),
+      Keyword.get(opts, :infer_schema_length, 100),
+      Keyword.get(opts, :batch_size, 1024),
+      encode_schema(Keyword.get(opts, :schema)),
+      Keyword.get(opts, :nested, "flatten")
+    )
+  end
+
+  @spec read_json_binary(binary(), boolean(), keyword()) :: {:ok, t()} | {:error, term()}
+  @doc """
+  Same as `read_json/3`, but parses json from a binary. `compression` isn't supported.
+  """
+  def read_json_binary(data, line_delimited_json \\ false, opts \\ []) do
+    Native.df_read_json_binary(
+      data,
+      line_delimited_json,
+      Keyword.get(opts, :infer_schema_length, 100),
+      Keyword.get(opts, :batch_size, 1024),
+      encode_schema(Keyword.get(opts, :schema)),
+      Keyword.get(opts, :nested, "flatten")
+    )
+  end
+
+  @spec read_ipc(String.t(), list(integer()) | nil) :: {:ok, t()} | {:error, term()}
+  @doc """
+  Read an Arrow IPC (Feather v2) file, optionally keeping only the columns at `projection`.
+  """
+  defdelegate read_ipc(filename, projection \\ nil), to: Native, as: :df_read_ipc
+
+  @spec read_ipc_binary(binary(), list(integer()) | nil) :: {:ok, t()} | {:error, term()}
+  @doc """
+  Same as `read_ipc/2`, but reads from an in-memory binary.
+  """
+  defdelegate read_ipc_binary(data, projection \\ nil), to: Native, as: :df_read_ipc_binary
 
   @spec to_csv(t() | {:ok, t()}, integer(), boolean(), integer()) ::
           {:ok, String.t()} | {:error, term()}
@@ -54,17 +265,180 @@ defmodule ExPolars.DataFrame do
 
   defdelegate to_csv(df, batch_size, has_headers, delimiter), to: Native, as: :df_to_csv
 
-  @spec to_csv_file(t() | {:ok, t()}, String.t(), integer(), boolean(), integer()) ::
+  @spec to_csv_file(t() | {:ok, t()}, String.t(), integer(), boolean(), integer(), String.t()) ::
           {:ok, {}} | {:error, term()}
-  def to_csv_file(df, filename, batch_size \\ 100_000, has_headers \\ true, delimiter \\ ?,)
-
-  def to_csv_file({:ok, df}, filename, batch_size, has_headers, delimiter),
-    do: to_csv_file(df, filename, batch_size, has_headers, delimiter)
+  @doc """
+  Write DataFrame as CSV to `filename`.
 
-  defdelegate to_csv_file(df, filename, batch_size, has_headers, delimiter),
+  Parameters
+  ----------
+  compression
+      "gzip", "zstd" or "none", or "infer" (the default) to compress according to a
+      `.gz`/`.zst` extension of `filename`.
+  """
+  def to_csv_file(
+        df,
+        filename,
+        batch_size \\ 100_000,
+        has_headers \\ true,
+        delimiter \\ ?,,
+        compression \\ "infer"
+      )
+
+  def to_csv_file({:ok, df}, filename, batch_size, has_headers, delimiter, compression),
+    do: to_csv_file(df, filename, batch_size, has_headers, delimiter, compression)
+
+  defdelegate to_csv_file(df, filename, batch_size, has_headers, delimiter, compression),
     to: Native,
     as: :df_to_csv_file
 
+  @spec to_json(t() | {:ok, t()}, String.t()) :: {:ok, String.t()} | {:error, term()}
+  @doc """
+  Serialize DataFrame as json. Nulls become `null`, dates and times become ISO 8601 strings
+  and list columns become nested arrays.
+
+  Parameters
+  ----------
+  orient
+      "records" for an array of row objects, "columns" for an object of column arrays, or
+      "ndjson" for one row object per line.
+  """
+  def to_json(df, orient \\ "records")
+  def to_json({:ok, df}, orient), do: to_json(df, orient)
+  defdelegate to_json(df, orient), to: Native, as: :df_to_json
+
+  @spec to_json_file(t() | {:ok, t()}, String.t(), String.t(), String.t()) ::
+          {:ok, {}} | {:error, term()}
+  @doc """
+  Write DataFrame as json to `filename`, e.g. `to_json_file(df, "out.jsonl.zst", "ndjson")`.
+  `orient` is as in `to_json/2` and `compression` as in `to_csv_file/6`.
+  """
+  def to_json_file(df, filename, orient \\ "records", compression \\ "infer")
+
+  def to_json_file({:ok, df}, filename, orient, compression),
+    do: to_json_file(df, filename, orient, compression)
+
+  defdelegate to_json_file(df, filename, orient, compression),
+    to: Native,
+    as: :df_to_json_file
+
+  @spec to_rows(t() | {:ok, t()}) :: {:ok, list(map())} | {:error, term()}
+  @doc """
+  Convert DataFrame to a list of maps, one per row, keyed by column name.
+  Values are converted as in `ExPolars.Series.to_list/1`.
+  """
+  def to_rows({:ok, df}), do: to_rows(df)
+  defdelegate to_rows(df), to: Native, as: :df_to_rows
+
+  @spec to_columns_map(t() | {:ok, t()}) :: {:ok, map()} | {:error, term()}
+  @doc """
+  Convert DataFrame to a map of column name to a list of values.
+  Values are converted as in `ExPolars.Series.to_list/1`.
+  """
+  def to_columns_map({:ok, df}), do: to_columns_map(df)
+  defdelegate to_columns_map(df), to: Native, as: :df_to_columns_map
+
+  @spec to_parquet(t() | {:ok, t()}, String.t(), integer(), boolean()) ::
+          {:ok, binary()} | {:error, term()}
+  @doc """
+  Write DataFrame as parquet into a binary.
+
+  Parameters
+  ----------
+  compression
+      One of "snappy", "gzip", "zstd", "lz4" or "uncompressed".
+  row_group_size
+      Max number of rows in a row group.
+  statistics
+      Write column statistics.
+  """
+  def to_parquet(df, compression \\ "snappy", row_group_size \\ 1_000_000, statistics \\ true)
+
+  def to_parquet({:ok, df}, compression, row_group_size, statistics),
+    do: to_parquet(df, compression, row_group_size, statistics)
+
+  defdelegate to_parquet(df, compression, row_group_size, statistics),
+    to: Native,
+    as: :df_to_parquet
+
+  @spec to_parquet_file(t() | {:ok, t()}, String.t(), String.t(), integer(), boolean()) ::
+          {:ok, {}} | {:error, term()}
+  @doc """
+  Write DataFrame to a parquet file. See `to_parquet/4` for the options.
+  """
+  def to_parquet_file(
+        df,
+        filename,
+        compression \\ "snappy",
+        row_group_size \\ 1_000_000,
+        statistics \\ true
+      )
+
+  def to_parquet_file({:ok, df}, filename, compression, row_group_size, statistics),
+    do: to_parquet_file(df, filename, compression, row_group_size, statistics)
+
+  defdelegate to_parquet_file(df, filename, compression, row_group_size, statistics),
+    to: Native,
+    as: :df_to_parquet_file
+
+  @spec to_ipc(t() | {:ok, t()}) :: {:ok, binary()} | {:error, term()}
+  @doc """
+  Write DataFrame in Arrow IPC (Feather v2) file format into a binary.
+  """
+  def to_ipc({:ok, df}), do: to_ipc(df)
+  defdelegate to_ipc(df), to: Native, as: :df_to_ipc
+
+  @spec to_ipc_file(t() | {:ok, t()}, String.t()) :: {:ok, {}} | {:error, term()}
+  def to_ipc_file({:ok, df}, filename), do: to_ipc_file(df, filename)
+  defdelegate to_ipc_file(df, filename), to: Native, as: :df_to_ipc_file
+
+  @spec to_binary(t() | {:ok, t()}) :: {:ok, binary()} | {:error, term()}
+  @doc """
+  Serialize DataFrame as an Arrow IPC stream. Unlike the DataFrame itself, the binary can be
+  sent to other nodes or stored in ETS/Mnesia. Use `from_binary/1` to get the DataFrame back.
+  """
+  def to_binary({:ok, df}), do: to_binary(df)
+  defdelegate to_binary(df), to: Native, as: :df_to_binary
+
+  @spec from_binary(binary()) :: {:ok, t()} | {:error, term()}
+  defdelegate from_binary(data), to: Native, as: :df_from_binary
+
+  @spec from_rows(list(map()), list({String.t() | atom(), String.t() | integer()}) | nil) ::
+          {:ok, t()} | {:error, term()}
+  @doc """
+  Build a DataFrame from a list of maps with atom or string keys, e.g. the result of an Ecto
+  query. Missing keys are null, and the `__struct__` and Ecto `__meta__` fields of structs are
+  skipped. Without a schema, columns come in the order their keys are first seen when iterating
+  the maps, which is the map's term order rather than the order the keys were written in.
+
+  Parameters
+  ----------
+  schema
+      A list of `{column, dtype}` where dtype is a name like "i64" or a dtype id. Only these
+      columns are kept, in this order. When nil, dtypes are inferred: integers become "i64",
+      floats "f64", binaries "str", booleans "bool", `Date` "date32", `NaiveDateTime` "date64"
+      and `Time` "time64_nanosecond". Integers mixed with floats become "f64".
+  """
+  def from_rows(rows, schema \\ nil), do: Native.df_from_rows(rows, encode_schema(schema))
+
+  @spec from_columns(map(), list({String.t() | atom(), String.t() | integer()}) | nil) ::
+          {:ok, t()} | {:error, term()}
+  @doc """
+  Build a DataFrame from a map of column name to a list of values. See `from_rows/2` for the
+  schema and dtype inference.
+  """
+  def from_columns(data, schema \\ nil),
+    do: Native.df_from_columns(data, encode_schema(schema))
+
+  defp encode_schema(nil), do: nil
+
+  defp encode_schema(schema) do
+    Enum.map(schema, fn
+      {name, dtype} when is_integer(dtype) -> {to_string(name), dtype}
+      {name, dtype} -> {to_string(name), S.dtype_id(dtype)}
+    end)
+  end
+
   # defdelegate as_str(df), to: Native, as: :df_as_str
 
   @spec add(t() | {:ok, t()}, s() | {:ok, s()}) :: {:ok, t()} | {:error, term()}
@@ -105,10 +479,89 @@ defmodule ExPolars.DataFrame do
   def fill_none({:ok, df}, strategy), do: fill_none(df, strategy)
   defdelegate fill_none(df, strategy), to: Native, as: :df_fill_none
 
-  @spec join(t() | {:ok, t()}, t() | {:ok, t()}, list(String.t()), list(String.t()), String.t()) ::
+  @spec join(
+          t() | {:ok, t()},
+          t() | {:ok, t()},
+          list(String.t()),
+          list(String.t()),
+          String.t(),
+          String.t()
+        ) :: {:ok, t()} | {:error, term()}
+  @doc """
+  Join DataFrame with `other`.
+
+  Parameters
+  ----------
+  how
+      "left", "inner" or "outer"; "semi" keeps the rows of `df` that have a match in `other`,
+      "anti" the rows that don't, neither adds columns of `other`; "cross" combines every row
+      of `df` with every row of `other` and ignores `left_on` and `right_on`.
+  suffix
+      Appended to the non-key columns of `other` whose names also exist in `df`.
+  """
+  def join(df, other, left_on, right_on, how, suffix \\ "_right")
+
+  def join({:ok, df}, other, left_on, right_on, how, suffix),
+    do: join(df, other, left_on, right_on, how, suffix)
+
+  def join(df, {:ok, other}, left_on, right_on, how, suffix),
+    do: join(df, other, left_on, right_on, how, suffix)
+
+  def join(df, other, left_on, right_on, how, suffix),
+    do: Native.df_join(df, other, left_on, right_on, how, suffix)
+
+  @spec join_asof(t() | {:ok, t()}, t() | {:ok, t()}, String.t(), String.t(), keyword()) ::
           {:ok, t()} | {:error, term()}
-  def join({:ok, df}, other, left_on, right_on, how), do: join(df, other, left_on, right_on, how)
-  defdelegate join(df, other, left_on, right_on, how), to: Native, as: :df_join
+  @doc """
+  As-of join: join every row of DataFrame with the row of `other` whose key is the closest
+  to its own, rather than equal. `other` must be sorted ascending by `right_on`. Rows without
+  a match get nulls for the columns of `other`; the key of `other` is not kept.
+
+  Keys can be integer, float, date or time columns of the same dtype. Null keys never match,
+  and neither do NaN keys of DataFrame; `other` can't have NaN keys.
+
+  Parameters
+  ----------
+  opts
+      * `strategy` - "backward" (default) takes the last row of `other` with a key less than
+        or equal to the key, "forward" the first row with a key greater than or equal to it,
+        "nearest" the closest of both.
+      * `tolerance` - only match rows whose key is at most this far from the key, in the
+        unit of the key column (e.g. days for dates, milliseconds for datetimes). Must not be
+        negative, and must be an integer unless the keys are floats.
+      * `suffix` - appended to the columns of `other` whose names also exist in `df`,
+        defaults to "_right".
+
+  Example
+  -------
+
+      DataFrame.join_asof(trades, quotes, "time", "time", strategy: "backward", tolerance: 100)
+  """
+  def join_asof(df, other, left_on, right_on, opts \\ [])
+
+  def join_asof({:ok, df}, other, left_on, right_on, opts),
+    do: join_asof(df, other, left_on, right_on, opts)
+
+  def join_asof(df, {:ok, other}, left_on, right_on, opts),
+    do: join_asof(df, other, left_on, right_on, opts)
+
+  def join_asof(df, other, left_on, right_on, opts) do
+    tolerance =
+      case Keyword.get(opts, :tolerance) do
+        nil -> nil
+        tolerance -> tolerance / 1
+      end
+
+    Native.df_join_asof(
+      df,
+      other,
+      left_on,
+      right_on,
+      Keyword.get(opts, :strategy, "backward"),
+      tolerance,
+      Keyword.get(opts, :suffix, "_right")
+    )
+  end
 
   @spec get_columns(t() | {:ok, t()}) :: {:ok, list(s() | {:ok, s()})} | {:error, term()}
   def get_columns({:ok, df}), do: get_columns(df)
@@ -178,11 +631,17 @@ defmodule ExPolars.DataFrame do
   def column({:ok, df}, name), do: column(df, name)
   defdelegate column(df, name), to: Native, as: :df_column
 
-  @spec select(t() | {:ok, t()}, list(String.t())) :: {:ok, t()} | {:error, term()}
+  @spec select(t() | {:ok, t()}, list(String.t() | Expr.t())) :: {:ok, t()} | {:error, term()}
+  @doc """
+  Select columns by name, or compute new columns from expressions. See `ExPolars.Expr`.
+  """
   def select({:ok, df}, selection), do: select(df, selection)
   defdelegate select(df, selection), to: Native, as: :df_select
 
-  @spec filter(t() | {:ok, t()}, s() | {:ok, s()}) :: {:ok, t()} | {:error, term()}
+  @spec filter(t() | {:ok, t()}, s() | {:ok, s()} | Expr.t()) :: {:ok, t()} | {:error, term()}
+  @doc """
+  Filter rows by a boolean mask Series, or by a predicate expression. See `ExPolars.Expr`.
+  """
   def filter({:ok, df}, {:ok, mask}), do: filter(df, mask)
   def filter(df, {:ok, mask}), do: filter(df, mask)
   def filter({:ok, df}, mask), do: filter(df, mask)
@@ -271,7 +730,7 @@ defmodule ExPolars.DataFrame do
   @spec groupby_agg(
           t() | {:ok, t()},
           list(String.t()),
-          %{String.t() => list(String.t())} | list({String.t(), list(String.t())})
+          %{String.t() => list(String.t())} | list({String.t(), list(String.t())} | Expr.t())
         ) ::
           {:ok, t()} | {:error, term()}
   @doc """
@@ -289,6 +748,12 @@ defmodule ExPolars.DataFrame do
           {"foo": ["sum", "n_unique", "min"],
           "bar": "max"}
 
+      Aggregation expressions (see `ExPolars.Expr`) can be used as well:
+          [Expr.sum("foo") |> Expr.as("total"), {"bar", ["max"]}]
+
+      With expressions the result is sorted by the group keys, and the columns are in the
+      order of the items.
+
   Returns
   -------
   Result of groupby split apply operations.
@@ -301,6 +766,106 @@ defmodule ExPolars.DataFrame do
   def groupby_agg(df, by, column_to_agg) when is_list(column_to_agg),
     do: Native.df_groupby_agg(df, by, column_to_agg)
 
+  @spec groupby_exprs(
+          t() | {:ok, t()},
+          list(String.t()),
+          keyword(Expr.t()) | list({String.t(), Expr.t()}),
+          keyword()
+        ) :: {:ok, t()} | {:error, term()}
+  @doc """
+  Group by `by` and compute named aggregations. The result has the group keys followed by one
+  column per aggregation, named after its key. `by` must have at least one column.
+
+  Parameters
+  ----------
+  aggs
+      `{name, aggregation}` pairs where the aggregation is an `ExPolars.Expr`, e.g.
+
+          import Kernel,
+            except: [+: 2, -: 2, *: 2, /: 2, ==: 2, !=: 2, >: 2, >=: 2, <: 2, <=: 2, rem: 2]
+          import ExPolars.Expr
+
+          [
+            revenue: sum(col("price") * col("qty")),
+            orders: count(),
+            ok_orders: filter(count(), col("status") == "ok")
+          ]
+  opts
+      `sort: true` sorts the result by the group keys (ascending, nulls last), so the row
+      order is deterministic.
+  """
+  def groupby_exprs(df, by, aggs, opts \\ [])
+  def groupby_exprs({:ok, df}, by, aggs, opts), do: groupby_exprs(df, by, aggs, opts)
+
+  def groupby_exprs(_df, [], _aggs, _opts),
+    do: {:error, %ExPolars.Error{kind: :other, message: "groupby_exprs needs a key column"}}
+
+  def groupby_exprs(df, [key | _] = by, aggs, opts) do
+    aggs = Enum.map(aggs, fn {name, agg} -> {to_string(name), Expr.resolve_count(agg, key)} end)
+    Native.df_groupby_exprs(df, by, aggs, Keyword.get(opts, :sort, false))
+  end
+
+  @spec partition_by(t() | {:ok, t()}, list(String.t())) :: {:ok, list(t())} | {:error, term()}
+  @doc """
+  Split DataFrame into one DataFrame per group of `by`, in order of first appearance.
+  """
+  def partition_by({:ok, df}, by), do: partition_by(df, by)
+  defdelegate partition_by(df, by), to: Native, as: :df_partition_by
+
+  @spec concat(list(t())) :: {:ok, t()} | {:error, term()}
+  @doc """
+  Stack DataFrames with the same schema into a new DataFrame.
+  """
+  defdelegate concat(dfs), to: Native, as: :df_concat
+
+  @spec groupby_apply(t() | {:ok, t()}, list(String.t()), (t() -> t() | {:ok, t()})) ::
+          {:ok, t()} | {:error, term()}
+  @doc """
+  Call `fun` with the DataFrame of each group of `by` and concatenate the DataFrames it returns.
+  The callback runs in the calling process, one group at a time, in order of first appearance.
+  Returning `{:error, reason}` from `fun` stops the iteration and returns the error; returning
+  anything but a DataFrame, `{:ok, df}` or `{:error, reason}` is an `:invalid_argument` error.
+
+  All groups are split off with `partition_by/2` before `fun` is first called, so they take
+  about as much memory as `df` itself until the iteration is done. An empty `df` has no groups:
+  `fun` isn't called and `df` is returned as it is.
+
+      DF.groupby_apply(df, ["cohort"], fn group -> DF.head(group, 3) end)
+  """
+  def groupby_apply({:ok, df}, by, fun), do: groupby_apply(df, by, fun)
+
+  def groupby_apply(df, by, fun) do
+    case partition_by(df, by) do
+      {:ok, []} ->
+        {:ok, df}
+
+      {:ok, groups} ->
+        with {:ok, results} <- apply_groups(groups, fun, []), do: concat(results)
+
+      error ->
+        error
+    end
+  end
+
+  defp apply_groups([], _fun, acc), do: {:ok, Enum.reverse(acc)}
+
+  defp apply_groups([group | rest], fun, acc) do
+    case fun.(group) do
+      {:ok, %__MODULE__{} = df} -> apply_groups(rest, fun, [df | acc])
+      %__MODULE__{} = df -> apply_groups(rest, fun, [df | acc])
+      {:error, _} = error -> error
+      other -> {:error, invalid_group_result(other)}
+    end
+  end
+
+  defp invalid_group_result(result) do
+    %ExPolars.Error{
+      kind: :invalid_argument,
+      message: "groupby_apply callback returned #{inspect(result)}, expected a DataFrame",
+      details: result
+    }
+  end
+
   @spec groupby_quantile(t() | {:ok, t()}, list(String.t()), list(String.t()), float()) ::
           {:ok, t()} | {:error, term()}
   @doc """
@@ -452,6 +1017,80 @@ defmodule ExPolars.DataFrame do
     end
   end
 
+  @type sort_key ::
+          String.t()
+          | {String.t(), :asc | :desc}
+          | {String.t(), :asc | :desc, :nulls_first | :nulls_last}
+
+  @spec sort_by(t() | {:ok, t()}, list(sort_key()), keyword()) ::
+          {:ok, t()} | {:ok, {}} | {:error, term()}
+  @doc """
+  Sort DataFrame by several columns. The sort is stable: rows with equal keys keep their
+  original order.
+
+  Parameters
+  ----------
+  by
+      Columns to sort by, in order of precedence. Each is a column name (ascending), or
+      `{name, :asc | :desc}`, or `{name, :asc | :desc, :nulls_first | :nulls_last}`. Nulls go
+      last unless stated otherwise, whatever the direction.
+  opts
+      `inplace: true` sorts DataFrame in place.
+
+  Example
+  -------
+
+      DataFrame.sort_by(df, [{"region", :asc}, {"revenue", :desc, :nulls_last}])
+  """
+  def sort_by(df, by, opts \\ [])
+  def sort_by({:ok, df}, by, opts), do: sort_by(df, by, opts)
+
+  def sort_by(df, by, opts) do
+    {names, descending, nulls_last} = sort_keys(by)
+
+    case Keyword.get(opts, :inplace, false) do
+      true -> Native.df_sort_by_in_place(df, names, descending, nulls_last)
+      _ -> Native.df_sort_by(df, names, descending, nulls_last)
+    end
+  end
+
+  defp sort_keys(by) do
+    keys =
+      Enum.map(by, fn
+        {name, dir, nulls} -> {name, dir == :desc, nulls != :nulls_first}
+        {name, dir} -> {name, dir == :desc, true}
+        name -> {name, false, true}
+      end)
+
+    {Enum.map(keys, &elem(&1, 0)), Enum.map(keys, &elem(&1, 1)), Enum.map(keys, &elem(&1, 2))}
+  end
+
+  @spec top_k(t() | {:ok, t()}, non_neg_integer(), String.t() | list(String.t()), keyword()) ::
+          {:ok, t()} | {:error, term()}
+  @doc """
+  The `k` rows with the largest values of `by`, in descending order. Faster than sorting and
+  taking the head, since only the selected rows are sorted.
+
+  Parameters
+  ----------
+  by
+      A column name or a list of column names, later columns break ties of earlier ones.
+      Remaining ties keep the original row order. Rows with nulls come last.
+  opts
+      `bottom: true` takes the `k` smallest rows instead, in ascending order.
+  """
+  def top_k(df, k, by, opts \\ [])
+  def top_k({:ok, df}, k, by, opts), do: top_k(df, k, by, opts)
+  def top_k(df, k, by, opts) when is_binary(by), do: top_k(df, k, [by], opts)
+  def top_k(df, k, by, opts), do: Native.df_top_k(df, k, by, Keyword.get(opts, :bottom, false))
+
+  @spec lazy(t() | {:ok, t()}) :: {:ok, LazyFrame.t()} | {:error, term()}
+  @doc """
+  Start a lazy query on this DataFrame. See `ExPolars.LazyFrame`.
+  """
+  def lazy({:ok, df}), do: lazy(df)
+  defdelegate lazy(df), to: Native, as: :lf_from_df
+
   # custom functionalities
   @spec dtype(t() | {:ok, t()}, String.t()) :: String.t() | {:error, term()}
   def dtype(df, name, type \\ :str) do
@@ -0,0 +1,39 @@
+defmodule ExPolars.Error do
+  @moduledoc """
+  Error returned by the NIFs as `{:error, %ExPolars.Error{}}`.
+
+  Fields
+  ------
+  kind
+      What went wrong, so callers can pattern match on it:
+
+      * `:io` - reading or writing a file failed, `details` is the reason, e.g. `:not_found`
+        or `:permission_denied`
+      * polars errors: `:not_found`, `:shape_mismatch`, `:data_type_mismatch`,
+        `:invalid_operation`, `:invalid_value`, `:out_of_bounds`, `:no_data`,
+        `:has_null_values`, `:duplicate`, `:unknown_schema`, `:no_slice`, `:regex`,
+        `:memory_not_aligned`, `:rand` and `:polars_other`; the arrow, parquet and io errors
+        polars wraps have the kinds below
+      * `:schema_mismatch` - a batch doesn't match the schema of an open writer
+      * `:arrow`, `:parquet`, `:json` and `:utf8` - errors from the underlying libraries
+      * `:other` - invalid arguments, e.g. an unknown option value
+      * `:invalid_argument` - a callback returned a value of the wrong type, `details` is the
+        value
+      * `:internal` and `:unknown` - anything else
+  message
+      A human readable message.
+  details
+      The io error reason for `:io` errors, otherwise the debug representation of the error.
+
+  Example
+  -------
+
+      case DataFrame.read_csv("missing.csv") do
+        {:ok, df} -> df
+        {:error, %ExPolars.Error{kind: :io, details: :not_found}} -> DataFrame.from_rows([])
+      end
+  """
+  defexception [:kind, :message, :details]
+
+  @type t :: %__MODULE__{kind: atom(), message: String.t(), details: term()}
+end
@@ -0,0 +1,161 @@
+defmodule ExPolars.Expr do
+  @moduledoc """
+  Build polars column expressions.
+
+  Expressions are plain nested tuples which are decoded into polars expressions on the rust side,
+  so a whole pipeline is sent to the NIF in one call:
+
+      import Kernel,
+        except: [+: 2, -: 2, *: 2, /: 2, ==: 2, !=: 2, >: 2, >=: 2, <: 2, <=: 2, rem: 2]
+      import ExPolars.Expr
+
+      DF.filter(df, and_(col("price") > 10, col("qty") != 0))
+
+  The operators only build expressions when one of the operands is an expression; otherwise
+  they fall back to `Kernel`.
+  """
+  import Kernel,
+    except: [+: 2, -: 2, *: 2, /: 2, ==: 2, !=: 2, >: 2, >=: 2, <: 2, <=: 2, rem: 2]
+
+  alias ExPolars.Series, as: S
+
+  @type t :: tuple()
+
+  @tags [
+    :col,
+    :lit,
+    :binary_expr,
+    :not,
+    :is_null,
+    :is_not_null,
+    :agg,
+    :quantile,
+    :when,
+    :cast,
+    :alias,
+    :count_rows
+  ]
+
+  defguard is_expr(e)
+           when is_tuple(e) and Kernel.>(tuple_size(e), 1) and elem(e, 0) in @tags
+
+  @spec col(String.t()) :: t()
+  def col(name) when is_binary(name), do: {:col, name}
+
+  @spec lit(integer() | float() | boolean() | String.t() | nil) :: t()
+  def lit(value), do: {:lit, value}
+
+  @spec as(t(), String.t()) :: t()
+  @doc """
+  Rename the output of an expression.
+  """
+  def as(expr, name), do: {:alias, expr, name}
+
+  @spec cast(t(), String.t() | integer()) :: t()
+  @doc """
+  Cast an expression to another dtype, given by name (e.g. "f64") or by dtype id.
+  """
+  def cast(expr, dtype) when is_binary(dtype), do: cast(expr, S.dtype_id(dtype))
+  def cast(expr, dtype) when is_integer(dtype), do: {:cast, expr, dtype}
+
+  @spec when_then(t(), t() | term(), t() | term()) :: t()
+  @doc """
+  `when predicate then ... otherwise ...`
+  """
+  def when_then(predicate, then, otherwise),
+    do: {:when, predicate, to_expr(then), to_expr(otherwise)}
+
+  @spec not_(t()) :: t()
+  def not_(expr), do: {:not, expr}
+
+  @spec and_(t(), t()) :: t()
+  def and_(left, right), do: binary_expr(:and, left, right)
+
+  @spec or_(t(), t()) :: t()
+  def or_(left, right), do: binary_expr(:or, left, right)
+
+  @spec is_null(t()) :: t()
+  def is_null(expr), do: {:is_null, expr}
+
+  @spec is_not_null(t()) :: t()
+  def is_not_null(expr), do: {:is_not_null, expr}
+
+  # aggregations
+  for agg <- [:min, :max, :sum, :mean, :median, :first, :last, :count, :n_unique, :list] do
+    @spec unquote(agg)(t() | String.t()) :: t()
+    def unquote(agg)(expr), do: {:agg, unquote(agg), to_col(expr)}
+  end
+
+  @spec quantile(t() | String.t(), float()) :: t()
+  def quantile(expr, quant), do: {:quantile, to_col(expr), quant}
+
+  @spec count() :: t()
+  @doc """
+  Number of rows in each group. Only valid in `ExPolars.DataFrame.groupby_exprs/4`.
+  """
+  def count, do: {:count_rows, nil}
+
+  @spec filter(t(), t()) :: t()
+  @doc """
+  Only aggregate the values for which `predicate` holds, like SQL's `FILTER (WHERE ...)`:
+
+      filter(count(), col("status") == "ok")
+      filter(sum("amount"), col("amount") > 0)
+
+  Supported for `count`, `sum`, `mean`, `min`, `max`, `median` and `quantile`. As without a
+  filter, `count(expr)` counts rows, nulls in `expr` included, so it's the same as `count()`.
+  """
+  def filter({:count_rows, nil}, predicate), do: {:agg, :sum, cast(predicate, "u32")}
+
+  def filter({:agg, :count, _expr}, predicate), do: filter(count(), predicate)
+
+  def filter({:agg, agg, expr}, predicate) when agg in [:sum, :mean, :min, :max, :median],
+    do: {:agg, agg, when_then(predicate, expr, nil)}
+
+  def filter({:quantile, expr, quant}, predicate),
+    do: {:quantile, when_then(predicate, expr, nil), quant}
+
+  def filter(agg, _predicate), do: raise(ArgumentError, "cannot filter #{inspect(agg)}")
+
+  @doc false
+  # replace `count()` by a count of the given key column
+  def resolve_count({:count_rows, nil}, key), do: {:agg, :count, key}
+
+  def resolve_count(expr, key) when is_tuple(expr),
+    do: expr |> Tuple.to_list() |> Enum.map(&resolve_count(&1, key)) |> List.to_tuple()
+
+  def resolve_count(value, _key), do: value
+
+  # operators
+  for {op, name} <- [+: :plus, -: :minus, *: :multiply, /: :divide] do
+    @spec unquote(op)(t() | term(), t() | term()) :: t() | term()
+    def unquote(op)(left, right) when is_expr(left) or is_expr(right),
+      do: binary_expr(unquote(name), left, right)
+
+    def unquote(op)(left, right), do: Kernel.unquote(op)(left, right)
+  end
+
+  for {op, name} <- [==: :eq, !=: :neq, >: :gt, >=: :gt_eq, <: :lt, <=: :lt_eq] do
+    @spec unquote(op)(t() | term(), t() | term()) :: t() | boolean()
+    def unquote(op)(left, right) when is_expr(left) or is_expr(right),
+      do: binary_expr(unquote(name), left, right)
+
+    def unquote(op)(left, right), do: Kernel.unquote(op)(left, right)
+  end
+
+  @spec rem(t(), t() | term()) :: t()
+  def rem(left, right), do: binary_expr(:modulus, left, right)
+
+  for {fun, name} <- [eq: :eq, neq: :neq, gt: :gt, gt_eq: :gt_eq, lt: :lt, lt_eq: :lt_eq] do
+    @spec unquote(fun)(t(), t() | term()) :: t()
+    def unquote(fun)(left, right), do: binary_expr(unquote(name), left, right)
+  end
+
+  defp binary_expr(op, left, right), do: {:binary_expr, op, to_expr(left), to_expr(right)}
+
+  defp to_expr(e) when is_expr(e), do: e
+  defp to_expr(v), do: lit(v)
+
+  defp to_col(name) when is_binary(name), do: col(name)
+  defp to_col(e), do: e
+end
@@ -0,0 +1,135 @@
+defmodule ExPolars.LazyFrame do
+  @moduledoc """
+  A lazy query plan on top of a DataFrame or a scanned file.
+
+  Every step only extends the plan; nothing is computed until `collect/1` is called,
+  which lets polars push predicates and projections down to the scan.
+
+  Expressions are passed as nested tuples, e.g.
+  `{:binary_expr, :gt, {:col, "price"}, {:lit, 10}}`. A plain string is treated as a column name.
+  Use `ExPolars.Expr` to build them.
+  """
+
+  alias ExPolars.Native
+
+  defstruct [:inner]
+
+  @type t :: ExPolars.LazyFrame
+  @type df :: ExPolars.DataFrame
+  @type expr :: tuple() | String.t()
+
+  @spec from_df(df() | {:ok, df()}) :: {:ok, t()} | {:error, term()}
+  def from_df({:ok, df}), do: from_df(df)
+  defdelegate from_df(df), to: Native, as: :lf_from_df
+
+  @spec scan_csv(
+          String.t(),
+          String.t(),
+          boolean(),
+          boolean(),
+          integer(),
+          integer() | nil,
+          boolean()
+        ) :: {:ok, t()} | {:error, term()}
+  defdelegate scan_csv(
+                filename,
+                sep \\ ",",
+                has_header \\ true,
+                ignore_errors \\ false,
+                skip_rows \\ 0,
+                stop_after_n_rows \\ nil,
+                cache \\ true
+              ),
+              to: Native,
+              as: :lf_scan_csv
+
+  @spec scan_parquet(String.t(), integer() | nil, boolean()) :: {:ok, t()} | {:error, term()}
+  defdelegate scan_parquet(filename, stop_after_n_rows \\ nil, cache \\ true),
+    to: Native,
+    as: :lf_scan_parquet
+
+  @spec describe_plan(t() | {:ok, t()}) :: {:ok, String.t()} | {:error, term()}
+  def describe_plan({:ok, ldf}), do: describe_plan(ldf)
+  defdelegate describe_plan(ldf), to: Native, as: :lf_describe_plan
+
+  @spec describe_optimized_plan(t() | {:ok, t()}) :: {:ok, String.t()} | {:error, term()}
+  def describe_optimized_plan({:ok, ldf}), do: describe_optimized_plan(ldf)
+  defdelegate describe_optimized_plan(ldf), to: Native, as: :lf_describe_optimized_plan
+
+  @spec filter(t() | {:ok, t()}, expr()) :: {:ok, t()} | {:error, term()}
+  def filter({:ok, ldf}, predicate), do: filter(ldf, predicate)
+  defdelegate filter(ldf, predicate), to: Native, as: :lf_filter
+
+  @spec select(t() | {:ok, t()}, list(expr())) :: {:ok, t()} | {:error, term()}
+  def select({:ok, ldf}, exprs), do: select(ldf, exprs)
+  defdelegate select(ldf, exprs), to: Native, as: :lf_select
+
+  @spec with_column(t() | {:ok, t()}, expr()) :: {:ok, t()} | {:error, term()}
+  def with_column({:ok, ldf}, expr), do: with_column(ldf, expr)
+  defdelegate with_column(ldf, expr), to: Native, as: :lf_with_column
+
+  @spec with_columns(t() | {:ok, t()}, list(expr())) :: {:ok, t()} | {:error, term()}
+  def with_columns({:ok, ldf}, exprs), do: with_columns(ldf, exprs)
+  defdelegate with_columns(ldf, exprs), to: Native, as: :lf_with_columns
+
+  @spec groupby_agg(t() | {:ok, t()}, list(String.t()), list(expr())) ::
+          {:ok, t()} | {:error, term()}
+  @doc """
+  Group by the `by` columns and compute the `aggs` expressions per group.
+
+  Examples:
+      groupby_agg(ldf, ["region"], [{:agg, :sum, {:col, "revenue"}}])
+  """
+  def groupby_agg({:ok, ldf}, by, aggs), do: groupby_agg(ldf, by, aggs)
+  defdelegate groupby_agg(ldf, by, aggs), to: Native, as: :lf_groupby_agg
+
+  @spec join(t() | {:ok, t()}, t() | {:ok, t()}, expr(), expr(), String.t()) ::
+          {:ok, t()} | {:error, term()}
+  @doc """
+  Join on one key expression per side. `how` is "left", "inner" or "outer"; other join types
+  return an error.
+  """
+  def join(ldf, other, left_on, right_on, how \\ "inner")
+  def join({:ok, ldf}, other, left_on, right_on, how),
+    do: join(ldf, other, left_on, right_on, how)
+
+  def join(ldf, {:ok, other}, left_on, right_on, how),
+    do: join(ldf, other, left_on, right_on, how)
+
+  defdelegate join(ldf, other, left_on, right_on, how), to: Native, as: :lf_join
+
+  @spec sort(t() | {:ok, t()}, String.t(), boolean()) :: {:ok, t()} | {:error, term()}
+  @doc """
+  Sort by a single column. To sort by several columns, collect and use
+  `ExPolars.DataFrame.sort_by/3`.
+  """
+  def sort(ldf, by_column, reverse \\ false)
+  def sort({:ok, ldf}, by_column, reverse), do: sort(ldf, by_column, reverse)
+  defdelegate sort(ldf, by_column, reverse), to: Native, as: :lf_sort
+
+  @spec limit(t() | {:ok, t()}, integer()) :: {:ok, t()} | {:error, term()}
+  def limit({:ok, ldf}, n), do: limit(ldf, n)
+  defdelegate limit(ldf, n), to: Native, as: :lf_limit
+
+  @spec cache(t() | {:ok, t()}) :: {:ok, t()} | {:error, term()}
+  def cache({:ok, ldf}), do: cache(ldf)
+  defdelegate cache(ldf), to: Native, as: :lf_cache
+
+  @spec collect(t() | {:ok, t()}) :: {:ok, df()} | {:error, term()}
+  @doc """
+  Execute the (optimized) plan and materialize the result as a DataFrame.
+  """
+  def collect({:ok, ldf}), do: collect(ldf)
+  defdelegate collect(ldf), to: Native, as: :lf_collect
+end
+
+defimpl Inspect, for: ExPolars.LazyFrame do
+  alias ExPolars.Native
+
+  def inspect(data, _opts) do
+    case Native.lf_describe_plan(data) do
+      {:ok, s} -> s
+      _ -> "Cannot output lazy frame"
+    end
+  end
+end
@@ -19,12 +19,94 @@ defmodule ExPolars.Native do
         _sep \\ ",",
         _rechunk \\ true,
         _columns \\ nil,
+        _encoding \\ "utf8",
+        _compression \\ "infer"
+      ),
+      do: err()
+
+  def df_read_csv_binary(
+        _data,
+        _infer_schema_length \\ 100,
+        _batch_size \\ 64,
+        _has_header \\ true,
+        _ignore_errors \\ false,
+        _stop_after_n_rows \\ nil,
+        _skip_rows \\ 0,
+        _projection \\ nil,
+        _sep \\ ",",
+        _rechunk \\ true,
+        _columns \\ nil,
         _encoding \\ "utf8"
       ),
       do: err()
 
+  def df_read_csv_batched(
+        _filename,
+        _infer_schema_length \\ 100,
+        _batch_size \\ 64,
+        _has_header \\ true,
+        _ignore_errors \\ false,
+        _skip_rows \\ 0,
+        _projection \\ nil,
+        _sep \\ ",",
+        _columns \\ nil,
+        _encoding \\ "utf8",
+        _compression \\ "infer"
+      ),
+      do: err()
+
+  def df_next_batch(_reader, _n_rows), do: err()
+
   def df_read_parquet(_filename), do: err()
-  def df_read_json(_filename, _type), do: err()
+  def df_read_parquet_binary(_data), do: err()
+  def df_scan_csv(
+        _filename,
+        _columns \\ nil,
+        _predicate \\ nil,
+        _sep \\ ",",
+        _has_header \\ true,
+        _ignore_errors \\ false,
+        _skip_rows \\ 0,
+        _stop_after_n_rows \\ nil
+      ),
+      do: err()
+
+  def df_scan_parquet(_filename, _columns \\ nil, _predicate \\ nil), do: err()
+  def df_read_dataset(_root, _columns \\ nil, _predicate \\ nil), do: err()
+
+  def df_write_dataset(
+        _df,
+        _root,
+        _by,
+        _format \\ "parquet",
+        _compression \\ "snappy",
+        _mode \\ "error"
+      ),
+      do: err()
+
+  def df_read_json(
+        _filename,
+        _type,
+        _compression \\ "infer",
+        _infer_schema_length \\ 100,
+        _batch_size \\ 1024,
+        _schema \\ nil,
+        _nested \\ "flatten"
+      ),
+      do: err()
+
+  def df_read_json_binary(
+        _data,
+        _type,
+        _infer_schema_length \\ 100,
+        _batch_size \\ 1024,
+        _schema \\ nil,
+        _nested \\ "flatten"
+      ),
+      do: err()
+
+  def df_read_ipc(_filename, _projection \\ nil), do: err()
+  def df_read_ipc_binary(_data, _projection \\ nil), do: err()
 
   def df_to_csv(_df, _batch_size \\ 100_000, _has_headers \\ true, _delimiter \\ ?,),
     do: err()
@@ -34,10 +116,56 @@ defmodule ExPolars.Native do
         _filename,
         _batch_size \\ 100_000,
         _has_headers \\ true,
-        _delimiter \\ ?,
+        _delimiter \\ ?,,
+        _compression \\ "infer"
+      ),
+      do: err()
+
+  def df_to_json(_df, _orient \\ "records"), do: err()
+
+  def df_to_json_file(_df, _filename, _orient \\ "records", _compression \\ "infer"),
+    do: err()
+
+  def df_to_rows(_df), do: err()
+  def df_to_columns_map(_df), do: err()
+
+  def df_to_parquet(
+        _df,
+        _compression \\ "snappy",
+        _row_group_size \\ 1_000_000,
+        _statistics \\ true
       ),
       do: err()
 
+  def df_to_parquet_file(
+        _df,
+        _filename,
+        _compression \\ "snappy",
+        _row_group_size \\ 1_000_000,
+        _statistics \\ true
+      ),
+      do: err()
+
+  def df_csv_writer(
+        _filename,
+        _batch_size \\ 100_000,
+        _has_headers \\ true,
+        _delimiter \\ ?,,
+        _compression \\ "infer"
+      ),
+      do: err()
+
+  def df_parquet_writer(_filename, _compression \\ "snappy", _statistics \\ true), do: err()
+  def df_write_batch(_writer, _df), do: err()
+  def df_close_writer(_writer), do: err()
+
+  def df_to_ipc(_df), do: err()
+  def df_to_ipc_file(_df, _filename), do: err()
+  def df_to_binary(_df), do: err()
+  def df_from_binary(_data), do: err()
+  def df_from_rows(_rows, _schema \\ nil), do: err()
+  def df_from_columns(_data, _schema \\ nil), do: err()
+
   def df_as_str(_df), do: err()
 
   def df_add(_df, _s), do: err()
@@ -49,7 +177,19 @@ defmodule ExPolars.Native do
   def df_sample_frac(_df, _f, _with_replacement), do: err()
   def df_rechunk(_df), do: err()
   def df_fill_none(_df, _strategy), do: err()
-  def df_join(_df, _other, _left_on, _right_on, _how), do: err()
+  def df_join(_df, _other, _left_on, _right_on, _how, _suffix \\ "_right"), do: err()
+
+  def df_join_asof(
+        _df,
+        _other,
+        _left_on,
+        _right_on,
+        _strategy \\ "backward",
+        _tolerance \\ nil,
+        _suffix \\ "_right"
+      ),
+      do: err()
+
   def df_get_columns(_df), do: err()
   def df_columns(_def), do: err()
   def df_set_column_names(_df, _names), do: err()
@@ -73,6 +213,9 @@ defmodule ExPolars.Native do
   def df_take_with_series(_df, _indices), do: err()
   def df_sort_new(_df, _by_column, _reverse), do: err()
   def df_sort_in_place(_df, _by_column, _reverse), do: err()
+  def df_sort_by(_df, _by, _descending, _nulls_last), do: err()
+  def df_sort_by_in_place(_df, _by, _descending, _nulls_last), do: err()
+  def df_top_k(_df, _k, _by, _bottom \\ false), do: err()
   def df_replace(_df, _col, _new_col), do: err()
   def df_replace_at_idx(_df, _index, _new_col), do: err()
   def df_insert_at_idx(_df, _index, _new_col), do: err()
@@ -84,6 +227,9 @@ defmodule ExPolars.Native do
   def df_frame_equal(_df, _other, _null_equal \\ false), do: err()
   def df_groupby(_df, _by, _sel, _agg), do: err()
   def df_groupby_agg(_df, _by, _column_to_agg), do: err()
+  def df_groupby_exprs(_df, _by, _aggs, _sort \\ false), do: err()
+  def df_partition_by(_df, _by), do: err()
+  def df_concat(_dfs), do: err()
   def df_groupby_quantile(_df, _by, _sel, _quant), do: err()
   def df_pivot(_df, _by, _pivot_column, _values_column, _agg), do: err()
   def df_clone(_df), do: err()
@@ -101,6 +247,34 @@ defmodule ExPolars.Native do
   def df_quantile(_df, _quant), do: err()
   def df_to_dummies(_df), do: err()
 
+  # LazyFrame
+  def lf_from_df(_df), do: err()
+
+  def lf_scan_csv(
+        _filename,
+        _sep \\ ",",
+        _has_header \\ true,
+        _ignore_errors \\ false,
+        _skip_rows \\ 0,
+        _stop_after_n_rows \\ nil,
+        _cache \\ true
+      ),
+      do: err()
+
+  def lf_scan_parquet(_filename, _stop_after_n_rows \\ nil, _cache \\ true), do: err()
+  def lf_describe_plan(_ldf), do: err()
+  def lf_describe_optimized_plan(_ldf), do: err()
+  def lf_filter(_ldf, _predicate), do: err()
+  def lf_select(_ldf, _exprs), do: err()
+  def lf_with_column(_ldf, _expr), do: err()
+  def lf_with_columns(_ldf, _exprs), do: err()
+  def lf_groupby_agg(_ldf, _by, _aggs), do: err()
+  def lf_join(_ldf, _other, _left_on, _right_on, _how), do: err()
+  def lf_sort(_ldf, _by_column, _reverse), do: err()
+  def lf_limit(_ldf, _n), do: err()
+  def lf_cache(_ldf), do: err()
+  def lf_collect(_ldf), do: err()
+
   # Series
   def s_as_str(_s), do: err()
   def s_new_i8(_name, _val), do: err()
@@ -119,6 +293,23 @@ defmodule ExPolars.Native do
   def s_new_f64(_name, _val), do: err()
   def s_parse_date32_from_str_slice(_name, _val, _fmt), do: err()
   def s_new_str(_name, _val), do: err()
+  def s_new_opt_i8(_name, _val), do: err()
+  def s_new_opt_i16(_name, _val), do: err()
+  def s_new_opt_i32(_name, _val), do: err()
+  def s_new_opt_i64(_name, _val), do: err()
+  def s_new_opt_bool(_name, _val), do: err()
+  def s_new_opt_u8(_name, _val), do: err()
+  def s_new_opt_u16(_name, _val), do: err()
+  def s_new_opt_u32(_name, _val), do: err()
+  def s_new_opt_u64(_name, _val), do: err()
+  def s_new_opt_date32(_name, _val), do: err()
+  def s_new_opt_date64(_name, _val), do: err()
+  def s_new_opt_duration_ns(_name, _val), do: err()
+  def s_new_opt_duration_ms(_name, _val), do: err()
+  def s_new_opt_time_ns(_name, _val), do: err()
+  def s_new_opt_f32(_name, _val), do: err()
+  def s_new_opt_f64(_name, _val), do: err()
+  def s_new_opt_str(_name, _val), do: err()
   def s_rechunk(_s), do: err()
   def s_chunk_lengths(_s), do: err()
   def s_name(_s), do: err()
@@ -137,6 +328,9 @@ defmodule ExPolars.Native do
   def s_tail(_s, _length), do: err()
   def s_sort(_s, _reverse), do: err()
   def s_argsort(_s, _reverse), do: err()
+  def s_sort_by(_s, _descending, _nulls_last), do: err()
+  def s_argsort_by(_s, _descending, _nulls_last), do: err()
+  def s_top_k(_s, _k, _bottom \\ false), do: err()
   def s_unique(_s), do: err()
   def s_value_counts(_s), do: err()
   def s_arg_unique(_s), do: err()
@@ -366,6 +560,7 @@ defmodule ExPolars.Native do
   def s_gt_eq_str(_s, _rhs), do: err()
   def s_lt_eq_str(_s, _rhs), do: err()
   def s_to_json(_s), do: err()
+  def s_to_list(_s), do: err()
 
   defp err, do: :erlang.nif_error(:nif_not_loaded)
 end
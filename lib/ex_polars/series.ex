@@ -29,7 +29,28 @@ defmodule ExPolars.Series do
     15 => "time64_nanosecond",
     16 => "duration_nanosecond",
     17 => "duration_millisecond",
-    18 => "object"
+    18 => "object",
+    19 => "null",
+    20 => "f16",
+    21 => "time32_second",
+    22 => "time32_millisecond",
+    23 => "time64_microsecond",
+    24 => "timestamp_second",
+    25 => "timestamp_millisecond",
+    26 => "timestamp_microsecond",
+    27 => "timestamp_nanosecond",
+    28 => "duration_second",
+    29 => "duration_microsecond",
+    30 => "interval_year_month",
+    31 => "interval_day_time",
+    32 => "large_str",
+    33 => "large_binary",
+    34 => "fixed_size_binary",
+    35 => "large_list",
+    36 => "fixed_size_list",
+    37 => "struct",
+    38 => "union",
+    39 => "dictionary"
   }
 
   @dtype_vega %{
@@ -51,20 +72,84 @@ defmodule ExPolars.Series do
     15 => "temporal",
     16 => "temporal",
     17 => "temporal",
-    18 => "nominal"
+    18 => "nominal",
+    19 => "nominal",
+    20 => "quantitative",
+    21 => "temporal",
+    22 => "temporal",
+    23 => "temporal",
+    24 => "temporal",
+    25 => "temporal",
+    26 => "temporal",
+    27 => "temporal",
+    28 => "temporal",
+    29 => "temporal",
+    30 => "temporal",
+    31 => "temporal",
+    32 => "nominal",
+    33 => "nominal",
+    34 => "nominal",
+    35 => "ordinal",
+    36 => "ordinal",
+    37 => "nominal",
+    38 => "nominal",
+    39 => "nominal"
   }
 
-  @spec new(String.t(), list(String.t() | integer() | float() | boolean())) ::
+  @dtype_ids Map.new(@dtype_strs, fn {k, v} -> {v, k} end)
+
+  @spec new(String.t(), list(String.t() | integer() | float() | boolean() | nil)) ::
           {:ok, s()} | {:error, term}
+  @doc """
+  Create a series, the dtype is taken from the first non-nil value. `nil` values become nulls.
+  """
   def new(name, data) do
-    [first | _] = data
-
-    cond do
-      is_integer(first) -> Native.s_new_i64(name, data)
-      is_float(first) -> Native.s_new_f64(name, data)
-      is_boolean(first) -> Native.s_new_bool(name, data)
-      is_binary(first) -> Native.s_new_str(name, data)
-      true -> raise "Unspported datetype: #{inspect(first)}"
+    first = Enum.find(data, &(not is_nil(&1)))
+
+    if Enum.any?(data, &is_nil/1) do
+      cond do
+        is_integer(first) -> Native.s_new_opt_i64(name, data)
+        is_float(first) -> Native.s_new_opt_f64(name, data)
+        is_boolean(first) -> Native.s_new_opt_bool(name, data)
+        is_binary(first) or is_nil(first) -> Native.s_new_opt_str(name, data)
+        true -> raise "Unspported datetype: #{inspect(first)}"
+      end
+    else
+      cond do
+        is_integer(first) -> Native.s_new_i64(name, data)
+        is_float(first) -> Native.s_new_f64(name, data)
+        is_boolean(first) -> Native.s_new_bool(name, data)
+        is_binary(first) -> Native.s_new_str(name, data)
+        true -> raise "Unspported datetype: #{inspect(first)}"
+      end
+    end
+  end
+
+  @spec new_opt(String.t(), String.t(), list(term())) :: {:ok, s()} | {:error, term}
+  @doc """
+  Create a series of the given dtype (e.g. "u8", "date32" or "duration_millisecond") from a
+  list which may contain `nil`. Dates, times and durations are given as integers.
+  """
+  def new_opt(name, dtype, data) do
+    case dtype do
+      "i8" -> Native.s_new_opt_i8(name, data)
+      "i16" -> Native.s_new_opt_i16(name, data)
+      "i32" -> Native.s_new_opt_i32(name, data)
+      "i64" -> Native.s_new_opt_i64(name, data)
+      "u8" -> Native.s_new_opt_u8(name, data)
+      "u16" -> Native.s_new_opt_u16(name, data)
+      "u32" -> Native.s_new_opt_u32(name, data)
+      "u64" -> Native.s_new_opt_u64(name, data)
+      "f32" -> Native.s_new_opt_f32(name, data)
+      "f64" -> Native.s_new_opt_f64(name, data)
+      "bool" -> Native.s_new_opt_bool(name, data)
+      "str" -> Native.s_new_opt_str(name, data)
+      "date32" -> Native.s_new_opt_date32(name, data)
+      "date64" -> Native.s_new_opt_date64(name, data)
+      "time64_nanosecond" -> Native.s_new_opt_time_ns(name, data)
+      "duration_nanosecond" -> Native.s_new_opt_duration_ns(name, data)
+      "duration_millisecond" -> Native.s_new_opt_duration_ms(name, data)
+      _ -> {:error, %ExPolars.Error{kind: :other, message: "Unsupported dtype: #{dtype}"}}
     end
   end
 
@@ -72,16 +157,20 @@ defmodule ExPolars.Series do
   defdelegate new_duration_ns(name, data), to: Native, as: :s_new_duration_ns
 
   @spec to_list(s() | {:ok, s()}) :: {:ok, list()} | {:error, term}
+  @doc """
+  Convert the series to a list. Nulls become `nil`, dates and times become `Date`,
+  `NaiveDateTime` and `Time` structs and list series become nested lists.
+  """
   def to_list({:ok, s}), do: to_list(s)
+  defdelegate to_list(s), to: Native, as: :s_to_list
 
-  def to_list(s) do
-    with {:ok, json} <- Native.s_to_json(s),
-         {:ok, data} <- Jason.decode(json) do
-      {:ok, data}
-    else
-      e -> e
-    end
-  end
+  @spec to_json(s() | {:ok, s()}) :: {:ok, String.t()} | {:error, term}
+  @doc """
+  Serialize the series as a json array. Dates and times are written as the integers they're
+  stored as: days or milliseconds since the epoch, and nanoseconds since midnight.
+  """
+  def to_json({:ok, s}), do: to_json(s)
+  defdelegate to_json(s), to: Native, as: :s_to_json
 
   @spec rechunk(s() | {:ok, s()}) :: {:ok, s()} | {:error, term}
   def rechunk({:ok, s}), do: rechunk(s)
@@ -125,6 +214,12 @@ defmodule ExPolars.Series do
       @dtype_vega[18]
   end
 
+  @spec dtype_id(String.t()) :: integer() | nil
+  @doc """
+  Get the dtype id (as returned by `dtype/1`) of a dtype name like "f64".
+  """
+  def dtype_id(name), do: @dtype_ids[name]
+
   @spec n_chunks(s() | {:ok, s()}) :: {:ok, s()} | {:error, term}
   @doc """
   Get the number of chunks that this Series contains.
@@ -221,15 +316,45 @@ defmodule ExPolars.Series do
   def tail({:ok, s}, length), do: tail(s, length)
   defdelegate tail(s, length), to: Native, as: :s_tail
 
-  @spec sort(s() | {:ok, s()}, boolean()) :: {:ok, s()} | {:error, term}
+  @spec sort(s() | {:ok, s()}, boolean() | keyword()) :: {:ok, s()} | {:error, term}
+  @doc """
+  Sort Series, in reverse order when `reverse` is true.
+
+  With keyword options the sort is stable and null placement is explicit:
+
+  * `descending` - sort in descending order, defaults to false
+  * `nulls_last` - put nulls last (default) or first, whatever the direction
+  """
   def sort(s, reverse \\ false)
   def sort({:ok, s}, reverse), do: sort(s, reverse)
-  defdelegate sort(s, reverse), to: Native, as: :s_sort
 
-  @spec argsort(s() | {:ok, s()}, boolean()) :: {:ok, s()} | {:error, term}
+  def sort(s, opts) when is_list(opts),
+    do: Native.s_sort_by(s, Keyword.get(opts, :descending, false), nulls_last(opts))
+
+  def sort(s, reverse), do: Native.s_sort(s, reverse)
+
+  @spec argsort(s() | {:ok, s()}, boolean() | keyword()) :: {:ok, s()} | {:error, term}
+  @doc """
+  Indices that sort Series. Takes the same options as `sort/2`.
+  """
   def argsort(s, reverse \\ false)
   def argsort({:ok, s}, reverse), do: argsort(s, reverse)
-  defdelegate argsort(s, reverse), to: Native, as: :s_argsort
+
+  def argsort(s, opts) when is_list(opts),
+    do: Native.s_argsort_by(s, Keyword.get(opts, :descending, false), nulls_last(opts))
+
+  def argsort(s, reverse), do: Native.s_argsort(s, reverse)
+
+  defp nulls_last(opts), do: Keyword.get(opts, :nulls_last, true)
+
+  @spec top_k(s() | {:ok, s()}, non_neg_integer(), keyword()) :: {:ok, s()} | {:error, term}
+  @doc """
+  The `k` largest values of Series in descending order, or with `bottom: true` the `k`
+  smallest in ascending order. Nulls come last. See `ExPolars.DataFrame.top_k/4`.
+  """
+  def top_k(s, k, opts \\ [])
+  def top_k({:ok, s}, k, opts), do: top_k(s, k, opts)
+  def top_k(s, k, opts), do: Native.s_top_k(s, k, Keyword.get(opts, :bottom, false))
 
   @spec unique(s() | {:ok, s()}) :: {:ok, s()} | {:error, term}
   def unique({:ok, s}), do: unique(s)
@@ -0,0 +1,222 @@
+// Transparent gzip and zstd (de)compression of text files.
+//
+// The compression of a file is given explicitly ("gzip", "zstd" or "none"), or with "infer"
+// taken from the file extension (`.gz`, `.zst`) and, when reading, from the magic bytes at the
+// start of the file.
+
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Cursor, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::ExPolarsError;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Parse an explicit compression, `None` for "infer".
+    fn parse(compression: &str) -> Result<Option<Self>, ExPolarsError> {
+        match compression {
+            "infer" => Ok(None),
+            "none" | "uncompressed" => Ok(Some(Compression::None)),
+            "gzip" => Ok(Some(Compression::Gzip)),
+            "zstd" => Ok(Some(Compression::Zstd)),
+            c => Err(ExPolarsError::Other(format!(
+                "Compression {} not supported",
+                c
+            ))),
+        }
+    }
+
+    fn from_extension(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "gz" | "gzip" => Some(Compression::Gzip),
+            "zst" | "zstd" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    fn from_magic(f: &mut File) -> Result<Self, ExPolarsError> {
+        let mut magic = [0; 4];
+        let mut n = 0;
+        while n < magic.len() {
+            match f.read(&mut magic[n..])? {
+                0 => break,
+                read => n += read,
+            }
+        }
+        f.seek(SeekFrom::Start(0))?;
+        if n >= 2 && magic[..2] == GZIP_MAGIC {
+            Ok(Compression::Gzip)
+        } else if n == 4 && magic == ZSTD_MAGIC {
+            Ok(Compression::Zstd)
+        } else {
+            Ok(Compression::None)
+        }
+    }
+}
+
+fn open(path: &str, compression: &str) -> Result<(File, Compression), ExPolarsError> {
+    let mut f = File::open(path)?;
+    let compression = match Compression::parse(compression)? {
+        Some(c) => c,
+        None => match Compression::from_extension(Path::new(path)) {
+            Some(c) => c,
+            None => Compression::from_magic(&mut f)?,
+        },
+    };
+    Ok((f, compression))
+}
+
+/// A readable file, decompressed into memory if it's compressed, as the readers need `Seek`.
+pub(crate) enum Input {
+    File(File),
+    Memory(Cursor<Vec<u8>>),
+}
+
+impl Read for Input {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Input::File(f) => f.read(buf),
+            Input::Memory(c) => c.read(buf),
+        }
+    }
+}
+
+impl Seek for Input {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            Input::File(f) => f.seek(pos),
+            Input::Memory(c) => c.seek(pos),
+        }
+    }
+}
+
+pub(crate) fn open_input(path: &str, compression: &str) -> Result<Input, ExPolarsError> {
+    let (f, compression) = open(path, compression)?;
+    if compression == Compression::None {
+        return Ok(Input::File(f));
+    }
+    let mut data = Vec::new();
+    open_reader_from(f, compression)?.read_to_end(&mut data)?;
+    Ok(Input::Memory(Cursor::new(data)))
+}
+
+fn open_reader_from(
+    f: File,
+    compression: Compression,
+) -> Result<Box<dyn BufRead + Send>, ExPolarsError> {
+    let reader: Box<dyn BufRead + Send> = match compression {
+        Compression::None => Box::new(BufReader::new(f)),
+        // concatenated gzip members, as written by e.g. `cat a.gz b.gz`, are read as one stream
+        Compression::Gzip => Box::new(BufReader::new(MultiGzDecoder::new(f))),
+        Compression::Zstd => Box::new(BufReader::new(zstd::stream::read::Decoder::new(f)?)),
+    };
+    Ok(reader)
+}
+
+/// A streaming reader of the decompressed file.
+pub(crate) fn open_reader(
+    path: &str,
+    compression: &str,
+) -> Result<Box<dyn BufRead + Send>, ExPolarsError> {
+    let (f, compression) = open(path, compression)?;
+    open_reader_from(f, compression)
+}
+
+/// A file being written, compressed on the fly. `finish` must be called to complete it.
+pub(crate) enum Output {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+    Zstd(zstd::stream::write::Encoder<BufWriter<File>>),
+}
+
+/// The compression to write `path` with. With "infer" it's taken from the extension.
+pub(crate) fn output_compression(
+    path: &str,
+    compression: &str,
+) -> Result<Compression, ExPolarsError> {
+    Ok(match Compression::parse(compression)? {
+        Some(c) => c,
+        None => Compression::from_extension(Path::new(path)).unwrap_or(Compression::None),
+    })
+}
+
+impl Output {
+    /// Create (or truncate) `path`, see `output_compression`.
+    pub fn create(path: &str, compression: &str) -> Result<Self, ExPolarsError> {
+        Self::new(File::create(path)?, output_compression(path, compression)?)
+    }
+
+    pub fn new(f: File, compression: Compression) -> Result<Self, ExPolarsError> {
+        let f = BufWriter::new(f);
+        let output = match compression {
+            Compression::None => Output::Plain(f),
+            Compression::Gzip => Output::Gzip(GzEncoder::new(f, flate2::Compression::default())),
+            Compression::Zstd => Output::Zstd(zstd::stream::write::Encoder::new(f, 0)?),
+        };
+        Ok(output)
+    }
+
+    /// Write the compression trailer and flush the file.
+    pub fn finish(self) -> Result<(), ExPolarsError> {
+        match self {
+            Output::Plain(mut f) => f.flush()?,
+            Output::Gzip(e) => e.finish()?.flush()?,
+            Output::Zstd(e) => e.finish()?.flush()?,
+        }
+        Ok(())
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Plain(f) => f.write(buf),
+            Output::Gzip(e) => e.write(buf),
+            Output::Zstd(e) => e.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Plain(f) => f.flush(),
+            Output::Gzip(e) => e.flush(),
+            Output::Zstd(e) => e.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn compressed_output_is_detected_by_magic_bytes() {
+        for compression in &["gzip", "zstd"] {
+            let name = format!("expolars-{}-{}.csv", std::process::id(), compression);
+            let path = std::env::temp_dir().join(name);
+            let path = path.to_str().unwrap();
+            let mut output = Output::create(path, compression).unwrap();
+            output.write_all(b"a,b\n1,2\n").unwrap();
+            output.finish().unwrap();
+
+            let mut data = String::new();
+            open_reader(path, "infer")
+                .unwrap()
+                .read_to_string(&mut data)
+                .unwrap();
+            assert_eq!(data, "a,b\n1,2\n");
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+}
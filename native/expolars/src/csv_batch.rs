@@ -0,0 +1,277 @@
+// Batched CSV reading: whole records are read from the file a batch at a time and parsed with
+// the same options as `df_read_csv`, so a large file can be processed with bounded memory.
+
+use polars::frame::ser::csv::CsvEncoding;
+use polars::prelude::*;
+use std::io::{BufRead, Cursor};
+use std::result::Result;
+use std::sync::Arc;
+
+use crate::ExPolarsError;
+
+pub struct CsvBatchOptions {
+    pub infer_schema_length: usize,
+    pub batch_size: usize,
+    pub has_header: bool,
+    pub ignore_errors: bool,
+    pub skip_rows: usize,
+    pub projection: Option<Vec<usize>>,
+    pub sep: u8,
+    pub column_names: Option<Vec<String>>,
+    pub encoding: CsvEncoding,
+}
+
+pub struct CsvBatchReader {
+    reader: Box<dyn BufRead + Send>,
+    options: CsvBatchOptions,
+    header: Option<Vec<u8>>,
+    started: bool,
+    // schema of the first batch, so every batch has the same dtypes; it is never widened
+    schema: Option<Arc<Schema>>,
+    // the selected columns, by name
+    columns: Option<Vec<String>>,
+    // indices of the selected columns in `schema`, so later batches only parse those fields
+    projection: Option<Vec<usize>>,
+}
+
+impl CsvBatchReader {
+    pub fn new(reader: Box<dyn BufRead + Send>, options: CsvBatchOptions) -> Self {
+        Self {
+            reader,
+            options,
+            header: None,
+            started: false,
+            schema: None,
+            columns: None,
+            projection: None,
+        }
+    }
+
+    /// Read one record, which can span several lines when a quoted field contains newlines.
+    /// Returns false at the end of the file.
+    fn read_record(&mut self, buf: &mut Vec<u8>) -> Result<bool, ExPolarsError> {
+        let start = buf.len();
+        loop {
+            let n = self.reader.read_until(b'\n', buf)?;
+            if n == 0 {
+                return Ok(buf.len() > start);
+            }
+            // an odd number of quotes leaves a quoted field open
+            let in_quotes = buf[start..]
+                .iter()
+                .fold(false, |open, b| open != (*b == b'"'));
+            if !in_quotes {
+                if !buf.ends_with(b"\n") {
+                    buf.push(b'\n');
+                }
+                return Ok(true);
+            }
+        }
+    }
+
+    fn start(&mut self) -> Result<(), ExPolarsError> {
+        // like `df_read_csv`, `skip_rows` counts lines, not records
+        let mut skipped = Vec::new();
+        for _ in 0..self.options.skip_rows {
+            skipped.clear();
+            if self.reader.read_until(b'\n', &mut skipped)? == 0 {
+                break;
+            }
+        }
+        if self.options.has_header {
+            let mut header = Vec::new();
+            self.read_record(&mut header)?;
+            self.header = Some(header);
+        }
+        self.started = true;
+        Ok(())
+    }
+
+    fn parse(&self, data: Vec<u8>) -> Result<DataFrame, ExPolarsError> {
+        let opts = &self.options;
+        let mut reader = CsvReader::new(Cursor::new(data))
+            .infer_schema(Some(opts.infer_schema_length))
+            .has_header(opts.has_header)
+            .with_delimiter(opts.sep)
+            .with_ignore_parser_errors(opts.ignore_errors)
+            .with_batch_size(opts.batch_size)
+            .with_encoding(opts.encoding);
+        if let Some(schema) = &self.schema {
+            reader = reader
+                .with_schema(schema.clone())
+                .with_projection(self.projection.clone());
+        }
+        Ok(reader.finish()?)
+    }
+
+    /// Names of the columns to keep, from `projection` or `column_names`.
+    fn selected_columns(&self, df: &DataFrame) -> Result<Option<Vec<String>>, ExPolarsError> {
+        let names = df.get_column_names();
+        if let Some(projection) = &self.options.projection {
+            let columns = projection
+                .iter()
+                .map(|i| match names.get(*i) {
+                    Some(name) => Ok(name.to_string()),
+                    None => Err(ExPolarsError::Other(format!(
+                        "projection index {} out of bounds for {} columns",
+                        i,
+                        names.len()
+                    ))),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            return Ok(Some(columns));
+        }
+        Ok(self.options.column_names.clone())
+    }
+
+    /// Read and parse the next `n_rows` records, `None` once the file is exhausted.
+    pub fn next_batch(&mut self, n_rows: usize) -> Result<Option<DataFrame>, ExPolarsError> {
+        if !self.started {
+            self.start()?;
+        }
+        let mut data = self.header.clone().unwrap_or_default();
+        let mut rows = 0;
+        while rows < n_rows && self.read_record(&mut data)? {
+            rows += 1;
+        }
+        if rows == 0 {
+            return Ok(None);
+        }
+
+        // the first batch is parsed whole to infer the schema of every column
+        let df = self.parse(data)?;
+        if self.schema.is_none() {
+            let schema = df.schema();
+            self.columns = self.selected_columns(&df)?;
+            if let Some(columns) = &self.columns {
+                let projection = columns
+                    .iter()
+                    .map(|c| schema.index_of(c))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.projection = Some(projection);
+            }
+            self.schema = Some(Arc::new(schema));
+        }
+        match &self.columns {
+            Some(columns) => {
+                let columns: Vec<&str> = columns.iter().map(|c| c.as_str()).collect();
+                Ok(Some(df.select(columns)?))
+            }
+            None => Ok(Some(df)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn options(projection: Option<Vec<usize>>) -> CsvBatchOptions {
+        CsvBatchOptions {
+            infer_schema_length: 100,
+            batch_size: 64,
+            has_header: true,
+            ignore_errors: false,
+            skip_rows: 0,
+            projection,
+            sep: b',',
+            column_names: None,
+            encoding: CsvEncoding::Utf8,
+        }
+    }
+
+    fn batch_reader(csv: &str, projection: Option<Vec<usize>>) -> CsvBatchReader {
+        CsvBatchReader::new(
+            Box::new(Cursor::new(csv.as_bytes().to_vec())),
+            options(projection),
+        )
+    }
+
+    fn ids(df: &DataFrame) -> Vec<Option<i64>> {
+        df.column("id")
+            .unwrap()
+            .i64()
+            .unwrap()
+            .into_iter()
+            .collect()
+    }
+
+    fn notes(df: &DataFrame) -> Vec<Option<String>> {
+        let notes = df.column("note").unwrap().utf8().unwrap();
+        (0..notes.len())
+            .map(|i| notes.get(i).map(|s| s.to_string()))
+            .collect()
+    }
+
+    const CSV: &str = "id,note,score\n\
+                       1,a,0.5\n\
+                       2,\"two\nlines\",1.5\n\
+                       3,\"x, \"\"y\"\"\",2.5\n\
+                       4,d,3.5\n\
+                       5,\"e\n\",4.5";
+
+    #[test]
+    fn reads_whole_records_in_batches() {
+        let mut reader = batch_reader(CSV, None);
+        let first = reader.next_batch(2).unwrap().unwrap();
+        assert_eq!(first.get_column_names(), vec!["id", "note", "score"]);
+        assert_eq!(ids(&first), vec![Some(1), Some(2)]);
+        assert_eq!(
+            notes(&first),
+            vec![Some("a".into()), Some("two\nlines".into())]
+        );
+
+        let second = reader.next_batch(2).unwrap().unwrap();
+        assert_eq!(ids(&second), vec![Some(3), Some(4)]);
+        assert_eq!(
+            notes(&second),
+            vec![Some("x, \"y\"".into()), Some("d".into())]
+        );
+
+        // the last batch has the rows left
+        let last = reader.next_batch(2).unwrap().unwrap();
+        assert_eq!(ids(&last), vec![Some(5)]);
+        assert_eq!(notes(&last), vec![Some("e\n".into())]);
+        assert!(reader.next_batch(2).unwrap().is_none());
+    }
+
+    #[test]
+    fn projects_every_batch() {
+        let mut reader = batch_reader(CSV, Some(vec![1, 0]));
+        let mut batches = vec![];
+        while let Some(df) = reader.next_batch(3).unwrap() {
+            assert_eq!(df.get_column_names(), vec!["note", "id"]);
+            batches.push(ids(&df));
+        }
+        assert_eq!(
+            batches,
+            vec![vec![Some(1), Some(2), Some(3)], vec![Some(4), Some(5)]]
+        );
+
+        let mut reader = batch_reader(CSV, Some(vec![3]));
+        assert!(reader.next_batch(3).is_err());
+    }
+
+    #[test]
+    fn later_batches_keep_the_dtypes_of_the_first() {
+        let mut reader = batch_reader("id,x\n1,1\n2,2\n3,2.5\n", None);
+        let first = reader.next_batch(2).unwrap().unwrap();
+        assert_eq!(first.column("x").unwrap().dtype(), &ArrowDataType::Int64);
+        assert!(reader.next_batch(2).is_err());
+    }
+
+    #[test]
+    fn skip_rows_counts_lines() {
+        // an unbalanced quote in a skipped line doesn't swallow the header
+        let csv = "exported \"today\nby: me\nid,x\n1,2\n";
+        let options = CsvBatchOptions {
+            skip_rows: 2,
+            ..options(None)
+        };
+        let data = Box::new(Cursor::new(csv.as_bytes().to_vec()));
+        let mut reader = CsvBatchReader::new(data, options);
+        let df = reader.next_batch(10).unwrap().unwrap();
+        assert_eq!(df.get_column_names(), vec!["id", "x"]);
+        assert_eq!(ids(&df), vec![Some(1)]);
+    }
+}
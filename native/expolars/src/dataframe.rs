@@ -1,21 +1,141 @@
 // This file is modified based on: https://github.com/ritchie46/polars/blob/master/py-polars/src/dataframe.rs
 
+use polars::lazy::prelude::*;
 use polars::prelude::*;
 
+use arrow::array::{make_array, ArrayData, ArrayRef};
+use arrow::buffer::Buffer;
+use arrow::ipc::reader::{FileReader, StreamReader};
+use arrow::ipc::writer::StreamWriter;
+use arrow::record_batch::RecordBatch;
+use parquet::basic::Compression;
+use parquet::file::writer::ParquetWriter;
 use polars::frame::ser::csv::CsvEncoding;
+use polars::frame::ser::ipc::{IPCReader, IPCWriter};
+use rustler::{Binary, Encoder, Env, OwnedBinary, Term};
+use serde_json::Value;
+use std::convert::TryFrom;
 use std::fs::File;
-use std::io::Cursor;
+use std::io::{Cursor, Read, Seek, Write};
 use std::result::Result;
+use std::sync::Arc;
+
+use crate::compression::{open_input, open_reader, output_compression, Output};
+use crate::csv_batch::{CsvBatchOptions, CsvBatchReader};
+use crate::dataset::{read_dataset, write_dataset};
+use crate::expr::{to_expr_collection, ExAgg, ExExpr, ExPredicate};
+use crate::join::{asof_join, cross_join, semi_anti_join, suffix_overlapping, AsofStrategy};
+use crate::json::{read_json, JsonOptions};
+use crate::parquet_file::{read_parquet, FrameWriter, SharedBuffer};
+use crate::scan::{scan_csv, scan_parquet, ScanPredicate};
+use crate::series::{to_ex_series_collection, to_json_values, to_series_collection};
+use crate::sort::{arg_sort_by, sort_by, top_k};
+use crate::term::{columns_to_df, decode_schema, make_map, rows_to_df, to_terms};
+use crate::writer::{BatchWriter, SinkOptions};
+
+use crate::{DataType, ExBatchWriter, ExCsvBatchReader, ExDataFrame, ExPolarsError, ExSeries};
 
-use crate::series::{to_ex_series_collection, to_series_collection};
+use crate::{df_read, df_read_read, df_write, df_write_read};
 
-use crate::{DataType, ExDataFrame, ExPolarsError, ExSeries};
+pub(crate) fn to_binary<'a>(env: Env<'a>, data: &[u8]) -> Result<Binary<'a>, ExPolarsError> {
+    match OwnedBinary::new(data.len()) {
+        Some(mut b) => {
+            b.as_mut_slice().copy_from_slice(data);
+            Ok(b.release(env))
+        }
+        None => Err(ExPolarsError::Internal("Failed to allocate binary".into())),
+    }
+}
+
+/// A zero length array, so a frame read without any batches still has one chunk per column.
+fn empty_array(dtype: &ArrowDataType) -> ArrayRef {
+    let data = ArrayData::builder(dtype.clone());
+    let data = match dtype {
+        ArrowDataType::Utf8 => data
+            .add_buffer(Buffer::from(&0i32.to_ne_bytes()))
+            .add_buffer(Buffer::from(&[])),
+        ArrowDataType::List(inner) => data
+            .add_buffer(Buffer::from(&0i32.to_ne_bytes()))
+            .add_child_data(empty_array(inner).data()),
+        _ => data.add_buffer(Buffer::from(&[])),
+    };
+    make_array(data.build())
+}
+
+/// Convert record batches with the given schema to a data frame, without copying the data.
+pub(crate) fn batches_to_df(
+    schema: &Schema,
+    batches: &[RecordBatch],
+) -> Result<DataFrame, ExPolarsError> {
+    let mut columns = Vec::with_capacity(schema.fields().len());
+    for (i, field) in schema.fields().iter().enumerate() {
+        let name = field.name().as_str();
+        let chunks = match batches {
+            [] => vec![empty_array(field.data_type())],
+            _ => batches.iter().map(|b| b.column(i).clone()).collect(),
+        };
+        let s = match field.data_type() {
+            ArrowDataType::Boolean => BooleanChunked::new_from_chunks(name, chunks).into_series(),
+            ArrowDataType::UInt8 => UInt8Chunked::new_from_chunks(name, chunks).into_series(),
+            ArrowDataType::UInt16 => UInt16Chunked::new_from_chunks(name, chunks).into_series(),
+            ArrowDataType::UInt32 => UInt32Chunked::new_from_chunks(name, chunks).into_series(),
+            ArrowDataType::UInt64 => UInt64Chunked::new_from_chunks(name, chunks).into_series(),
+            ArrowDataType::Int8 => Int8Chunked::new_from_chunks(name, chunks).into_series(),
+            ArrowDataType::Int16 => Int16Chunked::new_from_chunks(name, chunks).into_series(),
+            ArrowDataType::Int32 => Int32Chunked::new_from_chunks(name, chunks).into_series(),
+            ArrowDataType::Int64 => Int64Chunked::new_from_chunks(name, chunks).into_series(),
+            ArrowDataType::Float32 => Float32Chunked::new_from_chunks(name, chunks).into_series(),
+            ArrowDataType::Float64 => Float64Chunked::new_from_chunks(name, chunks).into_series(),
+            ArrowDataType::Utf8 => Utf8Chunked::new_from_chunks(name, chunks).into_series(),
+            ArrowDataType::Date32(DateUnit::Day) => {
+                Date32Chunked::new_from_chunks(name, chunks).into_series()
+            }
+            ArrowDataType::Date64(DateUnit::Millisecond) => {
+                Date64Chunked::new_from_chunks(name, chunks).into_series()
+            }
+            ArrowDataType::Time64(TimeUnit::Nanosecond) => {
+                Time64NanosecondChunked::new_from_chunks(name, chunks).into_series()
+            }
+            ArrowDataType::Duration(TimeUnit::Nanosecond) => {
+                DurationNanosecondChunked::new_from_chunks(name, chunks).into_series()
+            }
+            ArrowDataType::Duration(TimeUnit::Millisecond) => {
+                DurationMillisecondChunked::new_from_chunks(name, chunks).into_series()
+            }
+            ArrowDataType::List(_) => ListChunked::new_from_chunks(name, chunks).into_series(),
+            dt => {
+                return Err(ExPolarsError::Other(format!(
+                    "column {}: {:?} not supported",
+                    name, dt
+                )))
+            }
+        };
+        columns.push(s);
+    }
+    Ok(DataFrame::new(columns)?)
+}
 
-use crate::{df_read, df_read_read, df_write, df_write_read};
+fn csv_encoding(encoding: &str) -> CsvEncoding {
+    match encoding {
+        "utf8-lossy" => CsvEncoding::LossyUtf8,
+        _ => CsvEncoding::Utf8,
+    }
+}
 
-#[rustler::nif]
-pub fn df_read_csv(
-    filename: &str,
+/// The separator byte of a csv file. `sep` must be a single byte, e.g. "," or "\t".
+pub(crate) fn csv_delimiter(sep: &str) -> Result<u8, ExPolarsError> {
+    match sep.as_bytes() {
+        [b] => Ok(*b),
+        _ => Err(ExPolarsError::Other(format!(
+            "Separator {:?} must be a single byte",
+            sep
+        ))),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn read_csv<R: 'static + Read + Seek + Send + Sync>(
+    reader: CsvReader<R>,
     infer_schema_length: usize,
     batch_size: usize,
     has_header: bool,
@@ -27,16 +147,13 @@ pub fn df_read_csv(
     do_rechunk: bool,
     column_names: Option<Vec<String>>,
     encoding: &str,
-) -> Result<ExDataFrame, ExPolarsError> {
-    let encoding = match encoding {
-        "utf8-lossy" => CsvEncoding::LossyUtf8,
-        _ => CsvEncoding::Utf8,
-    };
-    let df = CsvReader::from_path(filename)?
+) -> Result<DataFrame, ExPolarsError> {
+    let encoding = csv_encoding(encoding);
+    let df = reader
         .infer_schema(Some(infer_schema_length))
         .has_header(has_header)
         .with_stop_after_n_rows(stop_after_n_rows)
-        .with_delimiter(sep.as_bytes()[0])
+        .with_delimiter(csv_delimiter(sep)?)
         .with_skip_rows(skip_rows)
         .with_ignore_parser_errors(ignore_errors)
         .with_projection(projection)
@@ -45,48 +162,290 @@ pub fn df_read_csv(
         .with_encoding(encoding)
         .with_columns(column_names)
         .finish()?;
+    Ok(df)
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+#[allow(clippy::too_many_arguments)]
+pub fn df_read_csv(
+    filename: &str,
+    infer_schema_length: usize,
+    batch_size: usize,
+    has_header: bool,
+    ignore_errors: bool,
+    stop_after_n_rows: Option<usize>,
+    skip_rows: usize,
+    projection: Option<Vec<usize>>,
+    sep: &str,
+    do_rechunk: bool,
+    column_names: Option<Vec<String>>,
+    encoding: &str,
+    compression: &str,
+) -> Result<ExDataFrame, ExPolarsError> {
+    let df = read_csv(
+        CsvReader::new(open_input(filename, compression)?),
+        infer_schema_length,
+        batch_size,
+        has_header,
+        ignore_errors,
+        stop_after_n_rows,
+        skip_rows,
+        projection,
+        sep,
+        do_rechunk,
+        column_names,
+        encoding,
+    )?;
     Ok(ExDataFrame::new(df))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
+#[allow(clippy::too_many_arguments)]
+pub fn df_read_csv_binary(
+    data: Binary,
+    infer_schema_length: usize,
+    batch_size: usize,
+    has_header: bool,
+    ignore_errors: bool,
+    stop_after_n_rows: Option<usize>,
+    skip_rows: usize,
+    projection: Option<Vec<usize>>,
+    sep: &str,
+    do_rechunk: bool,
+    column_names: Option<Vec<String>>,
+    encoding: &str,
+) -> Result<ExDataFrame, ExPolarsError> {
+    // the reader needs to own its input, the binary only lives as long as the NIF call
+    let df = read_csv(
+        CsvReader::new(Cursor::new(data.as_slice().to_vec())),
+        infer_schema_length,
+        batch_size,
+        has_header,
+        ignore_errors,
+        stop_after_n_rows,
+        skip_rows,
+        projection,
+        sep,
+        do_rechunk,
+        column_names,
+        encoding,
+    )?;
+    Ok(ExDataFrame::new(df))
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+#[allow(clippy::too_many_arguments)]
+pub fn df_read_csv_batched(
+    filename: &str,
+    infer_schema_length: usize,
+    batch_size: usize,
+    has_header: bool,
+    ignore_errors: bool,
+    skip_rows: usize,
+    projection: Option<Vec<usize>>,
+    sep: &str,
+    column_names: Option<Vec<String>>,
+    encoding: &str,
+    compression: &str,
+) -> Result<ExCsvBatchReader, ExPolarsError> {
+    let options = CsvBatchOptions {
+        infer_schema_length,
+        batch_size,
+        has_header,
+        ignore_errors,
+        skip_rows,
+        projection,
+        sep: csv_delimiter(sep)?,
+        column_names,
+        encoding: csv_encoding(encoding),
+    };
+    let reader = open_reader(filename, compression)?;
+    Ok(ExCsvBatchReader::new(CsvBatchReader::new(reader, options)))
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn df_next_batch(
+    reader: ExCsvBatchReader,
+    n_rows: usize,
+) -> Result<Option<ExDataFrame>, ExPolarsError> {
+    match reader.inner.0.lock() {
+        Ok(mut reader) => Ok(reader.next_batch(n_rows)?.map(ExDataFrame::new)),
+        Err(_) => Err(ExPolarsError::Internal(
+            "Failed to take lock for csv reader".into(),
+        )),
+    }
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
 pub fn df_read_parquet(filename: &str) -> Result<ExDataFrame, ExPolarsError> {
-    let f = File::open(filename)?;
-    let df = ParquetReader::new(f).finish()?;
+    let df = read_parquet(File::open(filename)?)?;
     Ok(ExDataFrame::new(df))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn df_read_parquet_binary(data: Binary) -> Result<ExDataFrame, ExPolarsError> {
+    // parquet needs random access to the whole file, so the reader owns a copy of the data
+    let df = read_parquet(Cursor::new(data.as_slice().to_vec()))?;
+    Ok(ExDataFrame::new(df))
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+#[allow(clippy::too_many_arguments)]
+pub fn df_scan_csv(
+    filename: String,
+    columns: Option<Vec<String>>,
+    predicate: Option<ScanPredicate>,
+    sep: &str,
+    has_header: bool,
+    ignore_errors: bool,
+    skip_rows: usize,
+    stop_after_n_rows: Option<usize>,
+) -> Result<ExDataFrame, ExPolarsError> {
+    let df = scan_csv(
+        filename,
+        columns,
+        predicate,
+        csv_delimiter(sep)?,
+        has_header,
+        ignore_errors,
+        skip_rows,
+        stop_after_n_rows,
+    )?;
+    Ok(ExDataFrame::new(df))
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn df_scan_parquet(
+    filename: &str,
+    columns: Option<Vec<String>>,
+    predicate: Option<ScanPredicate>,
+) -> Result<ExDataFrame, ExPolarsError> {
+    let df = scan_parquet(filename, columns, predicate)?;
+    Ok(ExDataFrame::new(df))
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn df_read_dataset(
+    root: &str,
+    columns: Option<Vec<String>>,
+    predicate: Option<ScanPredicate>,
+) -> Result<ExDataFrame, ExPolarsError> {
+    let df = read_dataset(root, columns, predicate)?;
+    Ok(ExDataFrame::new(df))
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn df_write_dataset(
+    data: ExDataFrame,
+    root: &str,
+    by: Vec<&str>,
+    format: &str,
+    compression: &str,
+    mode: &str,
+) -> Result<Vec<String>, ExPolarsError> {
+    df_read!(data, df, {
+        write_dataset(&df, root, &by, format, compression, mode)
+    })
+}
+
+fn json_options(
+    infer_schema_length: Option<usize>,
+    batch_size: usize,
+    schema: Option<Vec<(String, u8)>>,
+    nested: &str,
+) -> Result<JsonOptions, ExPolarsError> {
+    Ok(JsonOptions {
+        infer_schema_length,
+        batch_size,
+        schema: schema.map(decode_schema).transpose()?,
+        nested: nested.parse()?,
+    })
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
 pub fn df_read_json(
     filename: &str,
     line_delimited_json: bool,
+    compression: &str,
+    infer_schema_length: Option<usize>,
+    batch_size: usize,
+    schema: Option<Vec<(String, u8)>>,
+    nested: &str,
+) -> Result<ExDataFrame, ExPolarsError> {
+    let options = json_options(infer_schema_length, batch_size, schema, nested)?;
+    let f = open_reader(filename, compression)?;
+    let df = read_json(f, line_delimited_json, options)?;
+    Ok(ExDataFrame::new(df))
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn df_read_json_binary(
+    data: Binary,
+    line_delimited_json: bool,
+    infer_schema_length: Option<usize>,
+    batch_size: usize,
+    schema: Option<Vec<(String, u8)>>,
+    nested: &str,
 ) -> Result<ExDataFrame, ExPolarsError> {
-    let infer_size = 3;
-    let batch_size = 100;
-    let f = File::open(filename)?;
-    let df = if line_delimited_json {
-        JsonReader::new(f)
-            .infer_schema(Some(infer_size))
-            .with_batch_size(batch_size)
-            .finish()?
-    } else {
-        let v: serde_json::Value = serde_json::from_reader(f)?;
-        let items: Vec<String> = v
-            .as_array()
-            .unwrap()
-            .iter()
-            .map(|item| serde_json::to_string(&item).unwrap())
-            .collect();
+    let options = json_options(infer_schema_length, batch_size, schema, nested)?;
+    let df = read_json(data.as_slice(), line_delimited_json, options)?;
+    Ok(ExDataFrame::new(df))
+}
 
-        JsonReader::new(Cursor::new(items.join("\n")))
-            .infer_schema(Some(infer_size))
-            .with_batch_size(batch_size)
-            .finish()?
+/// Read an IPC file, keeping only the columns at the given indices, in that order. arrow's
+/// reader has no projection, so each record batch is projected as soon as it's read and the
+/// other columns are dropped before they're converted to series.
+fn read_ipc<R: Read + Seek>(
+    reader: R,
+    projection: Option<Vec<usize>>,
+) -> Result<DataFrame, ExPolarsError> {
+    let indices = match projection {
+        Some(indices) => indices,
+        None => return Ok(IPCReader::new(reader).finish()?),
     };
+    let reader = FileReader::try_new(reader)?;
+    let schema = reader.schema();
+    let mut fields = Vec::with_capacity(indices.len());
+    for idx in &indices {
+        match schema.fields().get(*idx) {
+            Some(field) => fields.push(field.clone()),
+            None => {
+                return Err(ExPolarsError::Other(format!(
+                    "Projection index {} out of bounds",
+                    idx
+                )))
+            }
+        }
+    }
+    let projected = Arc::new(Schema::new(fields));
+    let mut batches = Vec::new();
+    for batch in reader {
+        let batch = batch?;
+        let columns = indices.iter().map(|idx| batch.column(*idx).clone());
+        batches.push(RecordBatch::try_new(projected.clone(), columns.collect())?);
+    }
+    batches_to_df(&projected, &batches)
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn df_read_ipc(
+    filename: &str,
+    projection: Option<Vec<usize>>,
+) -> Result<ExDataFrame, ExPolarsError> {
+    let f = File::open(filename)?;
+    Ok(ExDataFrame::new(read_ipc(f, projection)?))
+}
 
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn df_read_ipc_binary(
+    data: Binary,
+    projection: Option<Vec<usize>>,
+) -> Result<ExDataFrame, ExPolarsError> {
+    let df = read_ipc(Cursor::new(data.as_slice()), projection)?;
     Ok(ExDataFrame::new(df))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_to_csv(
     data: ExDataFrame,
     batch_size: usize,
@@ -106,32 +465,338 @@ pub fn df_to_csv(
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyIo")]
 pub fn df_to_csv_file(
     data: ExDataFrame,
     filename: &str,
     batch_size: usize,
     has_headers: bool,
     delimiter: u8,
+    compression: &str,
 ) -> Result<(), ExPolarsError> {
     df_write!(data, df, {
-        let mut f = File::create(filename)?;
+        let mut f = Output::create(filename, compression)?;
         CsvWriter::new(&mut f)
             .has_headers(has_headers)
             .with_delimiter(delimiter)
             .with_batch_size(batch_size)
             .finish(&mut *df)?;
+        f.finish()
+    })
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn df_csv_writer(
+    filename: &str,
+    batch_size: usize,
+    has_headers: bool,
+    delimiter: u8,
+    compression: &str,
+) -> Result<ExBatchWriter, ExPolarsError> {
+    let options = SinkOptions::Csv {
+        has_header: has_headers,
+        delimiter,
+        batch_size,
+        compression: output_compression(filename, compression)?,
+    };
+    let f = File::create(filename)?;
+    Ok(ExBatchWriter::new(BatchWriter::new(f, options)?))
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn df_parquet_writer(
+    filename: &str,
+    compression: &str,
+    statistics: bool,
+) -> Result<ExBatchWriter, ExPolarsError> {
+    let options = SinkOptions::Parquet {
+        compression: parquet_compression(compression)?,
+        statistics,
+    };
+    let f = File::create(filename)?;
+    Ok(ExBatchWriter::new(BatchWriter::new(f, options)?))
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn df_write_batch(writer: ExBatchWriter, data: ExDataFrame) -> Result<(), ExPolarsError> {
+    df_read!(data, df, { writer.inner.0.write(df.clone()) })
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn df_close_writer(writer: ExBatchWriter) -> Result<(), ExPolarsError> {
+    writer.inner.0.close()
+}
+
+fn write_json_object<'a, I>(buf: &mut String, fields: I) -> Result<(), ExPolarsError>
+where
+    I: Iterator<Item = (&'a str, Value)>,
+{
+    buf.push('{');
+    for (i, (name, value)) in fields.enumerate() {
+        if i > 0 {
+            buf.push(',');
+        }
+        buf.push_str(&serde_json::to_string(name)?);
+        buf.push(':');
+        buf.push_str(&serde_json::to_string(&value)?);
+    }
+    buf.push('}');
+    Ok(())
+}
+
+/// Serialize the frame as json, keeping the column order. `orient` is one of:
+/// - "records": an array of row objects, `[{"a": 1, "b": "x"}, ...]`
+/// - "columns": an object of column arrays, `{"a": [1, ...], "b": ["x", ...]}`
+/// - "ndjson": one row object per line
+fn to_json(df: &DataFrame, orient: &str) -> Result<String, ExPolarsError> {
+    let names = df.get_column_names();
+    let columns = df
+        .get_columns()
+        .iter()
+        .map(|s| to_json_values(s, true))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut buf = String::with_capacity(81920);
+    match orient {
+        "columns" => {
+            let fields = names
+                .iter()
+                .zip(columns.into_iter())
+                .map(|(name, values)| (*name, Value::Array(values)));
+            write_json_object(&mut buf, fields)?;
+        }
+        "records" | "ndjson" => {
+            let records = orient == "records";
+            let mut iters: Vec<_> = columns.into_iter().map(|c| c.into_iter()).collect();
+            if records {
+                buf.push('[');
+            }
+            for i in 0..df.height() {
+                if records && i > 0 {
+                    buf.push(',');
+                }
+                let row = names
+                    .iter()
+                    .zip(iters.iter_mut())
+                    .map(|(name, it)| (*name, it.next().unwrap_or(Value::Null)));
+                write_json_object(&mut buf, row)?;
+                if !records {
+                    buf.push('\n');
+                }
+            }
+            if records {
+                buf.push(']');
+            }
+        }
+        _ => {
+            return Err(ExPolarsError::Other(format!(
+                "json orient {} not supported",
+                orient
+            )))
+        }
+    }
+    Ok(buf)
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn df_to_json(data: ExDataFrame, orient: &str) -> Result<String, ExPolarsError> {
+    df_read!(data, df, { to_json(&df, orient) })
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn df_to_json_file(
+    data: ExDataFrame,
+    filename: &str,
+    orient: &str,
+    compression: &str,
+) -> Result<(), ExPolarsError> {
+    df_read!(data, df, {
+        let json = to_json(&df, orient)?;
+        let mut f = Output::create(filename, compression)?;
+        f.write_all(json.as_bytes())?;
+        f.finish()
+    })
+}
+
+/// Encode the frame as a list of maps, one per row, keyed by column name.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn df_to_rows(env: Env, data: ExDataFrame) -> Result<Vec<Term>, ExPolarsError> {
+    df_read!(data, df, {
+        let keys: Vec<Term> = df
+            .get_column_names()
+            .iter()
+            .map(|name| name.encode(env))
+            .collect();
+        let columns = df
+            .get_columns()
+            .iter()
+            .map(|s| to_terms(env, s))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut row = Vec::with_capacity(columns.len());
+        (0..df.height())
+            .map(|i| {
+                row.clear();
+                row.extend(columns.iter().map(|c| c[i]));
+                make_map(env, &keys, &row)
+            })
+            .collect()
+    })
+}
+
+/// Encode the frame as a map of column name to a list of values.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn df_to_columns_map(env: Env, data: ExDataFrame) -> Result<Term, ExPolarsError> {
+    df_read!(data, df, {
+        let mut keys = Vec::with_capacity(df.width());
+        let mut values = Vec::with_capacity(df.width());
+        for s in df.get_columns() {
+            keys.push(s.name().encode(env));
+            values.push(to_terms(env, s)?.encode(env));
+        }
+        make_map(env, &keys, &values)
+    })
+}
+
+pub(crate) fn parquet_compression(compression: &str) -> Result<Compression, ExPolarsError> {
+    match compression {
+        "uncompressed" => Ok(Compression::UNCOMPRESSED),
+        "snappy" => Ok(Compression::SNAPPY),
+        "gzip" => Ok(Compression::GZIP),
+        "lz4" => Ok(Compression::LZ4),
+        "zstd" => Ok(Compression::ZSTD),
+        c => Err(ExPolarsError::Other(format!(
+            "Compression {} not supported",
+            c
+        ))),
+    }
+}
+
+pub(crate) fn write_parquet<W: 'static + ParquetWriter>(
+    df: &DataFrame,
+    w: W,
+    compression: &str,
+    row_group_size: usize,
+    statistics: bool,
+) -> Result<(), ExPolarsError> {
+    let compression = parquet_compression(compression)?;
+    let row_group_size = row_group_size.max(1);
+    let mut writer = FrameWriter::try_new(w, &df.schema(), compression, statistics)?;
+    let height = df.height();
+    let mut offset = 0;
+    while offset < height {
+        let length = row_group_size.min(height - offset);
+        writer.write(&df.slice(offset, length)?)?;
+        offset += length;
+    }
+    writer.close()
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn df_to_parquet<'a>(
+    env: Env<'a>,
+    data: ExDataFrame,
+    compression: &str,
+    row_group_size: usize,
+    statistics: bool,
+) -> Result<Binary<'a>, ExPolarsError> {
+    df_read!(data, df, {
+        let buf = SharedBuffer::default();
+        write_parquet(&df, buf.clone(), compression, row_group_size, statistics)?;
+        to_binary(env, &buf.data())
+    })
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn df_to_parquet_file(
+    data: ExDataFrame,
+    filename: &str,
+    compression: &str,
+    row_group_size: usize,
+    statistics: bool,
+) -> Result<(), ExPolarsError> {
+    df_read!(data, df, {
+        let f = File::create(filename)?;
+        write_parquet(&df, f, compression, row_group_size, statistics)
+    })
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn df_to_ipc(env: Env, data: ExDataFrame) -> Result<Binary, ExPolarsError> {
+    df_write!(data, df, {
+        let mut buf: Vec<u8> = Vec::with_capacity(81920);
+        IPCWriter::new(&mut buf).finish(&mut *df)?;
+        to_binary(env, &buf)
+    })
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn df_to_ipc_file(data: ExDataFrame, filename: &str) -> Result<(), ExPolarsError> {
+    df_write!(data, df, {
+        let mut f = File::create(filename)?;
+        IPCWriter::new(&mut f).finish(&mut *df)?;
         Ok(())
     })
 }
 
+fn write_ipc_stream<W: Write>(df: &DataFrame, w: W) -> Result<(), ExPolarsError> {
+    let mut writer = StreamWriter::try_new(w, &df.schema())?;
+    for batch in df.as_record_batches()? {
+        writer.write(&batch)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+fn read_ipc_stream<R: Read>(r: R) -> Result<DataFrame, ExPolarsError> {
+    let reader = StreamReader::try_new(r)?;
+    let schema = reader.schema();
+    let batches = reader.collect::<Result<Vec<_>, _>>()?;
+    batches_to_df(&schema, &batches)
+}
+
+/// Serialize `DataFrame` as an Arrow IPC stream, e.g. to send it to another node
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn df_to_binary(env: Env, data: ExDataFrame) -> Result<Binary, ExPolarsError> {
+    df_read!(data, df, {
+        let mut buf: Vec<u8> = Vec::with_capacity(81920);
+        write_ipc_stream(&df, &mut buf)?;
+        to_binary(env, &buf)
+    })
+}
+
+/// Deserialize `DataFrame` from an Arrow IPC stream created by `df_to_binary`
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn df_from_binary(data: Binary) -> Result<ExDataFrame, ExPolarsError> {
+    let df = read_ipc_stream(data.as_slice())?;
+    Ok(ExDataFrame::new(df))
+}
+
+/// Build a frame from a list of maps, inferring dtypes unless `schema` gives them.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn df_from_rows(
+    rows: Vec<Term>,
+    schema: Option<Vec<(String, u8)>>,
+) -> Result<ExDataFrame, ExPolarsError> {
+    Ok(ExDataFrame::new(rows_to_df(rows, schema)?))
+}
+
+/// Build a frame from a map of column name to a list of values.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn df_from_columns(
+    data: Term,
+    schema: Option<Vec<(String, u8)>>,
+) -> Result<ExDataFrame, ExPolarsError> {
+    Ok(ExDataFrame::new(columns_to_df(data, schema)?))
+}
+
 #[rustler::nif]
 /// Format `DataFrame` as String
 pub fn df_as_str(data: ExDataFrame) -> Result<String, ExPolarsError> {
     df_read!(data, df, { Ok(format!("{:?}", &*df)) })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_add(data: ExDataFrame, s: ExSeries) -> Result<ExDataFrame, ExPolarsError> {
     df_read!(data, df, {
         let new_df = (&*df + &s.inner.0)?;
@@ -139,7 +804,7 @@ pub fn df_add(data: ExDataFrame, s: ExSeries) -> Result<ExDataFrame, ExPolarsErr
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_sub(data: ExDataFrame, s: ExSeries) -> Result<ExDataFrame, ExPolarsError> {
     df_read!(data, df, {
         let new_df = (&*df - &s.inner.0)?;
@@ -147,7 +812,7 @@ pub fn df_sub(data: ExDataFrame, s: ExSeries) -> Result<ExDataFrame, ExPolarsErr
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_div(data: ExDataFrame, s: ExSeries) -> Result<ExDataFrame, ExPolarsError> {
     df_read!(data, df, {
         let new_df = (&*df / &s.inner.0)?;
@@ -155,7 +820,7 @@ pub fn df_div(data: ExDataFrame, s: ExSeries) -> Result<ExDataFrame, ExPolarsErr
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_mul(data: ExDataFrame, s: ExSeries) -> Result<ExDataFrame, ExPolarsError> {
     df_read!(data, df, {
         let new_df = (&*df * &s.inner.0)?;
@@ -163,7 +828,7 @@ pub fn df_mul(data: ExDataFrame, s: ExSeries) -> Result<ExDataFrame, ExPolarsErr
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_rem(data: ExDataFrame, s: ExSeries) -> Result<ExDataFrame, ExPolarsError> {
     df_read!(data, df, {
         let new_df = (&*df % &s.inner.0)?;
@@ -171,7 +836,7 @@ pub fn df_rem(data: ExDataFrame, s: ExSeries) -> Result<ExDataFrame, ExPolarsErr
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_sample_n(
     data: ExDataFrame,
     n: usize,
@@ -183,7 +848,7 @@ pub fn df_sample_n(
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_sample_frac(
     data: ExDataFrame,
     frac: f64,
@@ -195,15 +860,15 @@ pub fn df_sample_frac(
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_rechunk(data: ExDataFrame) -> Result<(), ExPolarsError> {
     df_write!(data, df, {
-        (&mut *df).agg_chunks();
+        *df = df.agg_chunks();
         Ok(())
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_fill_none(data: ExDataFrame, strategy: &str) -> Result<ExDataFrame, ExPolarsError> {
     let strat = match strategy {
         "backward" => FillNoneStrategy::Backward,
@@ -211,7 +876,7 @@ pub fn df_fill_none(data: ExDataFrame, strategy: &str) -> Result<ExDataFrame, Ex
         "min" => FillNoneStrategy::Min,
         "max" => FillNoneStrategy::Max,
         "mean" => FillNoneStrategy::Mean,
-        s => return Err(ExPolarsError::Other(format!("Strategy {} not supported", s)).into()),
+        s => return Err(ExPolarsError::Other(format!("Strategy {} not supported", s))),
     };
     df_read!(data, df, {
         let new_df = df.fill_none(strat)?;
@@ -219,23 +884,49 @@ pub fn df_fill_none(data: ExDataFrame, strategy: &str) -> Result<ExDataFrame, Ex
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_join(
     data: ExDataFrame,
     other: ExDataFrame,
     left_on: Vec<&str>,
     right_on: Vec<&str>,
     how: &str,
+    suffix: &str,
 ) -> Result<ExDataFrame, ExPolarsError> {
-    let how = match how {
-        "left" => JoinType::Left,
-        "inner" => JoinType::Inner,
-        "outer" => JoinType::Outer,
-        _ => return Err(ExPolarsError::Other(format!("Join method {} not supported", how)).into()),
+    let join_type = match how {
+        "left" => Some(JoinType::Left),
+        "inner" => Some(JoinType::Inner),
+        "outer" => Some(JoinType::Outer),
+        "semi" | "anti" | "cross" => None,
+        _ => return Err(ExPolarsError::Other(format!("Join method {} not supported", how))),
     };
 
     df_read_read!(data, other, df, df1, {
-        let new_df = df.join(&*df1, left_on, right_on, how)?;
+        let new_df = match join_type {
+            Some(join_type) => {
+                let df1 = suffix_overlapping(&*df, df1.clone(), &right_on, suffix)?;
+                df.join(&df1, left_on, right_on, join_type)?
+            }
+            None if how == "cross" => cross_join(&*df, &*df1, suffix)?,
+            None => semi_anti_join(&*df, &*df1, left_on, right_on, how == "anti")?,
+        };
+        Ok(ExDataFrame::new(new_df))
+    })
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn df_join_asof(
+    data: ExDataFrame,
+    other: ExDataFrame,
+    left_on: &str,
+    right_on: &str,
+    strategy: &str,
+    tolerance: Option<f64>,
+    suffix: &str,
+) -> Result<ExDataFrame, ExPolarsError> {
+    let strategy = AsofStrategy::from_str(strategy)?;
+    df_read_read!(data, other, df, df1, {
+        let new_df = asof_join(&*df, &*df1, left_on, right_on, strategy, tolerance, suffix)?;
         Ok(ExDataFrame::new(new_df))
     })
 }
@@ -269,15 +960,10 @@ pub fn df_set_column_names(data: ExDataFrame, names: Vec<&str>) -> Result<(), Ex
 #[rustler::nif]
 pub fn df_dtypes(data: ExDataFrame) -> Result<Vec<u8>, ExPolarsError> {
     df_read!(data, df, {
-        let result = df
-            .dtypes()
+        df.dtypes()
             .iter()
-            .map(|arrow_dtype| {
-                let dt: DataType = arrow_dtype.into();
-                dt as u8
-            })
-            .collect();
-        Ok(result)
+            .map(|arrow_dtype| Ok(DataType::try_from(arrow_dtype)? as u8))
+            .collect()
     })
 }
 
@@ -301,7 +987,7 @@ pub fn df_width(data: ExDataFrame) -> Result<usize, ExPolarsError> {
     df_read!(data, df, { Ok(df.width()) })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_hstack_mut(data: ExDataFrame, cols: Vec<ExSeries>) -> Result<(), ExPolarsError> {
     let cols = to_series_collection(cols);
     df_write!(data, df, {
@@ -310,7 +996,7 @@ pub fn df_hstack_mut(data: ExDataFrame, cols: Vec<ExSeries>) -> Result<(), ExPol
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_hstack(data: ExDataFrame, cols: Vec<ExSeries>) -> Result<ExDataFrame, ExPolarsError> {
     let cols = to_series_collection(cols);
     df_read!(data, df, {
@@ -319,7 +1005,7 @@ pub fn df_hstack(data: ExDataFrame, cols: Vec<ExSeries>) -> Result<ExDataFrame,
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_vstack(data: ExDataFrame, other: ExDataFrame) -> Result<(), ExPolarsError> {
     df_write_read!(data, other, df, df1, {
         (&mut *df).vstack_mut(&df1)?;
@@ -335,7 +1021,7 @@ pub fn df_drop_in_place(data: ExDataFrame, name: &str) -> Result<ExSeries, ExPol
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_drop_nulls(
     data: ExDataFrame,
     subset: Option<Vec<String>>,
@@ -375,28 +1061,42 @@ pub fn df_column(data: ExDataFrame, name: &str) -> Result<ExSeries, ExPolarsErro
     })
 }
 
-#[rustler::nif]
-pub fn df_select(data: ExDataFrame, selection: Vec<&str>) -> Result<ExDataFrame, ExPolarsError> {
+/// Select columns by name or by expression
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn df_select(data: ExDataFrame, selection: Vec<ExExpr>) -> Result<ExDataFrame, ExPolarsError> {
     df_read!(data, df, {
-        let new_df = df.select(&selection)?;
+        let new_df = df
+            .clone()
+            .lazy()
+            .select(to_expr_collection(selection))
+            .collect()?;
         Ok(ExDataFrame::new(new_df))
     })
 }
 
-#[rustler::nif]
-pub fn df_filter(data: ExDataFrame, mask: ExSeries) -> Result<ExDataFrame, ExPolarsError> {
+/// Filter rows by a boolean mask series or by a predicate expression
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn df_filter(data: ExDataFrame, predicate: ExPredicate) -> Result<ExDataFrame, ExPolarsError> {
     df_read!(data, df, {
-        let filter_series = &mask.inner.0;
-        if let Ok(ca) = filter_series.bool() {
-            let new_df = df.filter(ca)?;
-            Ok(ExDataFrame::new(new_df))
-        } else {
-            Err(ExPolarsError::Other("Expected a boolean mask".into()))
+        match predicate {
+            ExPredicate::Mask(mask) => {
+                let filter_series = &mask.inner.0;
+                if let Ok(ca) = filter_series.bool() {
+                    let new_df = df.filter(ca)?;
+                    Ok(ExDataFrame::new(new_df))
+                } else {
+                    Err(ExPolarsError::Other("Expected a boolean mask".into()))
+                }
+            }
+            ExPredicate::Expr(e) => {
+                let new_df = df.clone().lazy().filter(e).collect()?;
+                Ok(ExDataFrame::new(new_df))
+            }
         }
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_take(data: ExDataFrame, indices: Vec<usize>) -> Result<ExDataFrame, ExPolarsError> {
     df_read!(data, df, {
         let new_df = df.take(&indices);
@@ -404,7 +1104,7 @@ pub fn df_take(data: ExDataFrame, indices: Vec<usize>) -> Result<ExDataFrame, Ex
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_take_with_series(
     data: ExDataFrame,
     indices: ExSeries,
@@ -416,7 +1116,7 @@ pub fn df_take_with_series(
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_sort_new(
     data: ExDataFrame,
     by_column: &str,
@@ -428,7 +1128,7 @@ pub fn df_sort_new(
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_sort_in_place(
     data: ExDataFrame,
     by_column: &str,
@@ -440,6 +1140,46 @@ pub fn df_sort_in_place(
     })
 }
 
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn df_sort_by(
+    data: ExDataFrame,
+    by: Vec<&str>,
+    descending: Vec<bool>,
+    nulls_last: Vec<bool>,
+) -> Result<ExDataFrame, ExPolarsError> {
+    df_read!(data, df, {
+        let new_df = sort_by(&*df, &by, &descending, &nulls_last)?;
+        Ok(ExDataFrame::new(new_df))
+    })
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn df_sort_by_in_place(
+    data: ExDataFrame,
+    by: Vec<&str>,
+    descending: Vec<bool>,
+    nulls_last: Vec<bool>,
+) -> Result<(), ExPolarsError> {
+    df_write!(data, df, {
+        let idx = arg_sort_by(&*df, &by, &descending, &nulls_last)?;
+        *df = df.take(&idx);
+        Ok(())
+    })
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn df_top_k(
+    data: ExDataFrame,
+    k: usize,
+    by: Vec<&str>,
+    bottom: bool,
+) -> Result<ExDataFrame, ExPolarsError> {
+    df_read!(data, df, {
+        let new_df = top_k(&*df, k, &by, bottom)?;
+        Ok(ExDataFrame::new(new_df))
+    })
+}
+
 #[rustler::nif]
 pub fn df_replace(data: ExDataFrame, col: &str, new_col: ExSeries) -> Result<(), ExPolarsError> {
     df_write!(data, df, {
@@ -500,7 +1240,7 @@ pub fn df_tail(data: ExDataFrame, length: Option<usize>) -> Result<ExDataFrame,
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_is_unique(data: ExDataFrame) -> Result<ExSeries, ExPolarsError> {
     df_read!(data, df, {
         let mask = df.is_unique()?;
@@ -508,7 +1248,7 @@ pub fn df_is_unique(data: ExDataFrame) -> Result<ExSeries, ExPolarsError> {
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_is_duplicated(data: ExDataFrame) -> Result<ExSeries, ExPolarsError> {
     df_read!(data, df, {
         let mask = df.is_unique()?;
@@ -516,7 +1256,7 @@ pub fn df_is_duplicated(data: ExDataFrame) -> Result<ExSeries, ExPolarsError> {
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_frame_equal(
     data: ExDataFrame,
     other: ExDataFrame,
@@ -532,7 +1272,7 @@ pub fn df_frame_equal(
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_groupby(
     data: ExDataFrame,
     by: Vec<&str>,
@@ -559,31 +1299,160 @@ pub fn df_groupby(
             "groups" => selection.groups(),
             "std" => selection.std(),
             "var" => selection.var(),
-            a => Err(PolarsError::Other(
-                format!("agg fn {} does not exists", a).into(),
-            )),
+            a => {
+                return Err(ExPolarsError::Other(format!(
+                    "agg fn {} does not exists",
+                    a
+                )))
+            }
         };
         Ok(ExDataFrame::new(result?))
     })
 }
 
-#[rustler::nif]
+/// Aggregate either `(column, [agg names])` pairs, or aggregation expressions
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_groupby_agg(
     data: ExDataFrame,
     by: Vec<&str>,
-    column_to_agg: Vec<(&str, Vec<&str>)>,
+    column_to_agg: Vec<ExAgg>,
 ) -> Result<ExDataFrame, ExPolarsError> {
+    let all_columns = column_to_agg
+        .iter()
+        .all(|agg| matches!(agg, ExAgg::Columns(_, _)));
     df_read!(data, df, {
-        let gb = df.groupby(&by)?;
-        let new_df = gb.agg(&column_to_agg)?;
+        let new_df = if all_columns {
+            let column_to_agg: Vec<(&str, Vec<&str>)> = column_to_agg
+                .into_iter()
+                .filter_map(|agg| match agg {
+                    ExAgg::Columns(name, aggs) => Some((name, aggs)),
+                    _ => None,
+                })
+                .collect();
+            let gb = df.groupby(&by)?;
+            gb.agg(&column_to_agg)?
+        } else {
+            // the pairs are still aggregated eagerly, the lazy DSL has no std or var, and lined
+            // up with the expression results by sorting both by the keys
+            let mut pairs = vec![];
+            let mut exprs = vec![];
+            // for each result column after the keys, whether it comes from an expression
+            let mut from_expr = vec![];
+            for agg in column_to_agg {
+                match agg {
+                    ExAgg::Columns(name, aggs) => {
+                        from_expr.extend(aggs.iter().map(|_| false));
+                        pairs.push((name, aggs));
+                    }
+                    ExAgg::Expr(e) => {
+                        from_expr.push(true);
+                        exprs.push(e);
+                    }
+                }
+            }
+            let (descending, nulls_last) = (vec![false; by.len()], vec![false; by.len()]);
+            let lazy = df.clone().lazy().groupby(by.clone()).agg(exprs).collect()?;
+            let mut result = sort_by(&lazy, &by, &descending, &nulls_last)?;
+            let mut pair_columns: Vec<String> = vec![];
+            if !pairs.is_empty() {
+                let eager = df.groupby(&by)?.agg(&pairs)?;
+                let eager = sort_by(&eager, &by, &descending, &nulls_last)?;
+                let columns = &eager.get_columns()[by.len()..];
+                pair_columns = columns.iter().map(|s| s.name().to_string()).collect();
+                result.hstack_mut(columns)?;
+            }
+            let lazy_names = lazy.get_column_names();
+            let mut expr_columns = lazy_names[by.len()..].iter().map(|name| name.to_string());
+            let mut pair_columns = pair_columns.into_iter();
+            let aggregated = from_expr
+                .into_iter()
+                .map(|is_expr| {
+                    if is_expr {
+                        expr_columns.next()
+                    } else {
+                        pair_columns.next()
+                    }
+                })
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| ExPolarsError::Internal("missing aggregation column".into()))?;
+            let columns: Vec<&str> = by
+                .iter()
+                .copied()
+                .chain(aggregated.iter().map(|c| c.as_str()))
+                .collect();
+            result.select(columns)?
+        };
         Ok(ExDataFrame::new(new_df))
     })
 }
 
-// TODO(tchen): groupby_apply(data: ExDataFrame, by: Vec<&str>, lambda: Fun) -> Result<ExDataFrame, ExPolarsError> not implemented
-// I don't know how to pass an elixir function to rust for execution
+/// Group by `by` and compute each `(name, aggregation expression)` as a column called `name`.
+/// With `sort` the result is sorted by the group keys, nulls last.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn df_groupby_exprs(
+    data: ExDataFrame,
+    by: Vec<&str>,
+    aggs: Vec<(String, ExExpr)>,
+    sort: bool,
+) -> Result<ExDataFrame, ExPolarsError> {
+    let aggs = aggs
+        .into_iter()
+        .map(|(name, agg)| agg.inner.alias(&name))
+        .collect();
+    df_read!(data, df, {
+        let mut new_df = df.clone().lazy().groupby(by.clone()).agg(aggs).collect()?;
+        if sort {
+            new_df = sort_by(&new_df, &by, &vec![false; by.len()], &vec![true; by.len()])?;
+        }
+        Ok(ExDataFrame::new(new_df))
+    })
+}
 
-#[rustler::nif]
+// groupby_apply is done on the elixir side: `df_partition_by` hands out the groups, the callback
+// runs in the calling process and `df_concat` stacks the results.
+
+/// Split the frame into one frame per group, in order of first appearance of the group keys.
+pub(crate) fn partition_by(df: &DataFrame, by: &[&str]) -> Result<Vec<DataFrame>, ExPolarsError> {
+    let gb = df.groupby(by.to_vec())?;
+    let mut groups: Vec<_> = gb.get_groups().iter().collect();
+    groups.sort_unstable_by_key(|(first, _)| *first);
+    Ok(groups.into_iter().map(|(_, idx)| df.take(idx)).collect())
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn df_partition_by(
+    data: ExDataFrame,
+    by: Vec<&str>,
+) -> Result<Vec<ExDataFrame>, ExPolarsError> {
+    df_read!(data, df, {
+        let frames = partition_by(&df, &by)?;
+        Ok(frames.into_iter().map(ExDataFrame::new).collect())
+    })
+}
+
+/// Stack frames with the same schema on top of each other.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn df_concat(frames: Vec<ExDataFrame>) -> Result<ExDataFrame, ExPolarsError> {
+    let mut iter = frames.iter();
+    let first = match iter.next() {
+        Some(first) => first,
+        None => {
+            return Err(ExPolarsError::Other(
+                "cannot concat an empty list of frames".into(),
+            ))
+        }
+    };
+    let mut acc = df_read!(first, df, { Ok(df.clone()) })?;
+    for other in iter {
+        df_read!(other, df, {
+            acc.vstack_mut(&df)?;
+            Ok(())
+        })?;
+    }
+    Ok(ExDataFrame::new(acc))
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_groupby_quantile(
     data: ExDataFrame,
     by: Vec<&str>,
@@ -598,7 +1467,7 @@ pub fn df_groupby_quantile(
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_pivot(
     data: ExDataFrame,
     by: Vec<String>,
@@ -617,9 +1486,12 @@ pub fn df_pivot(
             "median" => pivot.median(),
             "sum" => pivot.sum(),
             "count" => pivot.count(),
-            a => Err(PolarsError::Other(
-                format!("agg fn {} does not exists", a).into(),
-            )),
+            a => {
+                return Err(ExPolarsError::Other(format!(
+                    "agg fn {} does not exists",
+                    a
+                )))
+            }
         };
         Ok(ExDataFrame::new(result?))
     })
@@ -630,7 +1502,7 @@ pub fn df_clone(data: ExDataFrame) -> Result<ExDataFrame, ExPolarsError> {
     df_read!(data, df, { Ok(ExDataFrame::new(df.clone())) })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_explode(data: ExDataFrame, cols: Vec<String>) -> Result<ExDataFrame, ExPolarsError> {
     df_read!(data, df, {
         let new_df = df.explode(&cols)?;
@@ -638,7 +1510,7 @@ pub fn df_explode(data: ExDataFrame, cols: Vec<String>) -> Result<ExDataFrame, E
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_melt(
     data: ExDataFrame,
     id_vars: Vec<&str>,
@@ -650,7 +1522,7 @@ pub fn df_melt(
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_shift(data: ExDataFrame, periods: i32) -> Result<ExDataFrame, ExPolarsError> {
     df_read!(data, df, {
         let new_df = df.shift(periods)?;
@@ -658,7 +1530,7 @@ pub fn df_shift(data: ExDataFrame, periods: i32) -> Result<ExDataFrame, ExPolars
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_drop_duplicates(
     data: ExDataFrame,
     maintain_order: bool,
@@ -670,42 +1542,42 @@ pub fn df_drop_duplicates(
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_max(data: ExDataFrame) -> Result<ExDataFrame, ExPolarsError> {
     df_read!(data, df, { Ok(ExDataFrame::new(df.max())) })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_min(data: ExDataFrame) -> Result<ExDataFrame, ExPolarsError> {
     df_read!(data, df, { Ok(ExDataFrame::new(df.min())) })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_sum(data: ExDataFrame) -> Result<ExDataFrame, ExPolarsError> {
     df_read!(data, df, { Ok(ExDataFrame::new(df.sum())) })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_mean(data: ExDataFrame) -> Result<ExDataFrame, ExPolarsError> {
     df_read!(data, df, { Ok(ExDataFrame::new(df.mean())) })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_stdev(data: ExDataFrame) -> Result<ExDataFrame, ExPolarsError> {
     df_read!(data, df, { Ok(ExDataFrame::new(df.std())) })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_var(data: ExDataFrame) -> Result<ExDataFrame, ExPolarsError> {
     df_read!(data, df, { Ok(ExDataFrame::new(df.var())) })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_median(data: ExDataFrame) -> Result<ExDataFrame, ExPolarsError> {
     df_read!(data, df, { Ok(ExDataFrame::new(df.median())) })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_quantile(data: ExDataFrame, quant: f64) -> Result<ExDataFrame, ExPolarsError> {
     df_read!(data, df, {
         let new_df = df.quantile(quant)?;
@@ -713,10 +1585,116 @@ pub fn df_quantile(data: ExDataFrame, quant: f64) -> Result<ExDataFrame, ExPolar
     })
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn df_to_dummies(data: ExDataFrame) -> Result<ExDataFrame, ExPolarsError> {
     df_read!(data, df, {
         let new_df = df.to_dummies()?;
         Ok(ExDataFrame::new(new_df))
     })
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rustler::{Nif, SchedulerFlags};
+
+    fn frame() -> DataFrame {
+        DataFrame::new(vec![
+            Series::new("id", &[1i64, 2, 3]),
+            Series::new("name", &[Some("a"), None, Some("c")]),
+            Series::new("score", &[Some(0.5), Some(1.5), None]),
+        ])
+        .unwrap()
+    }
+
+    fn ipc_file(df: &mut DataFrame) -> Vec<u8> {
+        let mut buf = Vec::new();
+        IPCWriter::new(&mut buf).finish(df).unwrap();
+        buf
+    }
+
+    #[test]
+    fn read_ipc_keeps_projected_columns_in_order() {
+        let mut df = frame();
+        let buf = ipc_file(&mut df);
+
+        let read = read_ipc(Cursor::new(&buf), Some(vec![2, 0])).unwrap();
+        assert_eq!(read.get_column_names(), vec!["score", "id"]);
+        assert!(read.frame_equal_missing(&df.select(&["score", "id"]).unwrap()));
+
+        let read = read_ipc(Cursor::new(&buf), None).unwrap();
+        assert!(read.frame_equal_missing(&df));
+
+        assert!(read_ipc(Cursor::new(&buf), Some(vec![3])).is_err());
+    }
+
+    #[test]
+    fn ipc_stream_round_trips_every_chunk() {
+        let mut df = frame();
+        df.vstack_mut(&frame()).unwrap();
+        assert_eq!(df.n_chunks().unwrap(), 2);
+
+        let mut buf = Vec::new();
+        write_ipc_stream(&df, &mut buf).unwrap();
+        let read = read_ipc_stream(buf.as_slice()).unwrap();
+        assert_eq!(read.schema(), df.schema());
+        assert!(read.frame_equal_missing(&df));
+
+        let empty = frame().slice(0, 0).unwrap();
+        let mut buf = Vec::new();
+        write_ipc_stream(&empty, &mut buf).unwrap();
+        let read = read_ipc_stream(buf.as_slice()).unwrap();
+        assert_eq!(read.schema(), empty.schema());
+        assert_eq!(read.height(), 0);
+
+        assert!(read_ipc_stream(&buf[..buf.len() / 2]).is_err());
+    }
+
+    #[test]
+    fn read_ipc_projects_an_empty_frame() {
+        let mut df = frame().slice(0, 0).unwrap();
+        let buf = ipc_file(&mut df);
+        let read = read_ipc(Cursor::new(&buf), Some(vec![1])).unwrap();
+        assert_eq!(read.height(), 0);
+        assert_eq!(read.get_column_names(), vec!["name"]);
+    }
+
+    #[test]
+    fn stacking_runs_on_dirty_cpu_schedulers() {
+        let dirty_cpu = SchedulerFlags::DirtyCpu as u32;
+        assert_eq!(<df_hstack as Nif>::FLAGS, dirty_cpu);
+        assert_eq!(<df_hstack_mut as Nif>::FLAGS, dirty_cpu);
+        assert_eq!(<df_vstack as Nif>::FLAGS, dirty_cpu);
+        assert_eq!(<df_concat as Nif>::FLAGS, dirty_cpu);
+    }
+
+    #[test]
+    fn partition_by_keeps_the_order_of_first_appearance() {
+        let df = DataFrame::new(vec![
+            Series::new("cohort", &[Some("b"), Some("a"), None, Some("b")]),
+            Series::new("value", &[1i64, 2, 3, 4]),
+        ])
+        .unwrap();
+        let groups = partition_by(&df, &["cohort"]).unwrap();
+        let values: Vec<Vec<Option<i64>>> = groups
+            .iter()
+            .map(|g| {
+                g.column("value")
+                    .unwrap()
+                    .i64()
+                    .unwrap()
+                    .into_iter()
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            values,
+            vec![vec![Some(1), Some(4)], vec![Some(2)], vec![Some(3)]]
+        );
+
+        assert!(partition_by(&df.slice(0, 0).unwrap(), &["cohort"])
+            .unwrap()
+            .is_empty());
+        assert!(partition_by(&df, &["missing"]).is_err());
+    }
+}
@@ -0,0 +1,646 @@
+// Hive partitioned datasets: a directory tree like
+// `root/date=2026-10-01/region=eu/part-0.parquet`, where the `key=value` directories hold the
+// values of the partition columns of the files below them.
+
+use chrono::NaiveDate;
+use polars::prelude::*;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::result::Result;
+
+use crate::dataframe::{parquet_compression, partition_by, write_parquet};
+use crate::scan::{
+    filter_select, read_parquet_pruned, read_parquet_schema, Scalar, ScanPredicate, ValueRanges,
+};
+use crate::series::{date32_to_naive, date64_to_naive};
+use crate::ExPolarsError;
+
+/// The directory name hive uses for null partition values.
+const DEFAULT_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+const EXTENSIONS: [&str; 2] = ["parquet", "csv"];
+
+#[derive(Clone, Copy, PartialEq)]
+enum PartitionType {
+    Int,
+    Float,
+    Date,
+    Bool,
+    Str,
+}
+
+struct DatasetFile {
+    path: PathBuf,
+    // partition values in key order, `None` for nulls
+    values: Vec<Option<String>>,
+}
+
+/// Data files below `dir` in path order, skipping hidden and `_` prefixed entries like
+/// `_SUCCESS`.
+fn discover(dir: &Path, out: &mut Vec<PathBuf>) -> Result<(), ExPolarsError> {
+    let mut entries = fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|e| e.path());
+    for entry in entries {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') || name.starts_with('_') {
+            continue;
+        }
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            discover(&path, out)?;
+        } else if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+            if EXTENSIONS.contains(&ext) {
+                out.push(path);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c.is_control() || "\"#%'*/:=?\\{[]^".contains(c) {
+            let mut buf = [0; 4];
+            for b in c.encode_utf8(&mut buf).bytes() {
+                out.push_str(&format!("%{:02X}", b));
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn unescape(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = value.get(i + 1..i + 3).map(|h| u8::from_str_radix(h, 16));
+        match (bytes[i], hex) {
+            (b'%', Some(Ok(b))) => {
+                out.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Split the `key=value` directories between `root` and the file.
+fn partition_values(root: &Path, path: &Path) -> Vec<(String, Option<String>)> {
+    let dirs = match path.strip_prefix(root).ok().and_then(|p| p.parent()) {
+        Some(dirs) => dirs,
+        None => return vec![],
+    };
+    dirs.components()
+        .filter_map(|c| {
+            let segment = c.as_os_str().to_str()?;
+            let mut parts = segment.splitn(2, '=');
+            let key = parts.next()?;
+            let value = parts.next()?;
+            let value = if value == DEFAULT_PARTITION {
+                None
+            } else {
+                Some(unescape(value))
+            };
+            Some((unescape(key), value))
+        })
+        .collect()
+}
+
+/// Whether `v` is written the way a number is written back: an optional minus sign and digits
+/// with at most one decimal point, without a redundant leading zero. So zip codes like "01234"
+/// and values like "1e3", "inf" or "NaN" aren't numbers.
+fn is_plain_number(v: &str) -> bool {
+    let digits = v.strip_prefix('-').unwrap_or(v);
+    let int_part = digits.split('.').next().unwrap_or_default();
+    !int_part.is_empty()
+        && !(int_part.len() > 1 && int_part.starts_with('0'))
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+}
+
+/// Days since the epoch of a `%Y-%m-%d` date, if it's written the way `partition_dir_value`
+/// writes it back, so "2026-1-1" isn't a date.
+fn parse_date(v: &str) -> Option<i32> {
+    let date = NaiveDate::parse_from_str(v, "%Y-%m-%d").ok()?;
+    if date.to_string() != v {
+        return None;
+    }
+    Some((date - NaiveDate::from_ymd(1970, 1, 1)).num_days() as i32)
+}
+
+/// The narrowest type all non-null partition values parse as. Values that wouldn't be written
+/// back the same way, see `is_plain_number` and `parse_date`, keep the column a string.
+fn infer_type<'a>(mut values: impl Iterator<Item = &'a str> + Clone) -> PartitionType {
+    if values.clone().next().is_none() {
+        PartitionType::Str
+    } else if values
+        .clone()
+        .all(|v| is_plain_number(v) && v.parse::<i64>().is_ok())
+    {
+        PartitionType::Int
+    } else if values
+        .clone()
+        .all(|v| is_plain_number(v) && v.parse::<f64>().is_ok())
+    {
+        PartitionType::Float
+    } else if values.clone().all(|v| parse_date(v).is_some()) {
+        PartitionType::Date
+    } else if values.all(|v| v == "true" || v == "false") {
+        PartitionType::Bool
+    } else {
+        PartitionType::Str
+    }
+}
+
+fn scalar(value: &str, ty: PartitionType) -> Option<Scalar> {
+    match ty {
+        PartitionType::Int => value.parse().ok().map(Scalar::Int),
+        PartitionType::Float => value.parse().ok().map(Scalar::Float),
+        // as days, like the statistics of date columns in parquet files
+        PartitionType::Date => parse_date(value).map(|v| Scalar::Int(v as i64)),
+        PartitionType::Bool => value.parse().ok().map(Scalar::Bool),
+        PartitionType::Str => Some(Scalar::Str(value.to_string())),
+    }
+}
+
+fn partition_column(name: &str, value: Option<&str>, ty: PartitionType, height: usize) -> Series {
+    match ty {
+        PartitionType::Int => {
+            let v: Option<i64> = value.and_then(|v| v.parse().ok());
+            Series::new(name, vec![v; height].as_slice())
+        }
+        PartitionType::Float => {
+            let v: Option<f64> = value.and_then(|v| v.parse().ok());
+            Series::new(name, vec![v; height].as_slice())
+        }
+        PartitionType::Date => {
+            let v = value.and_then(parse_date);
+            Date32Chunked::new_from_opt_slice(name, &vec![v; height]).into_series()
+        }
+        PartitionType::Bool => {
+            let v: Option<bool> = value.and_then(|v| v.parse().ok());
+            Series::new(name, vec![v; height].as_slice())
+        }
+        PartitionType::Str => Series::new(name, vec![value; height].as_slice()),
+    }
+}
+
+/// Read a csv file with only the columns in `needed` that it has, in file order, the same
+/// columns `read_parquet_pruned` reads from a parquet file, so the frames of both stack. With
+/// `header_only` the frame has no rows and only the first row is parsed, for the dtypes.
+fn read_csv_projected(
+    path: &Path,
+    needed: Option<&[&str]>,
+    header_only: bool,
+) -> Result<DataFrame, ExPolarsError> {
+    let rows = if header_only { Some(1) } else { None };
+    let df = match needed {
+        None => CsvReader::new(File::open(path)?)
+            .with_stop_after_n_rows(rows)
+            .finish()?,
+        Some(needed) => {
+            // only the first row is parsed to get the column names
+            let head = CsvReader::new(File::open(path)?)
+                .with_stop_after_n_rows(Some(1))
+                .finish()?;
+            let columns = head
+                .get_column_names()
+                .into_iter()
+                .filter(|name| needed.contains(name))
+                .map(|name| name.to_string())
+                .collect();
+            CsvReader::new(File::open(path)?)
+                .with_stop_after_n_rows(rows)
+                .with_columns(Some(columns))
+                .finish()?
+        }
+    };
+    if header_only {
+        Ok(df.slice(0, 0)?)
+    } else {
+        Ok(df)
+    }
+}
+
+/// Read every parquet and csv file below `root`, adding the partition columns after the columns
+/// of the files. Files whose partition values `predicate` excludes aren't opened at all.
+pub(crate) fn read_dataset(
+    root: &str,
+    columns: Option<Vec<String>>,
+    predicate: Option<ScanPredicate>,
+) -> Result<DataFrame, ExPolarsError> {
+    let root = Path::new(root);
+    let mut paths = vec![];
+    discover(root, &mut paths)?;
+    if paths.is_empty() {
+        return Err(ExPolarsError::Other(format!(
+            "no parquet or csv files found under {}",
+            root.display()
+        )));
+    }
+
+    let mut keys: Option<Vec<String>> = None;
+    let mut files = Vec::with_capacity(paths.len());
+    for path in paths {
+        let (file_keys, values): (Vec<_>, Vec<_>) =
+            partition_values(root, &path).into_iter().unzip();
+        match &keys {
+            Some(keys) if *keys != file_keys => {
+                return Err(ExPolarsError::Other(format!(
+                    "partition keys of {} are {:?}, expected {:?}",
+                    path.display(),
+                    file_keys,
+                    keys
+                )))
+            }
+            Some(_) => {}
+            None => keys = Some(file_keys),
+        }
+        files.push(DatasetFile { path, values });
+    }
+    let keys = keys.unwrap_or_default();
+    let types: Vec<PartitionType> = (0..keys.len())
+        .map(|i| infer_type(files.iter().filter_map(|f| f.values[i].as_deref())))
+        .collect();
+
+    let selected: Vec<&DatasetFile> = files
+        .iter()
+        .filter(|file| {
+            let ranges: ValueRanges = keys
+                .iter()
+                .zip(types.iter())
+                .zip(file.values.iter())
+                .filter_map(|((key, ty), value)| {
+                    let v = scalar(value.as_deref()?, *ty)?;
+                    Some((key.clone(), (v.clone(), v)))
+                })
+                .collect();
+            predicate.as_ref().map_or(true, |p| !p.excludes(&ranges))
+        })
+        .collect();
+    // when every file is pruned the result is empty, only the columns of the first are read
+    let (selected, schema_only) = if selected.is_empty() {
+        (vec![&files[0]], true)
+    } else {
+        (selected, false)
+    };
+
+    // the partition columns aren't stored in the files
+    let file_columns: Option<Vec<String>> = columns
+        .as_ref()
+        .map(|c| c.iter().filter(|c| !keys.contains(c)).cloned().collect())
+        .filter(|c: &Vec<String>| !c.is_empty());
+    let csv_columns: Option<Vec<&str>> = file_columns.as_ref().map(|columns| {
+        let mut needed: Vec<&str> = columns.iter().map(|c| c.as_str()).collect();
+        if let Some(p) = &predicate {
+            needed.extend(p.columns().iter().map(|c| c.as_str()));
+        }
+        needed
+    });
+
+    let mut acc: Option<DataFrame> = None;
+    for file in selected {
+        let mut df = match file.path.extension().and_then(|e| e.to_str()) {
+            Some("parquet") if schema_only => {
+                read_parquet_schema(&file.path, file_columns.as_deref(), predicate.as_ref())?
+            }
+            Some("parquet") => {
+                read_parquet_pruned(&file.path, file_columns.as_deref(), predicate.as_ref())?
+            }
+            _ => read_csv_projected(&file.path, csv_columns.as_deref(), schema_only)?,
+        };
+        let partition_columns: Vec<Series> = keys
+            .iter()
+            .zip(types.iter())
+            .zip(file.values.iter())
+            .map(|((key, ty), value)| partition_column(key, value.as_deref(), *ty, df.height()))
+            .collect();
+        df.hstack_mut(&partition_columns)?;
+        match &mut acc {
+            Some(acc) => {
+                acc.vstack_mut(&df)?;
+            }
+            None => acc = Some(df),
+        }
+    }
+    let df = acc.ok_or_else(|| ExPolarsError::Internal("no dataset files read".into()))?;
+    filter_select(df.lazy(), columns, predicate)
+}
+
+/// A float the way `infer_type` reads it back as one. Whole floats are written without a
+/// decimal point, `1` for `1.0`, so one is added. NaN and infinities are read back as strings.
+fn float_dir_value(v: String) -> String {
+    if v.bytes().all(|b| b.is_ascii_digit() || b == b'-') {
+        v + ".0"
+    } else {
+        v
+    }
+}
+
+/// The directory name of the partition value in the first row of `s`.
+fn partition_dir_value(s: &Series) -> Result<String, ExPolarsError> {
+    let value = match s.dtype() {
+        ArrowDataType::Utf8 => s.utf8()?.get(0).map(|v| v.to_string()),
+        ArrowDataType::Float32 => s.f32()?.get(0).map(|v| float_dir_value(v.to_string())),
+        ArrowDataType::Float64 => s.f64()?.get(0).map(|v| float_dir_value(v.to_string())),
+        ArrowDataType::Date32(_) => match s.date32()?.get(0) {
+            Some(v) => Some(date32_to_naive(v)?.to_string()),
+            None => None,
+        },
+        ArrowDataType::Date64(_) => match s.date64()?.get(0) {
+            Some(v) => Some(date64_to_naive(v)?.to_string()),
+            None => None,
+        },
+        _ => s.cast::<Utf8Type>()?.utf8()?.get(0).map(|v| v.to_string()),
+    };
+    Ok(match value {
+        Some(v) => escape(&v),
+        None => DEFAULT_PARTITION.to_string(),
+    })
+}
+
+/// Empty `root` for a new dataset. Files left from an earlier write would be read back with the
+/// new ones, so a non-empty `root` is an error unless `mode` is "overwrite", which removes it.
+fn clear_root(root: &Path, mode: &str) -> Result<(), ExPolarsError> {
+    let overwrite = match mode {
+        "error" => false,
+        "overwrite" => true,
+        _ => {
+            return Err(ExPolarsError::Other(format!(
+                "dataset write mode {} not supported",
+                mode
+            )))
+        }
+    };
+    if !root.exists() || fs::read_dir(root)?.next().is_none() {
+        return Ok(());
+    }
+    if overwrite {
+        fs::remove_dir_all(root)?;
+        Ok(())
+    } else {
+        Err(ExPolarsError::Other(format!(
+            "{} is not empty, write with mode overwrite to replace it",
+            root.display()
+        )))
+    }
+}
+
+/// Write `df` below `root`, one file per distinct combination of the `by` columns, which are
+/// stored in the directory names instead of the files. Returns the paths of the written files.
+pub(crate) fn write_dataset(
+    df: &DataFrame,
+    root: &str,
+    by: &[&str],
+    format: &str,
+    compression: &str,
+    mode: &str,
+) -> Result<Vec<String>, ExPolarsError> {
+    let ext = match format {
+        "parquet" => {
+            // checked before anything is removed
+            parquet_compression(compression)?;
+            format
+        }
+        "csv" => format,
+        _ => {
+            return Err(ExPolarsError::Other(format!(
+                "dataset format {} not supported",
+                format
+            )))
+        }
+    };
+    let frames = if by.is_empty() {
+        vec![df.clone()]
+    } else {
+        partition_by(df, by)?
+    };
+    clear_root(Path::new(root), mode)?;
+
+    let mut paths = Vec::with_capacity(frames.len());
+    for mut frame in frames {
+        let mut dir = PathBuf::from(root);
+        for key in by {
+            let value = partition_dir_value(frame.column(key)?)?;
+            dir.push(format!("{}={}", escape(key), value));
+        }
+        for key in by {
+            frame.drop_in_place(key)?;
+        }
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("part-0.{}", ext));
+        let f = File::create(&path)?;
+        match ext {
+            "parquet" => write_parquet(&frame, f, compression, 1_000_000, true)?,
+            _ => {
+                let mut f = f;
+                CsvWriter::new(&mut f).finish(&mut frame)?;
+            }
+        }
+        paths.push(path.to_string_lossy().into_owned());
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn partition_values_round_trip_as_written() {
+        let root = std::env::temp_dir().join(format!("expolars-dataset-{}", std::process::id()));
+        let root_path = root.to_str().unwrap();
+        let df = DataFrame::new(vec![
+            Series::new("zip", &["01234", "98765", "01234"]),
+            Series::new("score", &["NaN", "1.5", "inf"]),
+            Series::new("n", &[1i64, 2, 3]),
+        ])
+        .unwrap();
+        write_dataset(
+            &df,
+            root_path,
+            &["zip", "score"],
+            "csv",
+            "uncompressed",
+            "error",
+        )
+        .unwrap();
+        let read = read_dataset(root_path, None, None);
+        fs::remove_dir_all(&root).unwrap();
+        let read = read.unwrap();
+
+        assert_eq!(read.column("zip").unwrap().dtype(), &ArrowDataType::Utf8);
+        assert_eq!(read.column("score").unwrap().dtype(), &ArrowDataType::Utf8);
+        let zips: Vec<_> = read
+            .column("zip")
+            .unwrap()
+            .utf8()
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(zips, vec![Some("01234"), Some("01234"), Some("98765")]);
+        let scores: Vec<_> = read
+            .column("score")
+            .unwrap()
+            .utf8()
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(scores, vec![Some("NaN"), Some("inf"), Some("1.5")]);
+    }
+
+    #[test]
+    fn dates_and_floats_round_trip_with_their_dtypes() {
+        let root =
+            std::env::temp_dir().join(format!("expolars-dataset-typed-{}", std::process::id()));
+        let root_path = root.to_str().unwrap();
+        let df = DataFrame::new(vec![
+            Date32Chunked::new_from_slice("date", &[18000, 18001, 18000]).into_series(),
+            Series::new("ratio", &[1.0f64, 2.5, 1.0]),
+            Series::new("n", &[1i64, 2, 3]),
+        ])
+        .unwrap();
+        write_dataset(
+            &df,
+            root_path,
+            &["date", "ratio"],
+            "csv",
+            "uncompressed",
+            "error",
+        )
+        .unwrap();
+        let read = read_dataset(root_path, None, None);
+        fs::remove_dir_all(&root).unwrap();
+        let read = read.unwrap();
+
+        let dates: Vec<_> = read
+            .column("date")
+            .unwrap()
+            .date32()
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(dates, vec![Some(18000), Some(18000), Some(18001)]);
+        let ratios: Vec<_> = read
+            .column("ratio")
+            .unwrap()
+            .f64()
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(ratios, vec![Some(1.0), Some(1.0), Some(2.5)]);
+    }
+
+    #[test]
+    fn excluded_partitions_are_never_opened() {
+        let root =
+            std::env::temp_dir().join(format!("expolars-dataset-pruned-{}", std::process::id()));
+        let root_path = root.to_str().unwrap();
+        let df = DataFrame::new(vec![
+            Series::new("year", &[2020i64, 2021, 2020]),
+            Series::new("n", &[1i64, 2, 3]),
+        ])
+        .unwrap();
+        let paths = write_dataset(&df, root_path, &["year"], "parquet", "snappy", "error").unwrap();
+        assert_eq!(paths.len(), 2);
+        // reading the 2021 partition now fails
+        let pruned = paths.iter().find(|p| p.contains("year=2021")).unwrap();
+        fs::write(pruned, b"not parquet").unwrap();
+
+        let read = read_dataset(root_path, None, Some(ScanPredicate::int_eq("year", 2020)));
+        // no partition matches, only the footer of the first file is read
+        let empty = read_dataset(root_path, None, Some(ScanPredicate::int_eq("year", 1999)));
+        let unpruned = read_dataset(root_path, None, None);
+        fs::remove_dir_all(&root).unwrap();
+
+        let read = read.unwrap();
+        assert_eq!(read.get_column_names(), vec!["n", "year"]);
+        let ns: Vec<_> = read
+            .column("n")
+            .unwrap()
+            .i64()
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(ns, vec![Some(1), Some(3)]);
+
+        let empty = empty.unwrap();
+        assert_eq!(empty.get_column_names(), vec!["n", "year"]);
+        assert_eq!(empty.height(), 0);
+        assert_eq!(empty.column("n").unwrap().dtype(), &ArrowDataType::Int64);
+        assert_eq!(empty.column("year").unwrap().dtype(), &ArrowDataType::Int64);
+
+        assert!(unpruned.is_err());
+    }
+
+    #[test]
+    fn rewriting_a_dataset_needs_overwrite_and_leaves_no_stale_partitions() {
+        let root =
+            std::env::temp_dir().join(format!("expolars-dataset-rewrite-{}", std::process::id()));
+        let root_path = root.to_str().unwrap();
+        let frame = |years: &[i64]| {
+            DataFrame::new(vec![
+                Series::new("year", years),
+                Series::new("n", vec![1i64; years.len()].as_slice()),
+            ])
+            .unwrap()
+        };
+        let write = |df: &DataFrame, mode: &str| {
+            write_dataset(df, root_path, &["year"], "parquet", "snappy", mode)
+        };
+        write(&frame(&[2020, 2021]), "error").unwrap();
+        let refused = write(&frame(&[2022]), "error");
+        let bad_mode = write(&frame(&[2022]), "append");
+        let rewritten = write(&frame(&[2022]), "overwrite");
+        let read = read_dataset(root_path, None, None);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(refused.is_err());
+        assert!(bad_mode.is_err());
+        assert_eq!(rewritten.unwrap().len(), 1);
+        let years: Vec<_> = read
+            .unwrap()
+            .column("year")
+            .unwrap()
+            .i64()
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(years, vec![Some(2022)]);
+    }
+
+    #[test]
+    fn dates_are_inferred_from_iso_dates_only() {
+        let ty = |values: &[&str]| infer_type(values.iter().copied());
+        assert!(ty(&["2026-10-01", "1969-12-31"]) == PartitionType::Date);
+        assert!(ty(&["2026-10-01", "eu"]) == PartitionType::Str);
+        assert!(ty(&["2026-1-1"]) == PartitionType::Str);
+        assert!(ty(&["2026-02-30"]) == PartitionType::Str);
+        assert_eq!(parse_date("1970-01-02"), Some(1));
+        assert_eq!(float_dir_value(1.0f64.to_string()), "1.0");
+        assert_eq!(float_dir_value((-3.0f64).to_string()), "-3.0");
+        assert_eq!(float_dir_value(2.5f64.to_string()), "2.5");
+        assert_eq!(float_dir_value(f64::NAN.to_string()), "NaN");
+    }
+
+    #[test]
+    fn numbers_are_inferred_from_plain_values_only() {
+        let ty = |values: &[&str]| infer_type(values.iter().copied());
+        assert!(ty(&["1", "-20", "0"]) == PartitionType::Int);
+        assert!(ty(&["1", "2.5"]) == PartitionType::Float);
+        assert!(ty(&["01234", "98765"]) == PartitionType::Str);
+        assert!(ty(&["1.5", "NaN"]) == PartitionType::Str);
+        assert!(ty(&["inf"]) == PartitionType::Str);
+        assert!(ty(&["1e3"]) == PartitionType::Str);
+        assert!(ty(&["1.0", "-0.0"]) == PartitionType::Float);
+        assert!(ty(&["true", "false"]) == PartitionType::Bool);
+        assert!(ty(&[]) == PartitionType::Str);
+    }
+}
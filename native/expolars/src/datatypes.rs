@@ -1,14 +1,24 @@
 // This file is modified based on: https://github.com/ritchie46/polars/blob/master/py-polars/src/datatypes.rs
 
-use std::sync::RwLock;
+use std::sync::{Mutex, RwLock};
 use polars::prelude::*;
+use polars::lazy::frame::LazyFrame;
 use rustler::NifStruct;
 use rustler::resource::ResourceArc;
+use arrow::datatypes::IntervalUnit;
 
+use std::convert::TryFrom;
 use std::result::Result;
 
+use crate::csv_batch::CsvBatchReader;
+use crate::writer::BatchWriter;
+use crate::ExPolarsError;
+
 pub struct ExDataFrameRef(pub RwLock<DataFrame>);
 pub struct ExSeriesRef(pub Series);
+pub struct ExLazyFrameRef(pub LazyFrame);
+pub struct ExCsvBatchReaderRef(pub Mutex<CsvBatchReader>);
+pub struct ExBatchWriterRef(pub BatchWriter);
 
 #[derive(NifStruct)]
 #[module = "ExPolars.DataFrame"]
@@ -22,8 +32,27 @@ pub struct ExSeries {
     pub inner: ResourceArc<ExSeriesRef>,
 }
 
+#[derive(NifStruct)]
+#[module = "ExPolars.LazyFrame"]
+pub struct ExLazyFrame {
+    pub inner: ResourceArc<ExLazyFrameRef>,
+}
+
+#[derive(NifStruct)]
+#[module = "ExPolars.CsvBatchReader"]
+pub struct ExCsvBatchReader {
+    pub inner: ResourceArc<ExCsvBatchReaderRef>,
+}
+
+#[derive(NifStruct)]
+#[module = "ExPolars.BatchWriter"]
+pub struct ExBatchWriter {
+    pub inner: ResourceArc<ExBatchWriterRef>,
+}
+
 // Don't change the order of these!
 #[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum DataType {
     Int8,
     Int16,
@@ -44,6 +73,27 @@ pub enum DataType {
     DurationNanosecond,
     DurationMillisecond,
     Object,
+    Null,
+    Float16,
+    Time32Second,
+    Time32Millisecond,
+    Time64Microsecond,
+    TimestampSecond,
+    TimestampMillisecond,
+    TimestampMicrosecond,
+    TimestampNanosecond,
+    DurationSecond,
+    DurationMicrosecond,
+    IntervalYearMonth,
+    IntervalDayTime,
+    LargeUtf8,
+    LargeBinary,
+    FixedSizeBinary,
+    LargeList,
+    FixedSizeList,
+    Struct,
+    Union,
+    Dictionary,
 }
 
 impl ExDataFrameRef {
@@ -58,6 +108,24 @@ impl ExSeriesRef {
     }
 }
 
+impl ExLazyFrameRef {
+    pub fn new(ldf: LazyFrame) -> Self {
+        Self(ldf)
+    }
+}
+
+impl ExCsvBatchReaderRef {
+    pub fn new(reader: CsvBatchReader) -> Self {
+        Self(Mutex::new(reader))
+    }
+}
+
+impl ExBatchWriterRef {
+    pub fn new(writer: BatchWriter) -> Self {
+        Self(writer)
+    }
+}
+
 impl ExDataFrame {
     pub fn new(df: DataFrame) -> Self {
         Self {
@@ -75,10 +143,130 @@ impl ExSeries {
     }
 }
 
-impl From<&ArrowDataType> for DataType {
-    fn from(dt: &ArrowDataType) -> Self {
+impl ExLazyFrame {
+    pub fn new(ldf: LazyFrame) -> Self {
+        Self {
+            inner: ResourceArc::new(ExLazyFrameRef::new(ldf))
+        }
+    }
+}
+
+impl ExCsvBatchReader {
+    pub fn new(reader: CsvBatchReader) -> Self {
+        Self {
+            inner: ResourceArc::new(ExCsvBatchReaderRef::new(reader))
+        }
+    }
+}
+
+impl ExBatchWriter {
+    pub fn new(writer: BatchWriter) -> Self {
+        Self {
+            inner: ResourceArc::new(ExBatchWriterRef::new(writer))
+        }
+    }
+}
+
+impl DataType {
+    pub fn from_u8(v: u8) -> Option<Self> {
+        use DataType::*;
+        let dt = match v {
+            0 => Int8,
+            1 => Int16,
+            2 => Int32,
+            3 => Int64,
+            4 => UInt8,
+            5 => UInt16,
+            6 => UInt32,
+            7 => UInt64,
+            8 => Float32,
+            9 => Float64,
+            10 => Bool,
+            11 => Utf8,
+            12 => List,
+            13 => Date32,
+            14 => Date64,
+            15 => Time64Nanosecond,
+            16 => DurationNanosecond,
+            17 => DurationMillisecond,
+            18 => Object,
+            19 => Null,
+            20 => Float16,
+            21 => Time32Second,
+            22 => Time32Millisecond,
+            23 => Time64Microsecond,
+            24 => TimestampSecond,
+            25 => TimestampMillisecond,
+            26 => TimestampMicrosecond,
+            27 => TimestampNanosecond,
+            28 => DurationSecond,
+            29 => DurationMicrosecond,
+            30 => IntervalYearMonth,
+            31 => IntervalDayTime,
+            32 => LargeUtf8,
+            33 => LargeBinary,
+            34 => FixedSizeBinary,
+            35 => LargeList,
+            36 => FixedSizeList,
+            37 => Struct,
+            38 => Union,
+            39 => Dictionary,
+            _ => return None,
+        };
+        Some(dt)
+    }
+
+    /// Map back to an arrow type. Nested, parametrized and `Object` types don't carry their
+    /// inner type or parameters, so they have none.
+    pub fn to_arrow(&self) -> Option<ArrowDataType> {
         use DataType::*;
-        match dt {
+        let dt = match self {
+            Int8 => ArrowDataType::Int8,
+            Int16 => ArrowDataType::Int16,
+            Int32 => ArrowDataType::Int32,
+            Int64 => ArrowDataType::Int64,
+            UInt8 => ArrowDataType::UInt8,
+            UInt16 => ArrowDataType::UInt16,
+            UInt32 => ArrowDataType::UInt32,
+            UInt64 => ArrowDataType::UInt64,
+            Float32 => ArrowDataType::Float32,
+            Float64 => ArrowDataType::Float64,
+            Bool => ArrowDataType::Boolean,
+            Utf8 => ArrowDataType::Utf8,
+            Date32 => ArrowDataType::Date32(DateUnit::Day),
+            Date64 => ArrowDataType::Date64(DateUnit::Millisecond),
+            Time64Nanosecond => ArrowDataType::Time64(TimeUnit::Nanosecond),
+            DurationNanosecond => ArrowDataType::Duration(TimeUnit::Nanosecond),
+            DurationMillisecond => ArrowDataType::Duration(TimeUnit::Millisecond),
+            Null => ArrowDataType::Null,
+            Float16 => ArrowDataType::Float16,
+            Time32Second => ArrowDataType::Time32(TimeUnit::Second),
+            Time32Millisecond => ArrowDataType::Time32(TimeUnit::Millisecond),
+            Time64Microsecond => ArrowDataType::Time64(TimeUnit::Microsecond),
+            TimestampSecond => ArrowDataType::Timestamp(TimeUnit::Second, None),
+            TimestampMillisecond => ArrowDataType::Timestamp(TimeUnit::Millisecond, None),
+            TimestampMicrosecond => ArrowDataType::Timestamp(TimeUnit::Microsecond, None),
+            TimestampNanosecond => ArrowDataType::Timestamp(TimeUnit::Nanosecond, None),
+            DurationSecond => ArrowDataType::Duration(TimeUnit::Second),
+            DurationMicrosecond => ArrowDataType::Duration(TimeUnit::Microsecond),
+            IntervalYearMonth => ArrowDataType::Interval(IntervalUnit::YearMonth),
+            IntervalDayTime => ArrowDataType::Interval(IntervalUnit::DayTime),
+            LargeUtf8 => ArrowDataType::LargeUtf8,
+            LargeBinary => ArrowDataType::LargeBinary,
+            List | Object | FixedSizeBinary | LargeList | FixedSizeList | Struct | Union
+            | Dictionary => return None,
+        };
+        Some(dt)
+    }
+}
+
+impl TryFrom<&ArrowDataType> for DataType {
+    type Error = ExPolarsError;
+
+    fn try_from(dt: &ArrowDataType) -> Result<Self, Self::Error> {
+        use DataType::*;
+        let dt = match dt {
+            ArrowDataType::Null => Null,
             ArrowDataType::Int8 => Int8,
             ArrowDataType::Int16 => Int16,
             ArrowDataType::Int32 => Int32,
@@ -87,18 +275,84 @@ impl From<&ArrowDataType> for DataType {
             ArrowDataType::UInt16 => UInt16,
             ArrowDataType::UInt32 => UInt32,
             ArrowDataType::UInt64 => UInt64,
+            ArrowDataType::Float16 => Float16,
             ArrowDataType::Float32 => Float32,
             ArrowDataType::Float64 => Float64,
             ArrowDataType::Boolean => Bool,
             ArrowDataType::Utf8 => Utf8,
+            ArrowDataType::LargeUtf8 => LargeUtf8,
             ArrowDataType::List(_) => List,
+            ArrowDataType::LargeList(_) => LargeList,
+            ArrowDataType::FixedSizeList(_, _) => FixedSizeList,
             ArrowDataType::Date32(_) => Date32,
             ArrowDataType::Date64(_) => Date64,
+            ArrowDataType::Time32(TimeUnit::Second) => Time32Second,
+            ArrowDataType::Time32(TimeUnit::Millisecond) => Time32Millisecond,
+            ArrowDataType::Time64(TimeUnit::Microsecond) => Time64Microsecond,
             ArrowDataType::Time64(TimeUnit::Nanosecond) => Time64Nanosecond,
-            ArrowDataType::Duration(TimeUnit::Nanosecond) => DurationNanosecond,
+            ArrowDataType::Timestamp(TimeUnit::Second, _) => TimestampSecond,
+            ArrowDataType::Timestamp(TimeUnit::Millisecond, _) => TimestampMillisecond,
+            ArrowDataType::Timestamp(TimeUnit::Microsecond, _) => TimestampMicrosecond,
+            ArrowDataType::Timestamp(TimeUnit::Nanosecond, _) => TimestampNanosecond,
+            ArrowDataType::Duration(TimeUnit::Second) => DurationSecond,
             ArrowDataType::Duration(TimeUnit::Millisecond) => DurationMillisecond,
+            ArrowDataType::Duration(TimeUnit::Microsecond) => DurationMicrosecond,
+            ArrowDataType::Duration(TimeUnit::Nanosecond) => DurationNanosecond,
+            ArrowDataType::Interval(IntervalUnit::YearMonth) => IntervalYearMonth,
+            ArrowDataType::Interval(IntervalUnit::DayTime) => IntervalDayTime,
+            // polars has no binary series, its object series have the arrow type Binary
             ArrowDataType::Binary => Object,
-            dt => panic!(format!("datatype: {:?} not supported", dt)),
+            ArrowDataType::LargeBinary => LargeBinary,
+            ArrowDataType::FixedSizeBinary(_) => FixedSizeBinary,
+            ArrowDataType::Struct(_) => Struct,
+            ArrowDataType::Union(_) => Union,
+            ArrowDataType::Dictionary(_, _) => Dictionary,
+            // Time32 in micro/nanoseconds and Time64 in seconds/milliseconds are invalid in arrow
+            dt => {
+                return Err(ExPolarsError::Other(format!(
+                    "datatype: {:?} not supported",
+                    dt
+                )))
+            }
+        };
+        Ok(dt)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dtypes_round_trip_through_their_id_and_arrow_type() {
+        use DataType::*;
+        for v in 0..=u8::MAX {
+            let dt = match DataType::from_u8(v) {
+                Some(dt) => dt,
+                None => {
+                    assert!(v >= 40, "{}", v);
+                    continue;
+                }
+            };
+            assert_eq!(dt as u8, v);
+            match dt.to_arrow() {
+                Some(arrow) => assert_eq!(DataType::try_from(&arrow).unwrap(), dt),
+                None => assert!(matches!(
+                    dt,
+                    List | Object
+                        | FixedSizeBinary
+                        | LargeList
+                        | FixedSizeList
+                        | Struct
+                        | Union
+                        | Dictionary
+                )),
+            }
         }
+
+        let list = ArrowDataType::List(Box::new(ArrowDataType::Int64));
+        assert_eq!(DataType::try_from(&list).unwrap(), List);
+        assert_eq!(DataType::try_from(&ArrowDataType::Binary).unwrap(), Object);
+        assert!(DataType::try_from(&ArrowDataType::Time32(TimeUnit::Nanosecond)).is_err());
     }
 }
@@ -1,10 +1,21 @@
-use rustler::{Encoder, Env, Term};
+use polars::prelude::PolarsError;
+use rustler::types::atom::nil;
+use rustler::{Atom, Encoder, Env, Term};
 use std::io;
 use thiserror::Error;
 
+use crate::term::make_map;
+
 rustler::atoms! {
     ok,
-    error
+    error,
+    other,
+    struct_ = "__struct__",
+    exception = "__exception__",
+    module = "Elixir.ExPolars.Error",
+    kind,
+    message,
+    details,
 }
 
 #[derive(Error, Debug)]
@@ -16,7 +27,13 @@ pub enum ExPolarsError {
     #[error("Utf8 Conversion Error")]
     Utf8(#[from] std::string::FromUtf8Error),
     #[error("Polars Error")]
-    Polars(#[from] polars::prelude::PolarsError),
+    Polars(#[from] PolarsError),
+    #[error("Arrow Error")]
+    Arrow(#[from] arrow::error::ArrowError),
+    #[error("Parquet Error")]
+    Parquet(#[from] parquet::errors::ParquetError),
+    #[error("Schema mismatch: {0}")]
+    SchemaMismatch(String),
     #[error("Internal Error: {0}")]
     Internal(String),
     #[error("Other error: {0}")]
@@ -25,8 +42,145 @@ pub enum ExPolarsError {
     Unknown(#[from] anyhow::Error),
 }
 
+fn snake_case(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 4);
+    for (i, c) in s.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                out.push('_');
+            }
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// The `kind` of a polars error.
+fn polars_kind(e: &PolarsError) -> &'static str {
+    match e {
+        PolarsError::ArrowError(_) => "arrow",
+        PolarsError::InvalidOperation(_) => "invalid_operation",
+        PolarsError::DataTypeMisMatch(_) => "data_type_mismatch",
+        PolarsError::NotFound(_) => "not_found",
+        PolarsError::ShapeMisMatch(_) => "shape_mismatch",
+        // not `other`, which is for invalid arguments
+        PolarsError::Other(_) => "polars_other",
+        PolarsError::OutOfBounds(_) => "out_of_bounds",
+        PolarsError::NoSlice => "no_slice",
+        PolarsError::NoData(_) => "no_data",
+        PolarsError::ValueError(_) => "invalid_value",
+        PolarsError::MemoryNotAligned => "memory_not_aligned",
+        PolarsError::ParquetError(_) => "parquet",
+        PolarsError::RandError(_) => "rand",
+        PolarsError::HasNullValues(_) => "has_null_values",
+        PolarsError::UnknownSchema(_) => "unknown_schema",
+        PolarsError::Various(_) => "unknown",
+        PolarsError::IO(_) => "io",
+        PolarsError::Regex(_) => "regex",
+        PolarsError::Duplicate(_) => "duplicate",
+    }
+}
+
+impl ExPolarsError {
+    /// The `kind` field of `ExPolars.Error`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ExPolarsError::Io(_) => "io",
+            ExPolarsError::Json(_) => "json",
+            ExPolarsError::Utf8(_) => "utf8",
+            ExPolarsError::Polars(e) => polars_kind(e),
+            ExPolarsError::Arrow(_) => "arrow",
+            ExPolarsError::Parquet(_) => "parquet",
+            ExPolarsError::SchemaMismatch(_) => "schema_mismatch",
+            ExPolarsError::Internal(_) => "internal",
+            ExPolarsError::Other(_) => "other",
+            ExPolarsError::Unknown(_) => "unknown",
+        }
+    }
+
+    /// The `message` field of `ExPolars.Error`: the message of the wrapped error.
+    pub fn message(&self) -> String {
+        match self {
+            ExPolarsError::Io(e) => e.to_string(),
+            ExPolarsError::Json(e) => e.to_string(),
+            ExPolarsError::Utf8(e) => e.to_string(),
+            ExPolarsError::Polars(e) => e.to_string(),
+            ExPolarsError::Arrow(e) => e.to_string(),
+            ExPolarsError::Parquet(e) => e.to_string(),
+            ExPolarsError::SchemaMismatch(s)
+            | ExPolarsError::Internal(s)
+            | ExPolarsError::Other(s) => s.clone(),
+            ExPolarsError::Unknown(e) => e.to_string(),
+        }
+    }
+
+    fn encode_details<'b>(&self, env: Env<'b>) -> Term<'b> {
+        match self {
+            ExPolarsError::Io(e) => {
+                match Atom::from_str(env, &snake_case(&format!("{:?}", e.kind()))) {
+                    Ok(reason) => reason.encode(env),
+                    Err(_) => nil().encode(env),
+                }
+            }
+            _ => format!("{:?}", self).encode(env),
+        }
+    }
+}
+
+/// Encoded as an `%ExPolars.Error{kind: atom, message: string, details: term}` exception.
 impl<'a> Encoder for ExPolarsError {
     fn encode<'b>(&self, env: Env<'b>) -> Term<'b> {
-        format!("{:?}", self).encode(env)
+        let kind_atom = Atom::from_str(env, self.kind()).unwrap_or_else(|_| other());
+        let keys = [
+            struct_().encode(env),
+            exception().encode(env),
+            kind().encode(env),
+            message().encode(env),
+            details().encode(env),
+        ];
+        let values = [
+            module().encode(env),
+            true.encode(env),
+            kind_atom.encode(env),
+            self.message().encode(env),
+            self.encode_details(env),
+        ];
+        match make_map(env, &keys, &values) {
+            Ok(term) => term,
+            Err(_) => format!("{:?}", self).encode(env),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn errors_map_to_their_kinds() {
+        let not_found = PolarsError::NotFound("a".into());
+        assert_eq!(ExPolarsError::from(not_found).kind(), "not_found");
+        let value = PolarsError::ValueError("a".into());
+        assert_eq!(ExPolarsError::from(value).kind(), "invalid_value");
+        let various = PolarsError::Various("a".into());
+        assert_eq!(ExPolarsError::from(various).kind(), "unknown");
+        let other = PolarsError::Other("a".into());
+        assert_eq!(ExPolarsError::from(other).kind(), "polars_other");
+        let mismatch = PolarsError::DataTypeMisMatch("a".into());
+        assert_eq!(ExPolarsError::from(mismatch).kind(), "data_type_mismatch");
+        let arrow = PolarsError::ArrowError(arrow::error::ArrowError::MemoryError("a".into()));
+        assert_eq!(ExPolarsError::from(arrow).kind(), "arrow");
+        let io = io::Error::new(io::ErrorKind::NotFound, "a");
+        assert_eq!(ExPolarsError::from(io).kind(), "io");
+        assert_eq!(
+            ExPolarsError::SchemaMismatch("a".into()).kind(),
+            "schema_mismatch"
+        );
+        assert_eq!(
+            snake_case(&format!("{:?}", io::ErrorKind::PermissionDenied)),
+            "permission_denied"
+        );
     }
 }
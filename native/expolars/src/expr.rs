@@ -0,0 +1,178 @@
+// Decode expressions built on the elixir side into polars `Expr`.
+//
+// An expression is encoded as a nested tuple, e.g.
+//   {:binary_expr, :gt, {:col, "price"}, {:lit, 10}}
+// A bare string is a shortcut for `{:col, name}`.
+
+use polars::lazy::dsl;
+use polars::lazy::dsl::{Expr, Operator};
+use polars::lazy::prelude::ScalarValue;
+use polars::prelude::ArrowDataType;
+use rustler::types::tuple::get_tuple;
+use rustler::{Decoder, Error, NifResult, Term};
+
+use crate::{DataType, ExPolarsError, ExSeries};
+
+pub struct ExExpr {
+    pub inner: Expr,
+}
+
+impl ExExpr {
+    pub fn new(inner: Expr) -> Self {
+        Self { inner }
+    }
+}
+
+pub(crate) fn to_expr_collection(exprs: Vec<ExExpr>) -> Vec<Expr> {
+    exprs.into_iter().map(|e| e.inner).collect()
+}
+
+fn bad_expr(msg: String) -> Error {
+    Error::Term(Box::new(ExPolarsError::Other(msg)))
+}
+
+fn decode_lit(term: Term) -> NifResult<Expr> {
+    if term.is_atom() {
+        return match term.atom_to_string()?.as_str() {
+            "true" => Ok(dsl::lit(true)),
+            "false" => Ok(dsl::lit(false)),
+            "nil" => Ok(Expr::Literal(ScalarValue::Null)),
+            a => Err(bad_expr(format!("literal :{} not supported", a))),
+        };
+    }
+    if term.is_binary() {
+        let v: String = term.decode()?;
+        return Ok(dsl::lit(v));
+    }
+    if let Ok(v) = term.decode::<i64>() {
+        return Ok(dsl::lit(v));
+    }
+    if let Ok(v) = term.decode::<f64>() {
+        return Ok(dsl::lit(v));
+    }
+    Err(bad_expr(format!("literal {:?} not supported", term)))
+}
+
+fn decode_dtype(term: Term) -> NifResult<ArrowDataType> {
+    let v: u8 = term.decode()?;
+    match DataType::from_u8(v).and_then(|dt| dt.to_arrow()) {
+        Some(dt) => Ok(dt),
+        None => Err(bad_expr(format!("cannot cast to dtype {}", v))),
+    }
+}
+
+fn decode_operator(term: Term) -> NifResult<Operator> {
+    let op = match term.atom_to_string()?.as_str() {
+        "eq" => Operator::Eq,
+        "neq" => Operator::NotEq,
+        "lt" => Operator::Lt,
+        "lt_eq" => Operator::LtEq,
+        "gt" => Operator::Gt,
+        "gt_eq" => Operator::GtEq,
+        "plus" => Operator::Plus,
+        "minus" => Operator::Minus,
+        "multiply" => Operator::Multiply,
+        "divide" => Operator::Divide,
+        "modulus" => Operator::Modulus,
+        "and" => Operator::And,
+        "or" => Operator::Or,
+        op => return Err(bad_expr(format!("operator {} not supported", op))),
+    };
+    Ok(op)
+}
+
+fn decode_agg(name: &str, expr: Expr) -> NifResult<Expr> {
+    let expr = match name {
+        "min" => expr.min(),
+        "max" => expr.max(),
+        "sum" => expr.sum(),
+        "mean" => expr.mean(),
+        "median" => expr.median(),
+        "first" => expr.first(),
+        "last" => expr.last(),
+        "count" => expr.count(),
+        "n_unique" => expr.n_unique(),
+        "list" | "agg_list" => expr.list(),
+        a => return Err(bad_expr(format!("agg fn {} does not exists", a))),
+    };
+    Ok(expr)
+}
+
+pub(crate) fn decode_expr(term: Term) -> NifResult<Expr> {
+    if term.is_binary() {
+        let name: &str = term.decode()?;
+        return Ok(dsl::col(name));
+    }
+
+    let t = get_tuple(term)?;
+    if t.is_empty() {
+        return Err(bad_expr("empty expression".into()));
+    }
+    let tag = t[0].atom_to_string()?;
+    let expr = match (tag.as_str(), &t[1..]) {
+        ("col", [name]) => dsl::col(name.decode()?),
+        ("lit", [value]) => decode_lit(*value)?,
+        ("binary_expr", [op, l, r]) => {
+            dsl::binary_expr(decode_expr(*l)?, decode_operator(*op)?, decode_expr(*r)?)
+        }
+        ("not", [e]) => decode_expr(*e)?.not(),
+        ("is_null", [e]) => decode_expr(*e)?.is_null(),
+        ("is_not_null", [e]) => decode_expr(*e)?.is_not_null(),
+        ("agg", [name, e]) => decode_agg(&name.atom_to_string()?, decode_expr(*e)?)?,
+        ("quantile", [e, quant]) => decode_expr(*e)?.quantile(quant.decode()?),
+        ("when", [predicate, then, otherwise]) => dsl::when(decode_expr(*predicate)?)
+            .then(decode_expr(*then)?)
+            .otherwise(decode_expr(*otherwise)?),
+        ("cast", [e, dtype]) => decode_expr(*e)?.cast(decode_dtype(*dtype)?),
+        ("alias", [e, name]) => decode_expr(*e)?.alias(name.decode()?),
+        ("count_rows", [_]) => {
+            return Err(bad_expr(
+                "count() without a column is only supported in groupby aggregations".into(),
+            ))
+        }
+        (tag, args) => {
+            return Err(bad_expr(format!(
+                "expression {} with {} arguments not supported",
+                tag,
+                args.len()
+            )))
+        }
+    };
+    Ok(expr)
+}
+
+impl<'a> Decoder<'a> for ExExpr {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        Ok(ExExpr::new(decode_expr(term)?))
+    }
+}
+
+/// A `df_filter` predicate: either a boolean mask series or an expression.
+pub enum ExPredicate {
+    Mask(ExSeries),
+    Expr(Expr),
+}
+
+impl<'a> Decoder<'a> for ExPredicate {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        match term.decode::<ExSeries>() {
+            Ok(s) => Ok(ExPredicate::Mask(s)),
+            Err(_) => Ok(ExPredicate::Expr(decode_expr(term)?)),
+        }
+    }
+}
+
+/// A `df_groupby_agg` item: either `{column, [agg names]}` or an aggregation expression.
+pub enum ExAgg<'a> {
+    Columns(&'a str, Vec<&'a str>),
+    Expr(Expr),
+}
+
+impl<'a> Decoder<'a> for ExAgg<'a> {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        match term.decode::<(&'a str, Vec<&'a str>)>() {
+            Ok((name, aggs)) => Ok(ExAgg::Columns(name, aggs)),
+            Err(_) => Ok(ExAgg::Expr(decode_expr(term)?)),
+        }
+    }
+}
@@ -0,0 +1,442 @@
+// Joins polars doesn't provide (semi, anti, cross and as-of), and suffixing of overlapping
+// column names.
+
+use polars::prelude::*;
+use std::cmp::Ordering;
+use std::result::Result;
+
+use crate::ExPolarsError;
+
+const MATCH_COLUMN: &str = "__expolars_match__";
+
+/// Rename the columns of `right` that also exist in `left` by appending `suffix`. Columns in
+/// `skip` (the join keys) are left alone.
+pub(crate) fn suffix_overlapping(
+    left: &DataFrame,
+    mut right: DataFrame,
+    skip: &[&str],
+    suffix: &str,
+) -> Result<DataFrame, ExPolarsError> {
+    let left_names = left.get_column_names();
+    let overlapping: Vec<String> = right
+        .get_column_names()
+        .into_iter()
+        .filter(|name| !skip.contains(name) && left_names.contains(name))
+        .map(|name| name.to_string())
+        .collect();
+    for name in overlapping {
+        right.rename(&name, &format!("{}{}", name, suffix))?;
+    }
+    Ok(right)
+}
+
+/// Keep the rows of `left` which have (`anti == false`) or don't have (`anti == true`) a match
+/// in `right`. The row order of `left` is kept and no columns of `right` are added.
+pub(crate) fn semi_anti_join(
+    left: &DataFrame,
+    right: &DataFrame,
+    left_on: Vec<&str>,
+    right_on: Vec<&str>,
+    anti: bool,
+) -> Result<DataFrame, ExPolarsError> {
+    let mut keys = right
+        .select(right_on.clone())?
+        .drop_duplicates(false, None)?;
+    let marker = Series::new(MATCH_COLUMN, vec![true; keys.height()].as_slice());
+    keys.hstack_mut(&[marker])?;
+
+    let joined = left.join(&keys, left_on, right_on, JoinType::Left)?;
+    let matched = joined.column(MATCH_COLUMN)?;
+    let mask = if anti {
+        matched.is_null()
+    } else {
+        matched.is_not_null()
+    };
+    let mut result = joined.filter(&mask)?;
+    result.drop_in_place(MATCH_COLUMN)?;
+    Ok(result)
+}
+
+/// Every row of `left` combined with every row of `right`.
+pub(crate) fn cross_join(
+    left: &DataFrame,
+    right: &DataFrame,
+    suffix: &str,
+) -> Result<DataFrame, ExPolarsError> {
+    let (n, m) = (left.height(), right.height());
+    let left_idx: Vec<usize> = (0..n).flat_map(|i| std::iter::repeat(i).take(m)).collect();
+    let right_idx: Vec<usize> = (0..n).flat_map(|_| 0..m).collect();
+
+    let right = suffix_overlapping(left, right.take(&right_idx), &[], suffix)?;
+    Ok(left.take(&left_idx).hstack(right.get_columns())?)
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum AsofStrategy {
+    Backward,
+    Forward,
+    Nearest,
+}
+
+impl AsofStrategy {
+    pub fn from_str(strategy: &str) -> Result<Self, ExPolarsError> {
+        match strategy {
+            "backward" => Ok(AsofStrategy::Backward),
+            "forward" => Ok(AsofStrategy::Forward),
+            "nearest" => Ok(AsofStrategy::Nearest),
+            _ => Err(ExPolarsError::Other(format!(
+                "asof strategy {} not supported",
+                strategy
+            ))),
+        }
+    }
+}
+
+/// A key an as-of join can match on.
+trait AsofKey: PartialOrd + Copy {
+    /// `|self - other|`, `None` if it doesn't fit in the key type.
+    fn distance(self, other: Self) -> Option<Self>;
+}
+
+impl AsofKey for i64 {
+    fn distance(self, other: Self) -> Option<Self> {
+        if self > other {
+            self.checked_sub(other)
+        } else {
+            other.checked_sub(self)
+        }
+    }
+}
+
+impl AsofKey for u64 {
+    fn distance(self, other: Self) -> Option<Self> {
+        Some(if self > other {
+            self - other
+        } else {
+            other - self
+        })
+    }
+}
+
+impl AsofKey for f64 {
+    fn distance(self, other: Self) -> Option<Self> {
+        Some((self - other).abs())
+    }
+}
+
+/// Whether `key` is closer to `a` than to `b`. A distance that overflows is farther than any
+/// other.
+fn closer<T: AsofKey>(key: T, a: T, b: T) -> bool {
+    match (a.distance(key), b.distance(key)) {
+        (Some(a), Some(b)) => a < b,
+        (Some(_), None) => true,
+        (None, _) => false,
+    }
+}
+
+/// Only NaN isn't comparable with itself.
+fn is_nan<T: AsofKey>(v: T) -> bool {
+    v.partial_cmp(&v).is_none()
+}
+
+/// For every left key, the index of the matching right row. `right` must be sorted ascending
+/// and free of NaN, null right keys never match, and neither do null or NaN left keys.
+fn asof_indices<T: AsofKey>(
+    left: &[Option<T>],
+    right: &[Option<T>],
+    strategy: AsofStrategy,
+    tolerance: Option<T>,
+) -> Result<Vec<Option<usize>>, ExPolarsError> {
+    let right: Vec<(T, usize)> = right
+        .iter()
+        .enumerate()
+        .filter_map(|(i, v)| v.map(|v| (v, i)))
+        .collect();
+    if right.iter().any(|(v, _)| is_nan(*v)) || right.windows(2).any(|w| w[0].0 > w[1].0) {
+        return Err(ExPolarsError::Other(
+            "asof join requires the right key to be sorted ascending and not NaN".into(),
+        ));
+    }
+
+    let indices = left
+        .iter()
+        .map(|key| {
+            let key = key.filter(|k| !is_nan(*k))?;
+            // number of right keys <= key, and < key
+            let le = position(&right, |r| r <= key);
+            let lt = position(&right, |r| r < key);
+            let backward = if le > 0 { Some(right[le - 1]) } else { None };
+            let forward = right.get(lt).copied();
+            let found = match strategy {
+                AsofStrategy::Backward => backward,
+                AsofStrategy::Forward => forward,
+                AsofStrategy::Nearest => match (backward, forward) {
+                    (Some(b), Some(f)) if closer(key, f.0, b.0) => Some(f),
+                    (Some(b), _) => Some(b),
+                    (None, f) => f,
+                },
+            };
+            let (value, idx) = found?;
+            match tolerance {
+                Some(tol) if value.distance(key).map_or(true, |d| d > tol) => None,
+                _ => Some(idx),
+            }
+        })
+        .collect();
+    Ok(indices)
+}
+
+/// Number of leading elements of the sorted `values` for which `pred` holds.
+fn position<T: Copy>(values: &[(T, usize)], pred: impl Fn(T) -> bool) -> usize {
+    match values.binary_search_by(|(v, _)| {
+        if pred(*v) {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    }) {
+        Ok(i) | Err(i) => i,
+    }
+}
+
+/// A tolerance for integer keys, which a fractional tolerance would be silently truncated for.
+fn integral_tolerance(tolerance: Option<f64>) -> Result<Option<f64>, ExPolarsError> {
+    match tolerance {
+        Some(t) if t.fract() != 0.0 => Err(ExPolarsError::Other(format!(
+            "asof join tolerance {} must be an integer for integer, date and time keys",
+            t
+        ))),
+        _ => Ok(tolerance),
+    }
+}
+
+macro_rules! int_asof {
+    ($l:expr, $r:expr, $cast:ident, $key:ty, $strategy:expr, $tolerance:expr) => {{
+        let left: Vec<Option<$key>> = $l
+            .$cast()?
+            .into_iter()
+            .map(|v| v.map(|v| v as $key))
+            .collect();
+        let right: Vec<Option<$key>> = $r
+            .$cast()?
+            .into_iter()
+            .map(|v| v.map(|v| v as $key))
+            .collect();
+        let tolerance = integral_tolerance($tolerance)?.map(|t| t as $key);
+        asof_indices(&left, &right, $strategy, tolerance)?
+    }};
+}
+
+/// Join every row of `left` with the last (backward), next (forward) or closest (nearest) row of
+/// `right` by key, optionally within `tolerance` of the key. Unmatched rows get nulls.
+pub(crate) fn asof_join(
+    left: &DataFrame,
+    right: &DataFrame,
+    left_on: &str,
+    right_on: &str,
+    strategy: AsofStrategy,
+    tolerance: Option<f64>,
+    suffix: &str,
+) -> Result<DataFrame, ExPolarsError> {
+    let l = left.column(left_on)?;
+    let r = right.column(right_on)?;
+    if l.dtype() != r.dtype() {
+        return Err(ExPolarsError::Other(format!(
+            "asof join keys {} and {} have different dtypes",
+            left_on, right_on
+        )));
+    }
+    if let Some(t) = tolerance {
+        if t.is_nan() || t < 0.0 {
+            return Err(ExPolarsError::Other(format!(
+                "asof join tolerance {} must be a non-negative number",
+                t
+            )));
+        }
+    }
+    let indices = match l.dtype() {
+        ArrowDataType::Int8 => int_asof!(l, r, i8, i64, strategy, tolerance),
+        ArrowDataType::Int16 => int_asof!(l, r, i16, i64, strategy, tolerance),
+        ArrowDataType::Int32 => int_asof!(l, r, i32, i64, strategy, tolerance),
+        ArrowDataType::Int64 => int_asof!(l, r, i64, i64, strategy, tolerance),
+        ArrowDataType::UInt8 => int_asof!(l, r, u8, u64, strategy, tolerance),
+        ArrowDataType::UInt16 => int_asof!(l, r, u16, u64, strategy, tolerance),
+        ArrowDataType::UInt32 => int_asof!(l, r, u32, u64, strategy, tolerance),
+        ArrowDataType::UInt64 => int_asof!(l, r, u64, u64, strategy, tolerance),
+        ArrowDataType::Date32(_) => int_asof!(l, r, date32, i64, strategy, tolerance),
+        ArrowDataType::Date64(_) => int_asof!(l, r, date64, i64, strategy, tolerance),
+        ArrowDataType::Time64(TimeUnit::Nanosecond) => {
+            int_asof!(l, r, time64_nanosecond, i64, strategy, tolerance)
+        }
+        ArrowDataType::Float32 | ArrowDataType::Float64 => {
+            let left: Vec<Option<f64>> = l.cast::<Float64Type>()?.f64()?.into_iter().collect();
+            let right: Vec<Option<f64>> = r.cast::<Float64Type>()?.f64()?.into_iter().collect();
+            asof_indices(&left, &right, strategy, tolerance)?
+        }
+        dt => {
+            return Err(ExPolarsError::Other(format!(
+                "asof join on {:?} not supported",
+                dt
+            )))
+        }
+    };
+
+    let mut right = right.take_opt_iter(indices.into_iter(), Some(left.height()));
+    right.drop_in_place(right_on)?;
+    let right = suffix_overlapping(left, right, &[], suffix)?;
+    Ok(left.hstack(right.get_columns())?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn asof<T: AsofKey>(
+        left: &[Option<T>],
+        right: &[Option<T>],
+        strategy: &str,
+        tolerance: Option<T>,
+    ) -> Vec<Option<usize>> {
+        let strategy = AsofStrategy::from_str(strategy).unwrap();
+        asof_indices(left, right, strategy, tolerance).unwrap()
+    }
+
+    #[test]
+    fn asof_indices_by_strategy() {
+        let left = [Some(0i64), Some(1), Some(4), Some(5), Some(6), Some(12)];
+        let right = [Some(1i64), Some(3), Some(7), Some(10)];
+        assert_eq!(
+            asof(&left, &right, "backward", None),
+            vec![None, Some(0), Some(1), Some(1), Some(1), Some(3)]
+        );
+        assert_eq!(
+            asof(&left, &right, "forward", None),
+            vec![Some(0), Some(0), Some(2), Some(2), Some(2), None]
+        );
+        // a tie between both sides goes backward
+        assert_eq!(
+            asof(&left, &right, "nearest", None),
+            vec![Some(0), Some(0), Some(1), Some(1), Some(2), Some(3)]
+        );
+    }
+
+    #[test]
+    fn asof_indices_within_tolerance() {
+        let left = [Some(2i64), Some(5), Some(9)];
+        let right = [Some(1i64), Some(7)];
+        assert_eq!(
+            asof(&left, &right, "backward", Some(1)),
+            vec![Some(0), None, None]
+        );
+        assert_eq!(
+            asof(&left, &right, "forward", Some(2)),
+            vec![None, Some(1), None]
+        );
+        assert_eq!(
+            asof(&left, &right, "nearest", Some(2)),
+            vec![Some(0), Some(1), Some(1)]
+        );
+        assert_eq!(
+            asof(&left, &right, "nearest", Some(0)),
+            vec![None, None, None]
+        );
+    }
+
+    #[test]
+    fn asof_indices_skip_null_keys() {
+        let left = [None, Some(3i64), Some(8)];
+        let right = [None, Some(1i64), None, Some(5)];
+        assert_eq!(
+            asof(&left, &right, "backward", None),
+            vec![None, Some(1), Some(3)]
+        );
+        assert_eq!(
+            asof(&left, &right, "forward", None),
+            vec![None, Some(3), None]
+        );
+    }
+
+    #[test]
+    fn asof_indices_never_overflow() {
+        let left = [Some(i64::MIN), Some(i64::MAX)];
+        let right = [Some(i64::MIN), Some(i64::MAX)];
+        assert_eq!(
+            asof(&left, &right, "nearest", Some(10)),
+            vec![Some(0), Some(1)]
+        );
+        // i64::MAX - i64::MIN overflows, which is beyond any tolerance
+        let left = [Some(i64::MIN)];
+        let right = [Some(i64::MAX)];
+        assert_eq!(asof(&left, &right, "forward", Some(10)), vec![None]);
+        assert_eq!(asof(&left, &right, "forward", None), vec![Some(0)]);
+        // and farther than any distance that doesn't
+        let right = [Some(i64::MIN), Some(i64::MAX)];
+        assert_eq!(asof(&[Some(0i64)], &right, "nearest", None), vec![Some(1)]);
+        assert_eq!(asof(&[Some(-1i64)], &right, "nearest", None), vec![Some(0)]);
+
+        let left = [Some(u64::MAX - 1)];
+        let right = [Some(0u64), Some(u64::MAX)];
+        assert_eq!(asof(&left, &right, "nearest", Some(1)), vec![Some(1)]);
+    }
+
+    #[test]
+    fn asof_indices_reject_unsorted_or_nan_right_keys() {
+        let strategy = AsofStrategy::Backward;
+        assert!(asof_indices(&[Some(1i64)], &[Some(2), Some(1)], strategy, None).is_err());
+        assert!(asof_indices(&[Some(1.0)], &[Some(f64::NAN)], strategy, None).is_err());
+        assert!(asof_indices(&[Some(1.0)], &[Some(0.0), Some(f64::NAN)], strategy, None).is_err());
+
+        let left = [Some(f64::NAN), Some(1.5)];
+        let right = [Some(1.0), Some(2.0)];
+        assert_eq!(asof(&left, &right, "nearest", None), vec![None, Some(0)]);
+    }
+
+    fn keys(df: &DataFrame) -> Vec<Option<i64>> {
+        df.column("id")
+            .unwrap()
+            .i64()
+            .unwrap()
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn asof_join_checks_the_tolerance() {
+        let left = DataFrame::new(vec![Series::new("t", &[1i64, 5, 9])]).unwrap();
+        let right = DataFrame::new(vec![
+            Series::new("t", &[1u64, 8]),
+            Series::new("id", &[10i64, 20]),
+        ])
+        .unwrap();
+        let strategy = AsofStrategy::Backward;
+        assert!(asof_join(&left, &right, "t", "t", strategy, None, "_right").is_err());
+
+        let left = DataFrame::new(vec![Series::new("t", &[1u64, 5, 9])]).unwrap();
+        let joined = asof_join(&left, &right, "t", "t", strategy, Some(1.0), "_right").unwrap();
+        assert_eq!(keys(&joined), vec![Some(10), None, Some(20)]);
+        assert!(asof_join(&left, &right, "t", "t", strategy, Some(1.5), "_right").is_err());
+        assert!(asof_join(&left, &right, "t", "t", strategy, Some(-1.0), "_right").is_err());
+    }
+
+    #[test]
+    fn semi_and_anti_joins_keep_duplicate_left_rows_once() {
+        let left = DataFrame::new(vec![
+            Series::new("k", &[1i64, 2, 1, 3, 2]),
+            Series::new("id", &[0i64, 1, 2, 3, 4]),
+        ])
+        .unwrap();
+        let right = DataFrame::new(vec![
+            Series::new("key", &[2i64, 1, 2, 2]),
+            Series::new("other", &["a", "b", "c", "d"]),
+        ])
+        .unwrap();
+
+        let semi = semi_anti_join(&left, &right, vec!["k"], vec!["key"], false).unwrap();
+        assert_eq!(semi.get_column_names(), vec!["k", "id"]);
+        assert_eq!(keys(&semi), vec![Some(0), Some(1), Some(2), Some(4)]);
+
+        let anti = semi_anti_join(&left, &right, vec!["k"], vec!["key"], true).unwrap();
+        assert_eq!(anti.get_column_names(), vec!["k", "id"]);
+        assert_eq!(keys(&anti), vec![Some(3)]);
+    }
+}
@@ -0,0 +1,548 @@
+// Read json documents into data frames.
+//
+// A document is either an array of objects or, line delimited, one object per line. Rows are
+// parsed one at a time and converted to columns in batches, so only a batch of parsed rows is
+// held in memory. Column dtypes are inferred from the first rows unless a schema is given.
+//
+// Nested objects are flattened into `parent.child` columns or, as polars has no struct dtype,
+// kept as json text columns as a fallback. Arrays become list columns, except arrays of arrays
+// which are kept as json text like objects inside arrays.
+
+use chrono::{NaiveDate, NaiveDateTime};
+use polars::chunked_array::builder::get_list_builder;
+use polars::prelude::*;
+use serde::de::{self, DeserializeSeed, SeqAccess, Visitor};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{BufRead, BufReader, Read};
+use std::result::Result;
+use std::str::FromStr;
+
+use crate::{DataType, ExPolarsError};
+
+pub enum Nested {
+    Flatten,
+    Keep,
+}
+
+impl FromStr for Nested {
+    type Err = ExPolarsError;
+
+    fn from_str(nested: &str) -> Result<Self, ExPolarsError> {
+        match nested {
+            "flatten" => Ok(Nested::Flatten),
+            "keep" => Ok(Nested::Keep),
+            _ => Err(ExPolarsError::Other(format!(
+                "nested {} not supported, expected flatten or keep",
+                nested
+            ))),
+        }
+    }
+}
+
+pub struct JsonOptions {
+    /// Rows to infer dtypes from, all rows if `None`.
+    pub infer_schema_length: Option<usize>,
+    pub batch_size: usize,
+    pub schema: Option<Vec<(String, DataType)>>,
+    pub nested: Nested,
+}
+
+#[derive(Clone, Copy)]
+enum ColumnType {
+    Scalar(DataType),
+    List(DataType),
+}
+
+/// The json type of a value, as far as dtypes are concerned.
+#[derive(Clone, PartialEq)]
+enum JsonType {
+    Null,
+    Bool,
+    Int,
+    Float,
+    Str,
+    List(Box<JsonType>),
+}
+
+impl JsonType {
+    fn of(value: &Value) -> Self {
+        match value {
+            Value::Null => JsonType::Null,
+            Value::Bool(_) => JsonType::Bool,
+            Value::Number(n) if n.is_i64() => JsonType::Int,
+            Value::Number(_) => JsonType::Float,
+            Value::Array(items) => {
+                let inner = items
+                    .iter()
+                    .fold(JsonType::Null, |acc, v| acc.merge(JsonType::of(v)));
+                match inner {
+                    // nested arrays are kept as json text
+                    JsonType::List(_) => JsonType::Str,
+                    inner => JsonType::List(Box::new(inner)),
+                }
+            }
+            Value::String(_) | Value::Object(_) => JsonType::Str,
+        }
+    }
+
+    /// Integers widen to floats, any other mix becomes a string column.
+    fn merge(self, other: JsonType) -> Self {
+        match (self, other) {
+            (JsonType::Null, b) => b,
+            (a, JsonType::Null) => a,
+            (JsonType::Int, JsonType::Float) | (JsonType::Float, JsonType::Int) => JsonType::Float,
+            (JsonType::List(a), JsonType::List(b)) => match a.merge(*b) {
+                JsonType::List(_) => JsonType::Str,
+                inner => JsonType::List(Box::new(inner)),
+            },
+            (a, b) if a == b => a,
+            _ => JsonType::Str,
+        }
+    }
+
+    fn scalar_dtype(&self) -> DataType {
+        match self {
+            JsonType::Bool => DataType::Bool,
+            JsonType::Int => DataType::Int64,
+            JsonType::Float => DataType::Float64,
+            // a column with only nulls is a string column
+            _ => DataType::Utf8,
+        }
+    }
+
+    fn column_type(&self) -> ColumnType {
+        match self {
+            JsonType::List(inner) => ColumnType::List(inner.scalar_dtype()),
+            t => ColumnType::Scalar(t.scalar_dtype()),
+        }
+    }
+}
+
+fn parse_date(s: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()
+}
+
+fn parse_datetime(s: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f"))
+        .ok()
+        .or_else(|| Some(parse_date(s)?.and_hms(0, 0, 0)))
+}
+
+/// Days since epoch from an ISO 8601 date string or an integer.
+fn to_date32(v: &Value) -> Option<i32> {
+    match v {
+        Value::String(s) => {
+            let epoch = NaiveDate::from_ymd(1970, 1, 1);
+            Some((parse_date(s)? - epoch).num_days() as i32)
+        }
+        v => i32::try_from(v.as_i64()?).ok(),
+    }
+}
+
+/// Milliseconds since epoch from an ISO 8601 datetime string or an integer.
+fn to_date64(v: &Value) -> Option<i64> {
+    match v {
+        Value::String(s) => Some(parse_datetime(s)?.timestamp_millis()),
+        v => v.as_i64(),
+    }
+}
+
+fn to_int<T: TryFrom<i64>>(v: &Value) -> Option<T> {
+    T::try_from(v.as_i64()?).ok()
+}
+
+fn to_f32(v: &Value) -> Option<f32> {
+    Some(v.as_f64()? as f32)
+}
+
+/// Strings as is, any other value as json text.
+fn to_text(v: &Value) -> Option<String> {
+    match v {
+        Value::String(s) => Some(s.clone()),
+        v => Some(v.to_string()),
+    }
+}
+
+fn convert<T, F>(
+    name: &str,
+    values: &[Option<&Value>],
+    dtype: DataType,
+    f: F,
+) -> Result<Vec<Option<T>>, ExPolarsError>
+where
+    F: Fn(&Value) -> Option<T>,
+{
+    values
+        .iter()
+        .map(|v| match v {
+            None | Some(Value::Null) => Ok(None),
+            Some(v) => f(v).map(Some).ok_or_else(|| {
+                ExPolarsError::Other(format!(
+                    "column {}: cannot convert {} to {:?}, pass a schema or a larger \
+                     infer_schema_length",
+                    name, v, dtype
+                ))
+            }),
+        })
+        .collect()
+}
+
+macro_rules! column {
+    ($ca:ty, $name:expr, $values:expr, $dtype:expr, $f:expr) => {
+        <$ca>::new_from_opt_slice($name, &convert($name, $values, $dtype, $f)?).into_series()
+    };
+}
+
+fn scalar_series(
+    name: &str,
+    values: &[Option<&Value>],
+    dtype: DataType,
+) -> Result<Series, ExPolarsError> {
+    use DataType::*;
+    let s = match dtype {
+        Int8 => column!(Int8Chunked, name, values, dtype, to_int::<i8>),
+        Int16 => column!(Int16Chunked, name, values, dtype, to_int::<i16>),
+        Int32 => column!(Int32Chunked, name, values, dtype, to_int::<i32>),
+        Int64 => column!(Int64Chunked, name, values, dtype, Value::as_i64),
+        UInt8 => column!(UInt8Chunked, name, values, dtype, to_int::<u8>),
+        UInt16 => column!(UInt16Chunked, name, values, dtype, to_int::<u16>),
+        UInt32 => column!(UInt32Chunked, name, values, dtype, to_int::<u32>),
+        UInt64 => column!(UInt64Chunked, name, values, dtype, Value::as_u64),
+        Float32 => column!(Float32Chunked, name, values, dtype, to_f32),
+        Float64 => column!(Float64Chunked, name, values, dtype, Value::as_f64),
+        Bool => column!(BooleanChunked, name, values, dtype, Value::as_bool),
+        Utf8 => column!(Utf8Chunked, name, values, dtype, to_text),
+        Date32 => column!(Date32Chunked, name, values, dtype, to_date32),
+        Date64 => column!(Date64Chunked, name, values, dtype, to_date64),
+        dt => {
+            return Err(ExPolarsError::Other(format!(
+                "column {}: cannot build {:?} series from json",
+                name, dt
+            )))
+        }
+    };
+    Ok(s)
+}
+
+fn list_series(
+    name: &str,
+    values: &[Option<&Value>],
+    inner: DataType,
+) -> Result<Series, ExPolarsError> {
+    let arrow_dtype = inner
+        .to_arrow()
+        .ok_or_else(|| ExPolarsError::Other(format!("column {}: unsupported list dtype", name)))?;
+    let mut builder = get_list_builder(&arrow_dtype, values.len(), name);
+    for v in values {
+        match v {
+            None | Some(Value::Null) => builder.append_opt_series(None),
+            Some(Value::Array(items)) => {
+                let items: Vec<Option<&Value>> = items.iter().map(Some).collect();
+                let s = scalar_series(name, &items, inner)?;
+                builder.append_opt_series(Some(&s))
+            }
+            Some(v) => {
+                return Err(ExPolarsError::Other(format!(
+                    "column {}: cannot convert {} to a list",
+                    name, v
+                )))
+            }
+        }
+    }
+    Ok(builder.finish().into_series())
+}
+
+/// The columns of a row object, `parent.child` for nested objects when flattening.
+fn flatten_into(prefix: Option<&str>, object: Map<String, Value>, out: &mut Vec<(String, Value)>) {
+    for (k, v) in object {
+        let name = match prefix {
+            Some(prefix) => format!("{}.{}", prefix, k),
+            None => k,
+        };
+        match v {
+            Value::Object(nested) => flatten_into(Some(&name), nested, out),
+            v => out.push((name, v)),
+        }
+    }
+}
+
+struct JsonFrameBuilder {
+    options: JsonOptions,
+    // fixed once enough rows are seen to infer it
+    schema: Option<Vec<(String, ColumnType)>>,
+    index: HashMap<String, usize>,
+    rows: Vec<Vec<(String, Value)>>,
+    n_rows: usize,
+    df: Option<DataFrame>,
+}
+
+impl JsonFrameBuilder {
+    fn new(options: JsonOptions) -> Self {
+        let schema = options.schema.as_ref().map(|schema| {
+            schema
+                .iter()
+                .map(|(name, dtype)| (name.clone(), ColumnType::Scalar(*dtype)))
+                .collect()
+        });
+        let mut builder = Self {
+            options,
+            schema: None,
+            index: HashMap::new(),
+            rows: vec![],
+            n_rows: 0,
+            df: None,
+        };
+        if let Some(schema) = schema {
+            builder.set_schema(schema);
+        }
+        builder
+    }
+
+    fn set_schema(&mut self, schema: Vec<(String, ColumnType)>) {
+        self.index = schema
+            .iter()
+            .enumerate()
+            .map(|(i, (name, _))| (name.clone(), i))
+            .collect();
+        self.schema = Some(schema);
+    }
+
+    /// Column types of the buffered rows, ordered by first appearance.
+    fn infer_schema(&self) -> Vec<(String, ColumnType)> {
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        let mut types: Vec<(&str, JsonType)> = vec![];
+        for (name, value) in self.rows.iter().flatten() {
+            let t = JsonType::of(value);
+            match index.get(name.as_str()) {
+                Some(i) => {
+                    let merged = types[*i].1.clone().merge(t);
+                    types[*i].1 = merged;
+                }
+                None => {
+                    index.insert(name, types.len());
+                    types.push((name, t));
+                }
+            }
+        }
+        types
+            .into_iter()
+            .map(|(name, t)| (name.to_string(), t.column_type()))
+            .collect()
+    }
+
+    fn push(&mut self, row: Value) -> Result<(), ExPolarsError> {
+        let object = match row {
+            Value::Object(object) => object,
+            v => {
+                return Err(ExPolarsError::Other(format!(
+                    "row {}: expected an object, got {}",
+                    self.n_rows, v
+                )))
+            }
+        };
+        let mut columns = Vec::with_capacity(object.len());
+        match self.options.nested {
+            Nested::Flatten => flatten_into(None, object, &mut columns),
+            Nested::Keep => columns.extend(object),
+        }
+        self.rows.push(columns);
+        self.n_rows += 1;
+
+        match &self.schema {
+            Some(_) if self.rows.len() >= self.options.batch_size => self.flush(),
+            None if Some(self.rows.len()) == self.options.infer_schema_length => {
+                let schema = self.infer_schema();
+                self.set_schema(schema);
+                self.flush()
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Convert the buffered rows to a chunk of the frame.
+    fn flush(&mut self) -> Result<(), ExPolarsError> {
+        let schema = match &self.schema {
+            Some(schema) => schema,
+            None => return Err(ExPolarsError::Internal("json schema not set".into())),
+        };
+        let rows = std::mem::take(&mut self.rows);
+        let batch_size = self.options.batch_size.max(1);
+        let first_row = self.n_rows - rows.len();
+        for (n, batch) in rows.chunks(batch_size).enumerate() {
+            let mut values: Vec<Vec<Option<&Value>>> = vec![vec![None; batch.len()]; schema.len()];
+            for (i, row) in batch.iter().enumerate() {
+                for (name, value) in row {
+                    match self.index.get(name) {
+                        Some(idx) => values[*idx][i] = Some(value),
+                        // columns that aren't in a given schema are dropped
+                        None if self.options.schema.is_some() => {}
+                        None => {
+                            return Err(ExPolarsError::Other(format!(
+                                "row {}: column {} isn't in the schema inferred from the first \
+                                 {} rows, raise infer_schema_length or give a schema",
+                                first_row + n * batch_size + i,
+                                name,
+                                self.options.infer_schema_length.unwrap_or(self.n_rows)
+                            )))
+                        }
+                    }
+                }
+            }
+            let columns = schema
+                .iter()
+                .zip(values.iter())
+                .map(|((name, ty), values)| match ty {
+                    ColumnType::Scalar(dtype) => scalar_series(name, values, *dtype),
+                    ColumnType::List(inner) => list_series(name, values, *inner),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let chunk = DataFrame::new(columns)?;
+            match &mut self.df {
+                Some(df) => {
+                    df.vstack_mut(&chunk)?;
+                }
+                None => self.df = Some(chunk),
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<DataFrame, ExPolarsError> {
+        if self.schema.is_none() {
+            let schema = self.infer_schema();
+            self.set_schema(schema);
+        }
+        self.flush()?;
+        let df = match self.df {
+            Some(df) => df,
+            // no rows, the schema gives the columns
+            None => {
+                let schema = self.schema.unwrap_or_default();
+                let values = vec![];
+                let columns = schema
+                    .iter()
+                    .map(|(name, ty)| match ty {
+                        ColumnType::Scalar(dtype) => scalar_series(name, &values, *dtype),
+                        ColumnType::List(inner) => list_series(name, &values, *inner),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                DataFrame::new(columns)?
+            }
+        };
+        Ok(df.agg_chunks())
+    }
+}
+
+/// Feeds the elements of the top-level array to the builder without collecting them.
+struct Rows<'a>(&'a mut JsonFrameBuilder);
+
+impl<'de, 'a> DeserializeSeed<'de> for Rows<'a> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for Rows<'a> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an array of objects at the top level")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<(), A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        while let Some(row) = seq.next_element::<Value>()? {
+            self.0.push(row).map_err(de::Error::custom)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn read_json<R: Read>(
+    reader: R,
+    line_delimited_json: bool,
+    options: JsonOptions,
+) -> Result<DataFrame, ExPolarsError> {
+    let mut builder = JsonFrameBuilder::new(options);
+    if line_delimited_json {
+        for (i, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let row: Value = serde_json::from_str(&line).map_err(|e| {
+                ExPolarsError::Other(format!("line {}: invalid json: {}", i + 1, e))
+            })?;
+            builder.push(row)?;
+        }
+    } else {
+        let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(reader));
+        Rows(&mut builder).deserialize(&mut deserializer)?;
+        deserializer.end()?;
+    }
+    builder.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn options(nested: Nested) -> JsonOptions {
+        JsonOptions {
+            infer_schema_length: Some(1),
+            batch_size: 1,
+            schema: None,
+            nested,
+        }
+    }
+
+    #[test]
+    fn flattens_nested_objects_across_batches() {
+        let json = r#"[{"a": 1, "b": {"c": [1, 2], "d": "x"}}, {"a": 2.5, "b": {"d": null}}]"#;
+        let df = read_json(json.as_bytes(), false, options(Nested::Flatten));
+        // the dtype of `a` is inferred from the first row only
+        assert!(df.is_err());
+
+        for (nested, names) in vec![
+            (Nested::Flatten, vec!["a", "b.c", "b.d"]),
+            (Nested::Keep, vec!["a", "b"]),
+        ] {
+            let mut opts = options(nested);
+            opts.infer_schema_length = None;
+            let df = read_json(json.as_bytes(), false, opts).unwrap();
+            assert_eq!(df.get_column_names(), names);
+            assert_eq!(df.height(), 2);
+            assert_eq!(df.column("a").unwrap().f64().unwrap().get(1), Some(2.5));
+        }
+    }
+
+    #[test]
+    fn keys_missing_from_the_inferred_schema_are_an_error() {
+        let json = r#"[{"a": 1}, {"a": 2, "b": 3}]"#;
+        let df = read_json(json.as_bytes(), false, options(Nested::Flatten));
+        assert!(df.is_err());
+
+        let mut opts = options(Nested::Flatten);
+        opts.schema = Some(vec![("a".to_string(), DataType::Int64)]);
+        let df = read_json(json.as_bytes(), false, opts).unwrap();
+        assert_eq!(df.get_column_names(), vec!["a"]);
+        assert_eq!(df.height(), 2);
+    }
+
+    #[test]
+    fn top_level_object_is_an_error() {
+        let df = read_json(r#"{"a": 1}"#.as_bytes(), false, options(Nested::Flatten));
+        assert!(df.is_err());
+    }
+}
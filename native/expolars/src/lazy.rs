@@ -0,0 +1,190 @@
+// This file is modified based on: https://github.com/ritchie46/polars/blob/master/py-polars/src/lazy/dataframe.rs
+
+use polars::prelude::*;
+
+use parquet::file::reader::SerializedFileReader;
+use std::fs::File;
+use std::io;
+use std::result::Result;
+
+use crate::dataframe::csv_delimiter;
+use crate::expr::{to_expr_collection, ExExpr};
+use crate::{ExDataFrame, ExLazyFrame, ExPolarsError};
+
+use crate::df_read;
+
+#[rustler::nif]
+pub fn lf_from_df(data: ExDataFrame) -> Result<ExLazyFrame, ExPolarsError> {
+    df_read!(data, df, { Ok(ExLazyFrame::new(df.clone().lazy())) })
+}
+
+/// An error if `filename` can't be opened or isn't a file. `File::open` alone succeeds on a
+/// directory.
+fn check_is_file(filename: &str) -> Result<(), ExPolarsError> {
+    if File::open(filename)?.metadata()?.is_file() {
+        Ok(())
+    } else {
+        Err(io::Error::new(io::ErrorKind::Other, format!("{} is not a file", filename)).into())
+    }
+}
+
+/// A plan scanning a csv file. `LazyCsvReader::finish` opens the file and reads its schema with
+/// `expect`, so the file is checked here first to return an error instead of panicking.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn csv_scan_plan(
+    filename: String,
+    sep: u8,
+    has_header: bool,
+    ignore_errors: bool,
+    skip_rows: usize,
+    stop_after_n_rows: Option<usize>,
+    cache: bool,
+) -> Result<LazyFrame, ExPolarsError> {
+    check_is_file(&filename)?;
+    Ok(LazyCsvReader::new(filename)
+        .with_delimiter(sep)
+        .has_header(has_header)
+        .with_ignore_parser_errors(ignore_errors)
+        .with_skip_rows(skip_rows)
+        .with_stop_after_n_rows(stop_after_n_rows)
+        .with_cache(cache)
+        .finish())
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn lf_scan_csv(
+    filename: String,
+    sep: &str,
+    has_header: bool,
+    ignore_errors: bool,
+    skip_rows: usize,
+    stop_after_n_rows: Option<usize>,
+    cache: bool,
+) -> Result<ExLazyFrame, ExPolarsError> {
+    let ldf = csv_scan_plan(
+        filename,
+        csv_delimiter(sep)?,
+        has_header,
+        ignore_errors,
+        skip_rows,
+        stop_after_n_rows,
+        cache,
+    )?;
+    Ok(ExLazyFrame::new(ldf))
+}
+
+/// `LazyFrame::new_from_parquet` reads the schema of the file with `expect`, so the file is read
+/// as parquet here first to return an error instead of panicking.
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn lf_scan_parquet(
+    filename: String,
+    stop_after_n_rows: Option<usize>,
+    cache: bool,
+) -> Result<ExLazyFrame, ExPolarsError> {
+    SerializedFileReader::new(File::open(&filename)?)?;
+    let ldf = LazyFrame::new_from_parquet(filename, stop_after_n_rows, cache);
+    Ok(ExLazyFrame::new(ldf))
+}
+
+#[rustler::nif]
+pub fn lf_describe_plan(data: ExLazyFrame) -> Result<String, ExPolarsError> {
+    Ok(data.inner.0.describe_plan())
+}
+
+#[rustler::nif]
+pub fn lf_describe_optimized_plan(data: ExLazyFrame) -> Result<String, ExPolarsError> {
+    Ok(data.inner.0.describe_optimized_plan()?)
+}
+
+#[rustler::nif]
+pub fn lf_filter(data: ExLazyFrame, predicate: ExExpr) -> Result<ExLazyFrame, ExPolarsError> {
+    let ldf = data.inner.0.clone();
+    Ok(ExLazyFrame::new(ldf.filter(predicate.inner)))
+}
+
+#[rustler::nif]
+pub fn lf_select(data: ExLazyFrame, exprs: Vec<ExExpr>) -> Result<ExLazyFrame, ExPolarsError> {
+    let ldf = data.inner.0.clone();
+    Ok(ExLazyFrame::new(ldf.select(to_expr_collection(exprs))))
+}
+
+#[rustler::nif]
+pub fn lf_with_column(data: ExLazyFrame, expr: ExExpr) -> Result<ExLazyFrame, ExPolarsError> {
+    let ldf = data.inner.0.clone();
+    Ok(ExLazyFrame::new(ldf.with_column(expr.inner)))
+}
+
+#[rustler::nif]
+pub fn lf_with_columns(
+    data: ExLazyFrame,
+    exprs: Vec<ExExpr>,
+) -> Result<ExLazyFrame, ExPolarsError> {
+    let ldf = data.inner.0.clone();
+    Ok(ExLazyFrame::new(
+        ldf.with_columns(to_expr_collection(exprs)),
+    ))
+}
+
+#[rustler::nif]
+pub fn lf_groupby_agg(
+    data: ExLazyFrame,
+    by: Vec<&str>,
+    aggs: Vec<ExExpr>,
+) -> Result<ExLazyFrame, ExPolarsError> {
+    let ldf = data.inner.0.clone();
+    let lgb = ldf.groupby(by);
+    Ok(ExLazyFrame::new(lgb.agg(to_expr_collection(aggs))))
+}
+
+#[rustler::nif]
+pub fn lf_join(
+    data: ExLazyFrame,
+    other: ExLazyFrame,
+    left_on: ExExpr,
+    right_on: ExExpr,
+    how: &str,
+) -> Result<ExLazyFrame, ExPolarsError> {
+    let ldf = data.inner.0.clone();
+    let other = other.inner.0.clone();
+    let ldf = match how {
+        "left" => ldf.left_join(other, left_on.inner, right_on.inner, None),
+        "inner" => ldf.inner_join(other, left_on.inner, right_on.inner, None),
+        "outer" => ldf.outer_join(other, left_on.inner, right_on.inner, None),
+        _ => {
+            return Err(ExPolarsError::Other(format!(
+                "Join method {} not supported",
+                how
+            )))
+        }
+    };
+    Ok(ExLazyFrame::new(ldf))
+}
+
+#[rustler::nif]
+pub fn lf_sort(
+    data: ExLazyFrame,
+    by_column: &str,
+    reverse: bool,
+) -> Result<ExLazyFrame, ExPolarsError> {
+    let ldf = data.inner.0.clone();
+    Ok(ExLazyFrame::new(ldf.sort(by_column, reverse)))
+}
+
+#[rustler::nif]
+pub fn lf_limit(data: ExLazyFrame, n: usize) -> Result<ExLazyFrame, ExPolarsError> {
+    let ldf = data.inner.0.clone();
+    Ok(ExLazyFrame::new(ldf.limit(n)))
+}
+
+#[rustler::nif]
+pub fn lf_cache(data: ExLazyFrame) -> Result<ExLazyFrame, ExPolarsError> {
+    let ldf = data.inner.0.clone();
+    Ok(ExLazyFrame::new(ldf.cache()))
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn lf_collect(data: ExLazyFrame) -> Result<ExDataFrame, ExPolarsError> {
+    let ldf = data.inner.0.clone();
+    let df = ldf.collect()?;
+    Ok(ExDataFrame::new(df))
+}
@@ -1,13 +1,29 @@
 use rustler::{Env, Term};
 
+mod compression;
+mod csv_batch;
 mod dataframe;
+mod dataset;
 mod datatypes;
 mod error;
+mod expr;
+mod join;
+mod json;
+mod lazy;
+mod parquet_file;
+mod scan;
 mod series;
+mod sort;
+mod term;
+mod writer;
 
 use dataframe::*;
-pub use datatypes::{DataType, ExDataFrame, ExDataFrameRef, ExSeries, ExSeriesRef};
+pub use datatypes::{
+    DataType, ExBatchWriter, ExBatchWriterRef, ExCsvBatchReader, ExCsvBatchReaderRef, ExDataFrame,
+    ExDataFrameRef, ExLazyFrame, ExLazyFrameRef, ExSeries, ExSeriesRef,
+};
 pub use error::ExPolarsError;
+use lazy::*;
 use series::*;
 
 #[macro_export]
@@ -63,6 +79,9 @@ macro_rules! df_write_read {
 fn on_load(env: Env, _info: Term) -> bool {
     rustler::resource!(ExDataFrameRef, env);
     rustler::resource!(ExSeriesRef, env);
+    rustler::resource!(ExLazyFrameRef, env);
+    rustler::resource!(ExCsvBatchReaderRef, env);
+    rustler::resource!(ExBatchWriterRef, env);
     true
 }
 
@@ -70,10 +89,37 @@ rustler::init!(
     "Elixir.ExPolars.Native",
     [
         df_read_csv,
+        df_read_csv_binary,
+        df_read_csv_batched,
+        df_next_batch,
         df_read_parquet,
+        df_read_parquet_binary,
+        df_scan_csv,
+        df_scan_parquet,
+        df_read_dataset,
+        df_write_dataset,
         df_read_json,
+        df_read_json_binary,
+        df_read_ipc,
+        df_read_ipc_binary,
         df_to_csv,
         df_to_csv_file,
+        df_csv_writer,
+        df_parquet_writer,
+        df_write_batch,
+        df_close_writer,
+        df_to_json,
+        df_to_json_file,
+        df_to_rows,
+        df_to_columns_map,
+        df_to_parquet,
+        df_to_parquet_file,
+        df_to_ipc,
+        df_to_ipc_file,
+        df_to_binary,
+        df_from_binary,
+        df_from_rows,
+        df_from_columns,
         df_as_str,
         df_add,
         df_sub,
@@ -85,6 +131,7 @@ rustler::init!(
         df_rechunk,
         df_fill_none,
         df_join,
+        df_join_asof,
         df_get_columns,
         df_columns,
         df_set_column_names,
@@ -108,6 +155,9 @@ rustler::init!(
         df_take_with_series,
         df_sort_new,
         df_sort_in_place,
+        df_sort_by,
+        df_sort_by_in_place,
+        df_top_k,
         df_replace,
         df_replace_at_idx,
         df_insert_at_idx,
@@ -119,6 +169,9 @@ rustler::init!(
         df_frame_equal,
         df_groupby,
         df_groupby_agg,
+        df_groupby_exprs,
+        df_partition_by,
+        df_concat,
         df_groupby_quantile,
         df_pivot,
         df_clone,
@@ -135,6 +188,22 @@ rustler::init!(
         df_median,
         df_quantile,
         df_to_dummies,
+        // lazy
+        lf_from_df,
+        lf_scan_csv,
+        lf_scan_parquet,
+        lf_describe_plan,
+        lf_describe_optimized_plan,
+        lf_filter,
+        lf_select,
+        lf_with_column,
+        lf_with_columns,
+        lf_groupby_agg,
+        lf_join,
+        lf_sort,
+        lf_limit,
+        lf_cache,
+        lf_collect,
         // series
         s_as_str,
         s_new_i8,
@@ -153,6 +222,23 @@ rustler::init!(
         s_new_f64,
         s_parse_date32_from_str_slice,
         s_new_str,
+        s_new_opt_i8,
+        s_new_opt_i16,
+        s_new_opt_i32,
+        s_new_opt_i64,
+        s_new_opt_bool,
+        s_new_opt_u8,
+        s_new_opt_u16,
+        s_new_opt_u32,
+        s_new_opt_u64,
+        s_new_opt_date32,
+        s_new_opt_date64,
+        s_new_opt_duration_ns,
+        s_new_opt_duration_ms,
+        s_new_opt_time_ns,
+        s_new_opt_f32,
+        s_new_opt_f64,
+        s_new_opt_str,
         s_rechunk,
         s_chunk_lengths,
         s_name,
@@ -171,6 +257,9 @@ rustler::init!(
         s_tail,
         s_sort,
         s_argsort,
+        s_sort_by,
+        s_argsort_by,
+        s_top_k,
         s_unique,
         s_value_counts,
         s_arg_unique,
@@ -196,6 +285,7 @@ rustler::init!(
         s_not,
         s_len,
         s_to_json,
+        s_to_list,
         s_drop_nulls,
         s_fill_none,
         s_clone,
@@ -391,10 +481,12 @@ rustler::init!(
 
 #[cfg(test)]
 mod test {
+    use crate::json::{read_json, JsonOptions, Nested};
+    use polars::prelude::*;
     use serde_json::Value;
     use std::fs::File;
     #[test]
-    fn read_json() {
+    fn read_json_lines() {
         let f = File::open("crimea.json").unwrap();
         let v: Value = serde_json::from_reader(f).unwrap();
         let items: Vec<String> = v
@@ -403,8 +495,21 @@ mod test {
             .iter()
             .map(|item| serde_json::to_string(&item).unwrap())
             .collect();
-        for line in items {
-            println!("{}", line);
-        }
+        let options = JsonOptions {
+            infer_schema_length: None,
+            batch_size: 8,
+            schema: None,
+            nested: Nested::Flatten,
+        };
+        let df = read_json(items.join("\n").as_bytes(), true, options).unwrap();
+        assert_eq!(df.shape(), (24, 4));
+        assert_eq!(
+            df.get_column_names(),
+            vec!["date", "wounds", "other", "disease"]
+        );
+        let date = df.column("date").unwrap();
+        assert_eq!(date.utf8().unwrap().get(0), Some("1854-04-01"));
+        let disease = df.column("disease").unwrap();
+        assert_eq!(disease.i64().unwrap().get(3), Some(520));
     }
 }
@@ -0,0 +1,440 @@
+// Parquet files written and read with the column API of parquet 2, which has no arrow writer.
+//
+// Every column is an optional leaf of the file schema. The dtypes parquet 2 can't read back as
+// themselves (Date64, Time64 and Duration) are written as plain INT64 columns and named in the
+// `expolars.dtypes` key value metadata of the file, which the readers here use to restore them.
+
+use arrow::record_batch::RecordBatchReader;
+use parquet::arrow::{ArrowReader, ParquetFileArrowReader};
+use parquet::basic::{Compression, LogicalType, Repetition, Type as PhysicalType};
+use parquet::column::writer::{ColumnWriter, ColumnWriterImpl};
+use parquet::data_type as pq;
+use parquet::data_type::ByteArray;
+use parquet::file::metadata::{FileMetaData, KeyValue};
+use parquet::file::properties::WriterProperties;
+use parquet::file::reader::{FileReader, ParquetReader, SerializedFileReader, TryClone};
+use parquet::file::writer::{FileWriter, ParquetWriter, SerializedFileWriter};
+use parquet::schema::types::Type as SchemaType;
+use polars::prelude::*;
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{self, Cursor, Seek, SeekFrom, Write};
+use std::rc::Rc;
+use std::result::Result;
+
+use crate::dataframe::batches_to_df;
+use crate::ExPolarsError;
+
+const DTYPES_KEY: &str = "expolars.dtypes";
+const BATCH_SIZE: usize = 65536;
+
+/// The physical and logical parquet type of a column, plus the name of its dtype in
+/// `expolars.dtypes` if it has to be restored after reading.
+fn parquet_type(
+    field: &Field,
+) -> Result<(PhysicalType, LogicalType, Option<&'static str>), ExPolarsError> {
+    let t = match field.data_type() {
+        ArrowDataType::Boolean => (PhysicalType::BOOLEAN, LogicalType::NONE, None),
+        ArrowDataType::Int8 => (PhysicalType::INT32, LogicalType::INT_8, None),
+        ArrowDataType::Int16 => (PhysicalType::INT32, LogicalType::INT_16, None),
+        ArrowDataType::Int32 => (PhysicalType::INT32, LogicalType::NONE, None),
+        ArrowDataType::Int64 => (PhysicalType::INT64, LogicalType::NONE, None),
+        ArrowDataType::UInt8 => (PhysicalType::INT32, LogicalType::UINT_8, None),
+        ArrowDataType::UInt16 => (PhysicalType::INT32, LogicalType::UINT_16, None),
+        ArrowDataType::UInt32 => (PhysicalType::INT32, LogicalType::UINT_32, None),
+        ArrowDataType::UInt64 => (PhysicalType::INT64, LogicalType::UINT_64, None),
+        ArrowDataType::Float32 => (PhysicalType::FLOAT, LogicalType::NONE, None),
+        ArrowDataType::Float64 => (PhysicalType::DOUBLE, LogicalType::NONE, None),
+        ArrowDataType::Utf8 => (PhysicalType::BYTE_ARRAY, LogicalType::UTF8, None),
+        ArrowDataType::Date32(DateUnit::Day) => (PhysicalType::INT32, LogicalType::DATE, None),
+        ArrowDataType::Date64(DateUnit::Millisecond) => {
+            (PhysicalType::INT64, LogicalType::NONE, Some("date64"))
+        }
+        ArrowDataType::Time64(TimeUnit::Nanosecond) => {
+            (PhysicalType::INT64, LogicalType::NONE, Some("time64(ns)"))
+        }
+        ArrowDataType::Duration(TimeUnit::Nanosecond) => {
+            (PhysicalType::INT64, LogicalType::NONE, Some("duration(ns)"))
+        }
+        ArrowDataType::Duration(TimeUnit::Millisecond) => {
+            (PhysicalType::INT64, LogicalType::NONE, Some("duration(ms)"))
+        }
+        dt => {
+            return Err(ExPolarsError::Other(format!(
+                "column {}: {:?} can't be written to parquet",
+                field.name(),
+                dt
+            )))
+        }
+    };
+    Ok(t)
+}
+
+/// Writes data frames with the same schema to a parquet file, one row group per frame.
+pub(crate) struct FrameWriter<W: ParquetWriter> {
+    writer: SerializedFileWriter<W>,
+    statistics: bool,
+}
+
+impl<W: 'static + ParquetWriter> FrameWriter<W> {
+    pub(crate) fn try_new(
+        w: W,
+        schema: &Schema,
+        compression: Compression,
+        statistics: bool,
+    ) -> Result<Self, ExPolarsError> {
+        let mut fields = Vec::with_capacity(schema.fields().len());
+        let mut dtypes = Map::new();
+        for field in schema.fields() {
+            let (physical, logical, dtype) = parquet_type(field)?;
+            let leaf = SchemaType::primitive_type_builder(field.name(), physical)
+                .with_repetition(Repetition::OPTIONAL)
+                .with_logical_type(logical)
+                .build()?;
+            fields.push(Rc::new(leaf));
+            if let Some(dtype) = dtype {
+                dtypes.insert(field.name().clone(), Value::from(dtype));
+            }
+        }
+        let root = SchemaType::group_type_builder("schema")
+            .with_fields(&mut fields)
+            .build()?;
+        let metadata = KeyValue::new(DTYPES_KEY.to_string(), Value::from(dtypes).to_string());
+        let props = WriterProperties::builder()
+            .set_compression(compression)
+            .set_key_value_metadata(Some(vec![metadata]))
+            .build();
+        Ok(Self {
+            writer: SerializedFileWriter::new(w, Rc::new(root), Rc::new(props))?,
+            statistics,
+        })
+    }
+
+    /// Write `df` as one row group.
+    pub(crate) fn write(&mut self, df: &DataFrame) -> Result<(), ExPolarsError> {
+        let mut row_group = self.writer.next_row_group()?;
+        for s in df.get_columns() {
+            let mut column = row_group.next_column()?.ok_or_else(|| {
+                ExPolarsError::SchemaMismatch(format!("column {} isn't in the schema", s.name()))
+            })?;
+            write_column(&mut column, s, self.statistics)?;
+            row_group.close_column(column)?;
+        }
+        self.writer.close_row_group(row_group)?;
+        Ok(())
+    }
+
+    /// Write the footer. Nothing can be written after closing.
+    pub(crate) fn close(mut self) -> Result<(), ExPolarsError> {
+        self.writer.close()?;
+        Ok(())
+    }
+}
+
+fn write_column(
+    column: &mut ColumnWriter,
+    s: &Series,
+    statistics: bool,
+) -> Result<(), ExPolarsError> {
+    // the physical type of the column follows from the dtype of `s`, see `parquet_type`
+    match column {
+        ColumnWriter::BoolColumnWriter(w) => {
+            // the boolean and utf8 iterators misread the null bitmap of a sliced array, `get`
+            // doesn't
+            let ca = s.bool()?;
+            let (values, def_levels) = levels((0..ca.len()).map(|i| ca.get(i)), |v| v);
+            write_batch(w, &values, &def_levels, statistics)
+        }
+        ColumnWriter::Int32ColumnWriter(w) => {
+            // parquet keeps unsigned ints in the signed physical type of the same width
+            let (values, def_levels) = match s.dtype() {
+                ArrowDataType::Int8 => levels(s.i8()?, i32::from),
+                ArrowDataType::Int16 => levels(s.i16()?, i32::from),
+                ArrowDataType::UInt8 => levels(s.u8()?, i32::from),
+                ArrowDataType::UInt16 => levels(s.u16()?, i32::from),
+                ArrowDataType::UInt32 => levels(s.u32()?, |v| v as i32),
+                ArrowDataType::Date32(_) => levels(s.date32()?, |v| v),
+                _ => levels(s.i32()?, |v| v),
+            };
+            write_batch(w, &values, &def_levels, statistics)
+        }
+        ColumnWriter::Int64ColumnWriter(w) => {
+            let (values, def_levels) = match s.dtype() {
+                ArrowDataType::UInt64 => levels(s.u64()?, |v| v as i64),
+                ArrowDataType::Date64(_) => levels(s.date64()?, |v| v),
+                ArrowDataType::Time64(_) => levels(s.time64_nanosecond()?, |v| v),
+                ArrowDataType::Duration(TimeUnit::Nanosecond) => {
+                    levels(s.duration_nanosecond()?, |v| v)
+                }
+                ArrowDataType::Duration(_) => levels(s.duration_millisecond()?, |v| v),
+                _ => levels(s.i64()?, |v| v),
+            };
+            write_batch(w, &values, &def_levels, statistics)
+        }
+        ColumnWriter::FloatColumnWriter(w) => {
+            let (values, def_levels) = levels(s.f32()?, |v| v);
+            write_batch(w, &values, &def_levels, statistics)
+        }
+        ColumnWriter::DoubleColumnWriter(w) => {
+            let (values, def_levels) = levels(s.f64()?, |v| v);
+            write_batch(w, &values, &def_levels, statistics)
+        }
+        ColumnWriter::ByteArrayColumnWriter(w) => {
+            let ca = s.utf8()?;
+            let (values, def_levels) = levels((0..ca.len()).map(|i| ca.get(i)), ByteArray::from);
+            write_batch(w, &values, &def_levels, statistics)
+        }
+        _ => Err(ExPolarsError::Internal(format!(
+            "column {}: no parquet writer for {:?}",
+            s.name(),
+            s.dtype()
+        ))),
+    }
+}
+
+/// The non-null values of a column and its definition levels, 0 for null and 1 otherwise.
+fn levels<V, P>(
+    values: impl IntoIterator<Item = Option<V>>,
+    to_physical: impl Fn(V) -> P,
+) -> (Vec<P>, Vec<i16>) {
+    let mut physical = Vec::new();
+    let mut def_levels = Vec::new();
+    for v in values {
+        match v {
+            Some(v) => {
+                physical.push(to_physical(v));
+                def_levels.push(1);
+            }
+            None => def_levels.push(0),
+        }
+    }
+    (physical, def_levels)
+}
+
+fn write_batch<T: pq::DataType>(
+    writer: &mut ColumnWriterImpl<T>,
+    values: &[T::T],
+    def_levels: &[i16],
+    statistics: bool,
+) -> Result<(), ExPolarsError> {
+    if statistics {
+        writer.write_batch(values, Some(def_levels), None)?;
+    } else {
+        // a given null count stops the writer from computing min and max
+        let nulls = (def_levels.len() - values.len()) as u64;
+        writer.write_batch_with_statistics(
+            values,
+            Some(def_levels),
+            None,
+            &None,
+            &None,
+            Some(nulls),
+            None,
+        )?;
+    }
+    Ok(())
+}
+
+/// Read the row groups of `reader`, only decoding the leaf columns in `projection` if given.
+pub(crate) fn read_row_groups(
+    reader: Rc<dyn FileReader>,
+    projection: Option<Vec<usize>>,
+) -> Result<DataFrame, ExPolarsError> {
+    let mut arrow_reader = ParquetFileArrowReader::new(reader.clone());
+    if reader.metadata().num_row_groups() == 0 {
+        // the record reader fails on a file without row groups, e.g. one of an empty frame
+        let schema = match projection {
+            Some(indices) => arrow_reader.get_schema_by_columns(indices)?,
+            None => arrow_reader.get_schema()?,
+        };
+        let df = batches_to_df(&schema, &[])?;
+        return restore_dtypes(df, reader.metadata().file_metadata());
+    }
+    let batches = match projection {
+        Some(indices) => arrow_reader.get_record_reader_by_columns(indices, BATCH_SIZE)?,
+        None => arrow_reader.get_record_reader(BATCH_SIZE)?,
+    };
+    let schema = batches.schema();
+    let batches = batches.collect::<Result<Vec<_>, _>>()?;
+    let df = batches_to_df(&schema, &batches)?;
+    restore_dtypes(df, reader.metadata().file_metadata())
+}
+
+/// Read a whole parquet file.
+pub(crate) fn read_parquet<R: 'static + ParquetReader>(r: R) -> Result<DataFrame, ExPolarsError> {
+    read_row_groups(Rc::new(SerializedFileReader::new(r)?), None)
+}
+
+/// Give the columns named in `expolars.dtypes` their dtypes back.
+fn restore_dtypes(mut df: DataFrame, metadata: &FileMetaData) -> Result<DataFrame, ExPolarsError> {
+    let stored = metadata
+        .key_value_metadata()
+        .iter()
+        .flatten()
+        .find(|kv| kv.key == DTYPES_KEY)
+        .and_then(|kv| kv.value.as_ref());
+    let dtypes: HashMap<String, String> = match stored {
+        Some(dtypes) => serde_json::from_str(dtypes)?,
+        None => return Ok(df),
+    };
+    for (name, dtype) in dtypes {
+        let mut s = match df.column(&name) {
+            Ok(s) => {
+                let values = s.i64()?.into_iter();
+                match dtype.as_str() {
+                    "date64" => values.collect::<Date64Chunked>().into_series(),
+                    "time64(ns)" => values.collect::<Time64NanosecondChunked>().into_series(),
+                    "duration(ns)" => values.collect::<DurationNanosecondChunked>().into_series(),
+                    "duration(ms)" => values.collect::<DurationMillisecondChunked>().into_series(),
+                    _ => continue,
+                }
+            }
+            // projected away
+            Err(_) => continue,
+        };
+        s.rename(&name);
+        df.replace(&name, s)?;
+    }
+    Ok(df)
+}
+
+/// An in-memory file for `FrameWriter`. The parquet writer writes through clones of its file, so
+/// like clones of a `File` the clones of a buffer share the data and the position.
+#[derive(Clone, Default)]
+pub(crate) struct SharedBuffer(Rc<RefCell<Cursor<Vec<u8>>>>);
+
+impl SharedBuffer {
+    pub(crate) fn data(&self) -> Vec<u8> {
+        self.0.borrow().get_ref().clone()
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.borrow_mut().flush()
+    }
+}
+
+impl Seek for SharedBuffer {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.0.borrow_mut().seek(pos)
+    }
+}
+
+impl TryClone for SharedBuffer {
+    fn try_clone(&self) -> parquet::errors::Result<Self> {
+        Ok(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataframe::write_parquet;
+
+    fn temporal_frame() -> DataFrame {
+        DataFrame::new(vec![
+            Series::new("i", &[Some(1i64), None, Some(-3)]),
+            Series::new("small", &[Some(-1i8), Some(2), None]),
+            Series::new("big", &[Some(u64::MAX), None, Some(0)]),
+            Series::new("f", &[Some(0.5f64), Some(f64::INFINITY), None]),
+            Series::new("b", &[Some(true), None, Some(false)]),
+            Series::new("s", &[Some("a"), None, Some("ü")]),
+            Date32Chunked::new_from_opt_slice("date32", &[Some(18000), None, Some(-1)])
+                .into_series(),
+            Date64Chunked::new_from_opt_slice("date64", &[Some(1_600_000_000_000), None, Some(-1)])
+                .into_series(),
+            Time64NanosecondChunked::new_from_opt_slice("time", &[Some(1), Some(2), None])
+                .into_series(),
+            DurationNanosecondChunked::new_from_opt_slice("ns", &[None, Some(-5), Some(i64::MAX)])
+                .into_series(),
+            DurationMillisecondChunked::new_from_opt_slice(
+                "ms",
+                &[Some(86_400_000), None, Some(0)],
+            )
+            .into_series(),
+        ])
+        .unwrap()
+    }
+
+    // polars can't compare temporal series, so those are compared by their physical values
+    fn assert_frame_eq(left: &DataFrame, right: &DataFrame) {
+        assert_eq!(left.schema(), right.schema());
+        for (l, r) in left.get_columns().iter().zip(right.get_columns()) {
+            let (l, r) = match l.dtype() {
+                ArrowDataType::Date32(_) => (
+                    l.cast::<Int32Type>().unwrap(),
+                    r.cast::<Int32Type>().unwrap(),
+                ),
+                ArrowDataType::Date64(_)
+                | ArrowDataType::Time64(_)
+                | ArrowDataType::Duration(_) => (
+                    l.cast::<Int64Type>().unwrap(),
+                    r.cast::<Int64Type>().unwrap(),
+                ),
+                _ => (l.clone(), r.clone()),
+            };
+            assert!(
+                l.series_equal_missing(&r),
+                "{}: {:?} != {:?}",
+                l.name(),
+                l,
+                r
+            );
+        }
+    }
+
+    fn round_trip(df: &DataFrame, compression: &str, row_group_size: usize) -> DataFrame {
+        let buf = SharedBuffer::default();
+        write_parquet(df, buf.clone(), compression, row_group_size, true).unwrap();
+        read_parquet(Cursor::new(buf.data())).unwrap()
+    }
+
+    #[test]
+    fn round_trips_every_codec_with_temporal_dtypes() {
+        let df = temporal_frame();
+        for compression in &["uncompressed", "snappy", "gzip", "lz4", "zstd"] {
+            assert_frame_eq(&round_trip(&df, compression, 2), &df);
+        }
+    }
+
+    #[test]
+    fn round_trips_without_statistics() {
+        let df = temporal_frame();
+        let buf = SharedBuffer::default();
+        write_parquet(&df, buf.clone(), "snappy", 1, false).unwrap();
+        let reader = SerializedFileReader::new(Cursor::new(buf.data())).unwrap();
+        assert_eq!(reader.metadata().num_row_groups(), 3);
+        let stats = reader
+            .metadata()
+            .row_group(0)
+            .column(0)
+            .statistics()
+            .unwrap();
+        assert!(!stats.has_min_max_set());
+        assert_frame_eq(&read_parquet(Cursor::new(buf.data())).unwrap(), &df);
+    }
+
+    #[test]
+    fn round_trips_an_empty_frame() {
+        let df = temporal_frame().slice(0, 0).unwrap();
+        let read = round_trip(&df, "snappy", 10);
+        assert_eq!(read.height(), 0);
+        assert_eq!(read.schema(), df.schema());
+    }
+
+    #[test]
+    fn rejects_unknown_codecs_and_dtypes() {
+        let df = temporal_frame();
+        assert!(write_parquet(&df, SharedBuffer::default(), "brotli9", 10, true).is_err());
+
+        let list = Series::new("l", &[Series::new("", &[1i64])]);
+        let df = DataFrame::new(vec![list]).unwrap();
+        assert!(write_parquet(&df, SharedBuffer::default(), "snappy", 10, true).is_err());
+    }
+}
@@ -0,0 +1,640 @@
+// Eager scans of CSV and parquet files with column projection and a filter pushed down to the
+// reader.
+//
+// CSV scans go through the lazy engine, which pushes both into the CSV reader. For parquet the
+// row groups whose min/max statistics rule out the filter are skipped before anything is read,
+// and only the needed columns of the remaining row groups are decoded.
+
+use parquet::basic::LogicalType;
+use parquet::file::metadata::{FileMetaData, ParquetMetaData, RowGroupMetaData};
+use parquet::file::reader::{FileReader, RowGroupReader, SerializedFileReader};
+use parquet::file::statistics::Statistics;
+use parquet::record::reader::RowIter;
+use parquet::schema::types::Type as SchemaType;
+use polars::lazy::dsl::{col, Expr};
+use polars::prelude::*;
+use rustler::types::tuple::get_tuple;
+use rustler::{Decoder, NifResult, Term};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use std::rc::Rc;
+use std::result::Result;
+
+use crate::expr::decode_expr;
+use crate::lazy::csv_scan_plan;
+use crate::parquet_file::read_row_groups;
+use crate::ExPolarsError;
+
+#[derive(Clone, Copy)]
+enum Cmp {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+}
+
+impl Cmp {
+    fn from_atom(op: &str) -> Option<Self> {
+        match op {
+            "eq" => Some(Cmp::Eq),
+            "neq" => Some(Cmp::NotEq),
+            "lt" => Some(Cmp::Lt),
+            "lt_eq" => Some(Cmp::LtEq),
+            "gt" => Some(Cmp::Gt),
+            "gt_eq" => Some(Cmp::GtEq),
+            _ => None,
+        }
+    }
+
+    /// The operator with its operands swapped, `lit < col` is `col > lit`.
+    fn flip(self) -> Self {
+        match self {
+            Cmp::Lt => Cmp::Gt,
+            Cmp::LtEq => Cmp::GtEq,
+            Cmp::Gt => Cmp::Lt,
+            Cmp::GtEq => Cmp::LtEq,
+            op => op,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub(crate) enum Scalar {
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Bool(bool),
+}
+
+impl Scalar {
+    fn partial_cmp(&self, other: &Scalar) -> Option<Ordering> {
+        match (self, other) {
+            (Scalar::Int(a), Scalar::Int(b)) => Some(a.cmp(b)),
+            (Scalar::Int(a), Scalar::Float(b)) => (*a as f64).partial_cmp(b),
+            (Scalar::Float(a), Scalar::Int(b)) => a.partial_cmp(&(*b as f64)),
+            (Scalar::Float(a), Scalar::Float(b)) => a.partial_cmp(b),
+            (Scalar::Str(a), Scalar::Str(b)) => Some(a.cmp(b)),
+            (Scalar::Bool(a), Scalar::Bool(b)) => Some(a.cmp(b)),
+            _ => None,
+        }
+    }
+}
+
+/// The `(min, max)` values of columns.
+pub(crate) type ValueRanges = HashMap<String, (Scalar, Scalar)>;
+
+/// What a filter says about the values of columns, as far as statistics can tell.
+enum Bounds {
+    Cmp(String, Cmp, Scalar),
+    And(Box<Bounds>, Box<Bounds>),
+    Or(Box<Bounds>, Box<Bounds>),
+    Unknown,
+}
+
+fn column_name(term: Term) -> Option<String> {
+    if term.is_binary() {
+        return term.decode().ok();
+    }
+    match get_tuple(term).ok()?.as_slice() {
+        [tag, name] if tag.atom_to_string().ok()? == "col" => name.decode().ok(),
+        _ => None,
+    }
+}
+
+fn literal(term: Term) -> Option<Scalar> {
+    let value = match get_tuple(term).ok()?.as_slice() {
+        [tag, value] if tag.atom_to_string().ok()? == "lit" => *value,
+        _ => return None,
+    };
+    if value.is_binary() {
+        return value.decode().ok().map(Scalar::Str);
+    }
+    if let Ok(v) = value.decode::<bool>() {
+        return Some(Scalar::Bool(v));
+    }
+    if let Ok(v) = value.decode::<i64>() {
+        return Some(Scalar::Int(v));
+    }
+    value.decode::<f64>().ok().map(Scalar::Float)
+}
+
+/// An operand of a comparison.
+enum Operand {
+    Column(String),
+    Literal(Scalar),
+    Other,
+}
+
+impl Operand {
+    fn from_term(term: Term) -> Self {
+        if let Some(name) = column_name(term) {
+            return Operand::Column(name);
+        }
+        match literal(term) {
+            Some(value) => Operand::Literal(value),
+            None => Operand::Other,
+        }
+    }
+}
+
+impl Bounds {
+    fn from_term(term: Term) -> Self {
+        let t = match get_tuple(term) {
+            Ok(t) => t,
+            Err(_) => return Bounds::Unknown,
+        };
+        let (op, l, r) = match t.as_slice() {
+            [tag, op, l, r] if tag.atom_to_string().ok().as_deref() == Some("binary_expr") => {
+                (op.atom_to_string().unwrap_or_default(), *l, *r)
+            }
+            _ => return Bounds::Unknown,
+        };
+        match op.as_str() {
+            "and" => Bounds::And(Box::new(Self::from_term(l)), Box::new(Self::from_term(r))),
+            "or" => Bounds::Or(Box::new(Self::from_term(l)), Box::new(Self::from_term(r))),
+            op => match Cmp::from_atom(op) {
+                Some(cmp) => Self::compare(cmp, Operand::from_term(l), Operand::from_term(r)),
+                None => Bounds::Unknown,
+            },
+        }
+    }
+
+    /// `left cmp right`, as a comparison of a column with a literal.
+    fn compare(cmp: Cmp, left: Operand, right: Operand) -> Self {
+        match (left, right) {
+            (Operand::Column(name), Operand::Literal(value)) => Bounds::Cmp(name, cmp, value),
+            (Operand::Literal(value), Operand::Column(name)) => {
+                Bounds::Cmp(name, cmp.flip(), value)
+            }
+            _ => Bounds::Unknown,
+        }
+    }
+
+    /// True if no row with values in the `(min, max)` ranges of `stats` can match.
+    fn excludes(&self, stats: &ValueRanges) -> bool {
+        match self {
+            Bounds::Unknown => false,
+            Bounds::And(a, b) => a.excludes(stats) || b.excludes(stats),
+            Bounds::Or(a, b) => a.excludes(stats) && b.excludes(stats),
+            Bounds::Cmp(name, cmp, value) => {
+                let (min, max) = match stats.get(name) {
+                    Some(range) => range,
+                    None => return false,
+                };
+                let (to_min, to_max) = match (value.partial_cmp(min), value.partial_cmp(max)) {
+                    (Some(a), Some(b)) => (a, b),
+                    _ => return false,
+                };
+                match cmp {
+                    Cmp::Eq => to_min == Ordering::Less || to_max == Ordering::Greater,
+                    Cmp::NotEq => to_min == Ordering::Equal && to_max == Ordering::Equal,
+                    Cmp::Lt => to_min != Ordering::Greater,
+                    Cmp::LtEq => to_min == Ordering::Less,
+                    Cmp::Gt => to_max != Ordering::Less,
+                    Cmp::GtEq => to_max == Ordering::Greater,
+                }
+            }
+        }
+    }
+}
+
+/// A scan filter: the polars expression, plus what it says about column values so parquet row
+/// groups can be skipped by their statistics.
+pub struct ScanPredicate {
+    expr: Expr,
+    bounds: Bounds,
+    // every column the filter references, so they are read even when not selected
+    columns: Vec<String>,
+}
+
+/// Collect the column names in an expression term. Strings that aren't columns, like alias
+/// names, may slip in; they only cause an extra column to be read.
+fn term_columns(term: Term, out: &mut Vec<String>) {
+    if term.is_binary() {
+        if let Ok(name) = term.decode() {
+            out.push(name);
+        }
+        return;
+    }
+    if let Ok(t) = get_tuple(term) {
+        match t.as_slice() {
+            [tag, ..] if tag.atom_to_string().ok().as_deref() == Some("lit") => {}
+            [_, args @ ..] => args.iter().for_each(|arg| term_columns(*arg, out)),
+            [] => {}
+        }
+    }
+}
+
+impl ScanPredicate {
+    /// True if no row with column values within `ranges` can match the filter.
+    pub(crate) fn excludes(&self, ranges: &ValueRanges) -> bool {
+        self.bounds.excludes(ranges)
+    }
+
+    /// The columns the filter references.
+    pub(crate) fn columns(&self) -> &[String] {
+        &self.columns
+    }
+}
+
+#[cfg(test)]
+impl ScanPredicate {
+    /// `column == value`, as decoded from `{:binary_expr, :eq, {:col, column}, {:lit, value}}`.
+    pub(crate) fn int_eq(column: &str, value: i64) -> Self {
+        use polars::lazy::dsl::{binary_expr, lit, Operator};
+        ScanPredicate {
+            expr: binary_expr(col(column), Operator::Eq, lit(value)),
+            bounds: Bounds::Cmp(column.to_string(), Cmp::Eq, Scalar::Int(value)),
+            columns: vec![column.to_string()],
+        }
+    }
+}
+
+impl<'a> Decoder<'a> for ScanPredicate {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let mut columns = vec![];
+        term_columns(term, &mut columns);
+        Ok(ScanPredicate {
+            expr: decode_expr(term)?,
+            bounds: Bounds::from_term(term),
+            columns,
+        })
+    }
+}
+
+pub(crate) fn filter_select(
+    ldf: LazyFrame,
+    columns: Option<Vec<String>>,
+    predicate: Option<ScanPredicate>,
+) -> Result<DataFrame, ExPolarsError> {
+    let mut ldf = ldf;
+    if let Some(predicate) = predicate {
+        ldf = ldf.filter(predicate.expr);
+    }
+    if let Some(columns) = columns {
+        ldf = ldf.select(columns.iter().map(|c| col(c)).collect::<Vec<_>>());
+    }
+    Ok(ldf.collect()?)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn scan_csv(
+    filename: String,
+    columns: Option<Vec<String>>,
+    predicate: Option<ScanPredicate>,
+    sep: u8,
+    has_header: bool,
+    ignore_errors: bool,
+    skip_rows: usize,
+    stop_after_n_rows: Option<usize>,
+) -> Result<DataFrame, ExPolarsError> {
+    // the plan is collected once, there is nothing to cache
+    let ldf = csv_scan_plan(
+        filename,
+        sep,
+        has_header,
+        ignore_errors,
+        skip_rows,
+        stop_after_n_rows,
+        false,
+    )?;
+    filter_select(ldf, columns, predicate)
+}
+
+/// The `(min, max)` of a column chunk, if its statistics can be trusted for pruning.
+fn value_range(stats: &Statistics, logical_type: LogicalType) -> Option<(Scalar, Scalar)> {
+    if !stats.has_min_max_set() {
+        return None;
+    }
+    match stats {
+        Statistics::Boolean(s) => Some((Scalar::Bool(*s.min()), Scalar::Bool(*s.max()))),
+        // unsigned integers are stored as signed ones, so their statistics may be out of order
+        Statistics::Int32(_) | Statistics::Int64(_)
+            if matches!(
+                logical_type,
+                LogicalType::UINT_8
+                    | LogicalType::UINT_16
+                    | LogicalType::UINT_32
+                    | LogicalType::UINT_64
+            ) =>
+        {
+            None
+        }
+        Statistics::Int32(s) => Some((Scalar::Int(*s.min() as i64), Scalar::Int(*s.max() as i64))),
+        Statistics::Int64(s) => Some((Scalar::Int(*s.min()), Scalar::Int(*s.max()))),
+        Statistics::Float(s) => Some((
+            Scalar::Float(*s.min() as f64),
+            Scalar::Float(*s.max() as f64),
+        )),
+        Statistics::Double(s) => Some((Scalar::Float(*s.min()), Scalar::Float(*s.max()))),
+        Statistics::ByteArray(s) => {
+            let (min, max) = (s.min().as_utf8().ok()?, s.max().as_utf8().ok()?);
+            // older writers compare bytes as signed, which only orders ascii correctly
+            if min.is_ascii() && max.is_ascii() {
+                Some((Scalar::Str(min.to_string()), Scalar::Str(max.to_string())))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+fn row_group_stats(rg: &RowGroupMetaData) -> ValueRanges {
+    rg.columns()
+        .iter()
+        .filter_map(|c| {
+            let range = value_range(c.statistics()?, c.column_descr().logical_type())?;
+            Some((c.column_path().string(), range))
+        })
+        .collect()
+}
+
+/// A parquet file reader that only sees some of the row groups of the file.
+struct RowGroupSelection {
+    reader: SerializedFileReader<File>,
+    row_groups: Vec<usize>,
+    metadata: ParquetMetaData,
+}
+
+impl RowGroupSelection {
+    fn new(
+        reader: SerializedFileReader<File>,
+        row_groups: Vec<usize>,
+    ) -> Result<Self, ExPolarsError> {
+        let metadata = reader.metadata();
+        let file = metadata.file_metadata();
+        let schema_descr = file.schema_descr_ptr();
+        let selected = row_groups
+            .iter()
+            .map(|i| {
+                RowGroupMetaData::from_thrift(
+                    schema_descr.clone(),
+                    metadata.row_group(*i).to_thrift(),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let num_rows = selected.iter().map(|rg| rg.num_rows()).sum();
+        let file_metadata = FileMetaData::new(
+            file.version(),
+            num_rows,
+            file.created_by().clone(),
+            file.key_value_metadata().clone(),
+            Rc::new(file.schema().clone()),
+            schema_descr,
+            file.column_orders().cloned(),
+        );
+        let metadata = ParquetMetaData::new(file_metadata, selected);
+        Ok(Self {
+            reader,
+            row_groups,
+            metadata,
+        })
+    }
+}
+
+impl FileReader for RowGroupSelection {
+    fn metadata(&self) -> &ParquetMetaData {
+        &self.metadata
+    }
+
+    fn num_row_groups(&self) -> usize {
+        self.row_groups.len()
+    }
+
+    fn get_row_group(&self, i: usize) -> parquet::errors::Result<Box<dyn RowGroupReader + '_>> {
+        self.reader.get_row_group(self.row_groups[i])
+    }
+
+    fn get_row_iter(&self, projection: Option<SchemaType>) -> parquet::errors::Result<RowIter> {
+        RowIter::from_file(projection, self)
+    }
+}
+
+/// The row groups `predicate` doesn't exclude by their statistics.
+fn selected_row_groups(
+    metadata: &ParquetMetaData,
+    predicate: Option<&ScanPredicate>,
+) -> Vec<usize> {
+    (0..metadata.num_row_groups())
+        .filter(|i| match predicate {
+            Some(p) => !p.excludes(&row_group_stats(metadata.row_group(*i))),
+            None => true,
+        })
+        .collect()
+}
+
+/// Read the row groups `select` picks from the metadata of a parquet file, only decoding
+/// `columns` and the columns `predicate` needs.
+fn read_parquet_selected(
+    path: &Path,
+    columns: Option<&[String]>,
+    predicate: Option<&ScanPredicate>,
+    select: impl FnOnce(&ParquetMetaData) -> Vec<usize>,
+) -> Result<DataFrame, ExPolarsError> {
+    let reader = SerializedFileReader::new(File::open(path)?)?;
+    let metadata = reader.metadata();
+    let row_groups = select(metadata);
+
+    // the projected columns plus those the filter needs, by leaf index
+    let projection = columns.map(|columns| {
+        let mut needed: Vec<&str> = columns.iter().map(|c| c.as_str()).collect();
+        if let Some(p) = predicate {
+            needed.extend(p.columns.iter().map(|c| c.as_str()));
+        }
+        let schema = metadata.file_metadata().schema_descr();
+        (0..schema.num_columns())
+            .filter(|i| needed.contains(&schema.column(*i).path().string().as_str()))
+            .collect::<Vec<usize>>()
+    });
+
+    let selection = RowGroupSelection::new(reader, row_groups)?;
+    read_row_groups(Rc::new(selection), projection)
+}
+
+/// Read a parquet file, skipping the row groups `predicate` excludes by their statistics and
+/// only decoding `columns` and the columns `predicate` needs. The filter itself isn't applied.
+pub(crate) fn read_parquet_pruned(
+    path: &Path,
+    columns: Option<&[String]>,
+    predicate: Option<&ScanPredicate>,
+) -> Result<DataFrame, ExPolarsError> {
+    read_parquet_selected(path, columns, predicate, |metadata| {
+        selected_row_groups(metadata, predicate)
+    })
+}
+
+/// The columns `read_parquet_pruned` reads, without any rows. Only the footer is read.
+pub(crate) fn read_parquet_schema(
+    path: &Path,
+    columns: Option<&[String]>,
+    predicate: Option<&ScanPredicate>,
+) -> Result<DataFrame, ExPolarsError> {
+    read_parquet_selected(path, columns, predicate, |_| vec![])
+}
+
+pub(crate) fn scan_parquet(
+    filename: &str,
+    columns: Option<Vec<String>>,
+    predicate: Option<ScanPredicate>,
+) -> Result<DataFrame, ExPolarsError> {
+    let df = read_parquet_pruned(Path::new(filename), columns.as_deref(), predicate.as_ref())?;
+    filter_select(df.lazy(), columns, predicate)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parquet_file::{read_parquet, FrameWriter};
+    use parquet::basic::Compression;
+    use polars::lazy::dsl::{binary_expr, lit, Operator};
+
+    // three row groups with x in 0..10, 10..20 and 20..30
+    fn parquet_file(name: &str) -> String {
+        let name = format!("expolars-scan-{}-{}.parquet", std::process::id(), name);
+        let path = std::env::temp_dir().join(name);
+        let path = path.to_str().unwrap().to_string();
+        let groups: Vec<DataFrame> = (0..3i64)
+            .map(|g| {
+                let x: Vec<i64> = (g * 10..g * 10 + 10).collect();
+                let name: Vec<String> = x.iter().map(|x| format!("r{:02}", x)).collect();
+                let name: Vec<&str> = name.iter().map(|s| s.as_str()).collect();
+                DataFrame::new(vec![
+                    Series::new("x", x.as_slice()),
+                    Series::new("name", name.as_slice()),
+                ])
+                .unwrap()
+            })
+            .collect();
+        let file = File::create(&path).unwrap();
+        let schema = groups[0].schema();
+        let mut writer = FrameWriter::try_new(file, &schema, Compression::SNAPPY, true).unwrap();
+        for df in &groups {
+            writer.write(df).unwrap();
+        }
+        writer.close().unwrap();
+        path
+    }
+
+    fn x_lit(cmp: Cmp, value: i64) -> Expr {
+        let op = match cmp {
+            Cmp::Eq => Operator::Eq,
+            Cmp::NotEq => Operator::NotEq,
+            Cmp::Lt => Operator::Lt,
+            Cmp::LtEq => Operator::LtEq,
+            Cmp::Gt => Operator::Gt,
+            Cmp::GtEq => Operator::GtEq,
+        };
+        binary_expr(col("x"), op, lit(value))
+    }
+
+    /// `x cmp value`, or `value cmp x` when flipped.
+    fn predicate(cmp: Cmp, value: i64, flipped: bool) -> ScanPredicate {
+        let (left, right, expr) = if flipped {
+            let expr = x_lit(cmp.flip(), value);
+            (
+                Operand::Literal(Scalar::Int(value)),
+                Operand::Column("x".into()),
+                expr,
+            )
+        } else {
+            (
+                Operand::Column("x".into()),
+                Operand::Literal(Scalar::Int(value)),
+                x_lit(cmp, value),
+            )
+        };
+        ScanPredicate {
+            expr,
+            bounds: Bounds::compare(cmp, left, right),
+            columns: vec!["x".into()],
+        }
+    }
+
+    fn row_groups(path: &str, predicate: &ScanPredicate) -> Vec<usize> {
+        let reader = SerializedFileReader::new(File::open(path).unwrap()).unwrap();
+        selected_row_groups(reader.metadata(), Some(predicate))
+    }
+
+    #[test]
+    fn row_groups_are_pruned_by_their_statistics() {
+        let path = parquet_file("prune");
+        let cases = vec![
+            (Cmp::Eq, 15, vec![1]),
+            (Cmp::Eq, 10, vec![1]),
+            (Cmp::NotEq, 15, vec![0, 1, 2]),
+            (Cmp::Lt, 10, vec![0]),
+            (Cmp::LtEq, 10, vec![0, 1]),
+            (Cmp::Gt, 19, vec![2]),
+            (Cmp::GtEq, 19, vec![1, 2]),
+            // without row groups `read_row_groups` reads the schema alone
+            (Cmp::Gt, 100, vec![]),
+        ];
+        for (cmp, value, expected) in cases {
+            assert_eq!(row_groups(&path, &predicate(cmp, value, false)), expected);
+        }
+        // `10 > x` is `x < 10`
+        let flipped = vec![
+            (Cmp::Gt, 10, vec![0]),
+            (Cmp::GtEq, 10, vec![0, 1]),
+            (Cmp::Lt, 19, vec![2]),
+            (Cmp::LtEq, 19, vec![1, 2]),
+            (Cmp::Eq, 25, vec![2]),
+        ];
+        for (cmp, value, expected) in flipped {
+            assert_eq!(row_groups(&path, &predicate(cmp, value, true)), expected);
+        }
+
+        let between = ScanPredicate {
+            expr: binary_expr(x_lit(Cmp::GtEq, 12), Operator::And, x_lit(Cmp::Lt, 18)),
+            bounds: Bounds::And(
+                Box::new(predicate(Cmp::GtEq, 12, false).bounds),
+                Box::new(predicate(Cmp::Lt, 18, false).bounds),
+            ),
+            columns: vec!["x".into()],
+        };
+        assert_eq!(row_groups(&path, &between), vec![1]);
+        let either = ScanPredicate {
+            expr: binary_expr(x_lit(Cmp::Lt, 5), Operator::Or, x_lit(Cmp::Gt, 25)),
+            bounds: Bounds::Or(
+                Box::new(predicate(Cmp::Lt, 5, false).bounds),
+                Box::new(predicate(Cmp::Gt, 25, false).bounds),
+            ),
+            columns: vec!["x".into()],
+        };
+        assert_eq!(row_groups(&path, &either), vec![0, 2]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn pruned_scans_match_unpruned_ones() {
+        let path = parquet_file("scan");
+        let full = read_parquet(File::open(&path).unwrap()).unwrap();
+        let cmps = [Cmp::Eq, Cmp::NotEq, Cmp::Lt, Cmp::LtEq, Cmp::Gt, Cmp::GtEq];
+        for cmp in cmps.iter() {
+            for value in &[-1, 0, 9, 10, 15, 29, 30] {
+                for flipped in &[false, true] {
+                    let columns = Some(vec!["name".to_string()]);
+                    let pruned = scan_parquet(
+                        &path,
+                        columns.clone(),
+                        Some(predicate(*cmp, *value, *flipped)),
+                    )
+                    .unwrap();
+                    let unpruned = filter_select(
+                        full.clone().lazy(),
+                        columns,
+                        Some(predicate(*cmp, *value, *flipped)),
+                    )
+                    .unwrap();
+                    assert!(pruned.frame_equal(&unpruned));
+                }
+            }
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,6 +1,12 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
 use polars::prelude::*;
+use rustler::{Env, Term};
+use serde_json::Value;
+use std::convert::TryFrom;
 use std::result::Result;
 
+use crate::sort::{arg_sort_series, top_k_series};
+use crate::term::to_terms;
 use crate::{DataType, ExDataFrame, ExPolarsError, ExSeries};
 
 pub(crate) fn to_series_collection(s: Vec<ExSeries>) -> Vec<Series> {
@@ -8,7 +14,7 @@ pub(crate) fn to_series_collection(s: Vec<ExSeries>) -> Vec<Series> {
 }
 
 pub(crate) fn to_ex_series_collection(s: Vec<Series>) -> Vec<ExSeries> {
-    s.into_iter().map(|c| ExSeries::new(c)).collect()
+    s.into_iter().map(ExSeries::new).collect()
 }
 
 #[rustler::nif]
@@ -20,7 +26,7 @@ pub fn s_as_str(data: ExSeries) -> Result<String, ExPolarsError> {
 // Init with arrays
 macro_rules! init_method {
     ($name:ident, $type:ty) => {
-        #[rustler::nif]
+        #[rustler::nif(schedule = "DirtyCpu")]
         pub fn $name(name: &str, val: Vec<$type>) -> ExSeries {
             ExSeries::new(Series::new(name, val.as_slice()))
         }
@@ -43,19 +49,47 @@ init_method!(s_new_time_ns, i64);
 init_method!(s_new_f32, f32);
 init_method!(s_new_f64, f64);
 
-#[rustler::nif]
+// Init with arrays which may contain nil, nils become nulls
+macro_rules! init_method_opt {
+    ($name:ident, $ca:ty, $type:ty) => {
+        #[rustler::nif(schedule = "DirtyCpu")]
+        pub fn $name(name: &str, val: Vec<Option<$type>>) -> Result<ExSeries, ExPolarsError> {
+            Ok(ExSeries::new(<$ca>::new_from_opt_slice(name, &val).into_series()))
+        }
+    };
+}
+
+init_method_opt!(s_new_opt_i8, Int8Chunked, i8);
+init_method_opt!(s_new_opt_i16, Int16Chunked, i16);
+init_method_opt!(s_new_opt_i32, Int32Chunked, i32);
+init_method_opt!(s_new_opt_i64, Int64Chunked, i64);
+init_method_opt!(s_new_opt_bool, BooleanChunked, bool);
+init_method_opt!(s_new_opt_u8, UInt8Chunked, u8);
+init_method_opt!(s_new_opt_u16, UInt16Chunked, u16);
+init_method_opt!(s_new_opt_u32, UInt32Chunked, u32);
+init_method_opt!(s_new_opt_u64, UInt64Chunked, u64);
+init_method_opt!(s_new_opt_date32, Date32Chunked, i32);
+init_method_opt!(s_new_opt_date64, Date64Chunked, i64);
+init_method_opt!(s_new_opt_duration_ns, DurationNanosecondChunked, i64);
+init_method_opt!(s_new_opt_duration_ms, DurationMillisecondChunked, i64);
+init_method_opt!(s_new_opt_time_ns, Time64NanosecondChunked, i64);
+init_method_opt!(s_new_opt_f32, Float32Chunked, f32);
+init_method_opt!(s_new_opt_f64, Float64Chunked, f64);
+init_method_opt!(s_new_opt_str, Utf8Chunked, &str);
+
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_parse_date32_from_str_slice(name: &str, val: Vec<&str>, fmt: &str) -> ExSeries {
     let parsed = Date32Chunked::parse_from_str_slice(name, &val, fmt);
     ExSeries::new(parsed.into_series())
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_new_str(name: &str, val: Vec<&str>) -> ExSeries {
     let chunked: Utf8Chunked = ChunkedArray::new_from_slice(name, &val);
     ExSeries::new(chunked.into_series())
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_rechunk(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let series = s.rechunk(None).expect("should not fail");
@@ -82,7 +116,7 @@ pub fn s_rename(data: ExSeries, name: &str) -> Result<ExSeries, ExPolarsError> {
 #[rustler::nif]
 pub fn s_dtype(data: ExSeries) -> Result<u8, ExPolarsError> {
     let s = &data.inner.0;
-    let dt: DataType = s.dtype().into();
+    let dt = DataType::try_from(s.dtype())?;
     Ok(dt as u8)
 }
 
@@ -114,7 +148,7 @@ pub fn s_append(data: ExSeries, other: ExSeries) -> Result<ExSeries, ExPolarsErr
     Ok(ExSeries::new(s))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_filter(data: ExSeries, filter: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let s1 = &filter.inner.0;
@@ -126,28 +160,28 @@ pub fn s_filter(data: ExSeries, filter: ExSeries) -> Result<ExSeries, ExPolarsEr
     }
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_add(data: ExSeries, other: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let s1 = &other.inner.0;
     Ok(ExSeries::new(s + s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_sub(data: ExSeries, other: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let s1 = &other.inner.0;
     Ok(ExSeries::new(s - s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_mul(data: ExSeries, other: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let s1 = &other.inner.0;
     Ok(ExSeries::new(s * s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_div(data: ExSeries, other: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let s1 = &other.inner.0;
@@ -166,47 +200,74 @@ pub fn s_tail(data: ExSeries, length: Option<usize>) -> Result<ExSeries, ExPolar
     Ok(ExSeries::new(s.tail(length)))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_sort(data: ExSeries, reverse: bool) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     Ok(ExSeries::new(s.sort(reverse)))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_argsort(data: ExSeries, reverse: bool) -> Result<Vec<usize>, ExPolarsError> {
     let s = &data.inner.0;
     Ok(s.argsort(reverse))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn s_sort_by(
+    data: ExSeries,
+    descending: bool,
+    nulls_last: bool,
+) -> Result<ExSeries, ExPolarsError> {
+    let s = &data.inner.0;
+    let idx = arg_sort_series(s, descending, nulls_last)?;
+    Ok(ExSeries::new(s.take(&idx)))
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn s_argsort_by(
+    data: ExSeries,
+    descending: bool,
+    nulls_last: bool,
+) -> Result<Vec<usize>, ExPolarsError> {
+    let s = &data.inner.0;
+    arg_sort_series(s, descending, nulls_last)
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn s_top_k(data: ExSeries, k: usize, bottom: bool) -> Result<ExSeries, ExPolarsError> {
+    let s = &data.inner.0;
+    Ok(ExSeries::new(top_k_series(s, k, bottom)?))
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_unique(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let unique = s.unique()?;
     Ok(ExSeries::new(unique))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_value_counts(data: ExSeries) -> Result<ExDataFrame, ExPolarsError> {
     let s = &data.inner.0;
     let df = s.value_counts()?;
     Ok(ExDataFrame::new(df))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_arg_unique(data: ExSeries) -> Result<Vec<usize>, ExPolarsError> {
     let s = &data.inner.0;
     let arg_unique = s.arg_unique()?;
     Ok(arg_unique)
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_take(data: ExSeries, indices: Vec<usize>) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let s1 = s.take(&indices);
     Ok(ExSeries::new(s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_take_with_series(data: ExSeries, indices: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let s1 = &indices.inner.0;
@@ -221,33 +282,33 @@ pub fn s_null_count(data: ExSeries) -> Result<usize, ExPolarsError> {
     Ok(s.null_count())
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_is_null(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     Ok(ExSeries::new(s.is_null().into_series()))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_is_not_null(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     Ok(ExSeries::new(s.is_not_null().into_series()))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_is_unique(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let ca = s.is_unique()?;
     Ok(ExSeries::new(ca.into_series()))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_arg_true(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let ca = s.arg_true()?;
     Ok(ExSeries::new(ca.into_series()))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_sample_n(
     data: ExSeries,
     n: usize,
@@ -258,7 +319,7 @@ pub fn s_sample_n(
     Ok(ExSeries::new(s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_sample_frac(
     data: ExSeries,
     frac: f64,
@@ -269,28 +330,28 @@ pub fn s_sample_frac(
     Ok(ExSeries::new(s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_is_duplicated(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let ca = s.is_duplicated()?;
     Ok(ExSeries::new(ca.into_series()))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_explode(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let s1 = s.explode()?;
     Ok(ExSeries::new(s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_take_every(data: ExSeries, n: usize) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let s1 = s.take_every(n);
     Ok(ExSeries::new(s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_series_equal(
     data: ExSeries,
     other: ExSeries,
@@ -306,49 +367,49 @@ pub fn s_series_equal(
     Ok(result)
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_eq(data: ExSeries, rhs: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let s1 = &rhs.inner.0;
     Ok(ExSeries::new(s.eq(s1).into_series()))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_neq(data: ExSeries, rhs: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let s1 = &rhs.inner.0;
     Ok(ExSeries::new(s.neq(s1).into_series()))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_gt(data: ExSeries, rhs: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let s1 = &rhs.inner.0;
     Ok(ExSeries::new(s.gt(s1).into_series()))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_gt_eq(data: ExSeries, rhs: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let s1 = &rhs.inner.0;
     Ok(ExSeries::new(s.gt_eq(s1).into_series()))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_lt(data: ExSeries, rhs: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let s1 = &rhs.inner.0;
     Ok(ExSeries::new(s.lt(s1).into_series()))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_lt_eq(data: ExSeries, rhs: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let s1 = &rhs.inner.0;
     Ok(ExSeries::new(s.lt_eq(s1).into_series()))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_not(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let bool = s.bool()?;
@@ -361,76 +422,153 @@ pub fn s_len(data: ExSeries) -> Result<usize, ExPolarsError> {
     Ok(s.len())
 }
 
-// TODO(tchen): Find an elegant way later
-#[rustler::nif]
-pub fn s_to_json(data: ExSeries) -> Result<String, ExPolarsError> {
-    let s = &data.inner.0;
-
-    let st = match s.dtype() {
-        ArrowDataType::Boolean => {
-            let arr: Vec<bool> = s
-                .bool()
-                .unwrap()
-                .into_iter()
-                .map(|item| item.unwrap())
-                .collect();
-            serde_json::to_string(&arr)
-        }
-        ArrowDataType::Utf8 => {
-            let arr: Vec<String> = s
-                .utf8()
-                .unwrap()
-                .into_iter()
-                .map(|item| item.unwrap().into())
-                .collect();
-            serde_json::to_string(&arr)
+/// The date `v` days after the epoch, an error if it's beyond the dates chrono can represent.
+pub(crate) fn date32_to_naive(v: i32) -> Result<NaiveDate, ExPolarsError> {
+    NaiveDate::from_ymd(1970, 1, 1)
+        .checked_add_signed(Duration::days(v as i64))
+        .ok_or_else(|| ExPolarsError::Other(format!("date32 {} is out of range", v)))
+}
+
+/// The datetime `v` milliseconds after the epoch, an error if it's beyond the dates chrono can
+/// represent.
+pub(crate) fn date64_to_naive(v: i64) -> Result<NaiveDateTime, ExPolarsError> {
+    NaiveDateTime::from_timestamp_opt(
+        v.div_euclid(1_000),
+        (v.rem_euclid(1_000) * 1_000_000) as u32,
+    )
+    .ok_or_else(|| ExPolarsError::Other(format!("date64 {} is out of range", v)))
+}
+
+const NANOS_PER_DAY: i64 = 86_400 * 1_000_000_000;
+
+pub(crate) fn time64ns_to_naive(v: i64) -> NaiveTime {
+    let v = v.rem_euclid(NANOS_PER_DAY);
+    NaiveTime::from_num_seconds_from_midnight(
+        (v / 1_000_000_000) as u32,
+        (v % 1_000_000_000) as u32,
+    )
+}
+
+fn json_values<T, I>(iter: I) -> Vec<Value>
+where
+    I: Iterator<Item = Option<T>>,
+    T: Into<Value>,
+{
+    iter.map(|v| v.map_or(Value::Null, Into::into)).collect()
+}
+
+fn json_strings<T, I, F>(iter: I, f: F) -> Result<Vec<Value>, ExPolarsError>
+where
+    I: Iterator<Item = Option<T>>,
+    F: Fn(T) -> Result<String, ExPolarsError>,
+{
+    iter.map(|v| match v {
+        Some(v) => Ok(Value::String(f(v)?)),
+        None => Ok(Value::Null),
+    })
+    .collect()
+}
+
+/// Convert a series into json values. Missing values become `null`, durations stay integers and
+/// lists are converted recursively. Dates and times become ISO 8601 strings with `iso_dates`,
+/// otherwise they stay the integers they're stored as (days or milliseconds since the epoch,
+/// nanoseconds since midnight), which is what `s_to_json` has always returned.
+pub(crate) fn to_json_values(s: &Series, iso_dates: bool) -> Result<Vec<Value>, ExPolarsError> {
+    let values = match s.dtype() {
+        ArrowDataType::Boolean => json_values(s.bool()?.into_iter()),
+        ArrowDataType::Utf8 => json_values(s.utf8()?.into_iter()),
+        ArrowDataType::UInt8 => json_values(s.u8()?.into_iter()),
+        ArrowDataType::UInt16 => json_values(s.u16()?.into_iter()),
+        ArrowDataType::UInt32 => json_values(s.u32()?.into_iter()),
+        ArrowDataType::UInt64 => json_values(s.u64()?.into_iter()),
+        ArrowDataType::Int8 => json_values(s.i8()?.into_iter()),
+        ArrowDataType::Int16 => json_values(s.i16()?.into_iter()),
+        ArrowDataType::Int32 => json_values(s.i32()?.into_iter()),
+        ArrowDataType::Int64 => json_values(s.i64()?.into_iter()),
+        ArrowDataType::Float32 => json_values(s.f32()?.into_iter()),
+        ArrowDataType::Float64 => json_values(s.f64()?.into_iter()),
+        ArrowDataType::Date32(DateUnit::Day) if iso_dates => {
+            json_strings(s.date32()?.into_iter(), |v| {
+                Ok(date32_to_naive(v)?.to_string())
+            })?
         }
-        ArrowDataType::UInt8 => serde_json::to_string(&s.u8().unwrap().data_views()),
-        ArrowDataType::UInt16 => serde_json::to_string(&s.u16().unwrap().data_views()),
-        ArrowDataType::UInt32 => serde_json::to_string(&s.u32().unwrap().data_views()),
-        ArrowDataType::UInt64 => serde_json::to_string(&s.u64().unwrap().data_views()),
-        ArrowDataType::Int8 => serde_json::to_string(&s.i8().unwrap().data_views()),
-        ArrowDataType::Int16 => serde_json::to_string(&s.i16().unwrap().data_views()),
-        ArrowDataType::Int32 => serde_json::to_string(&s.i32().unwrap().data_views()),
-        ArrowDataType::Int64 => serde_json::to_string(&s.i64().unwrap().data_views()),
-        ArrowDataType::Float32 => serde_json::to_string(&s.f32().unwrap().data_views()),
-        ArrowDataType::Float64 => serde_json::to_string(&s.f64().unwrap().data_views()),
-        ArrowDataType::Date32(DateUnit::Day) => {
-            serde_json::to_string(&s.date32().unwrap().data_views())
+        ArrowDataType::Date32(DateUnit::Day) => json_values(s.date32()?.into_iter()),
+        ArrowDataType::Date64(DateUnit::Millisecond) if iso_dates => {
+            json_strings(s.date64()?.into_iter(), |v| {
+                Ok(date64_to_naive(v)?
+                    .format("%Y-%m-%dT%H:%M:%S%.f")
+                    .to_string())
+            })?
         }
-        ArrowDataType::Date64(DateUnit::Millisecond) => {
-            serde_json::to_string(&s.date64().unwrap().data_views())
+        ArrowDataType::Date64(DateUnit::Millisecond) => json_values(s.date64()?.into_iter()),
+        ArrowDataType::Time64(TimeUnit::Nanosecond) if iso_dates => {
+            json_strings(s.time64_nanosecond()?.into_iter(), |v| {
+                Ok(time64ns_to_naive(v).format("%H:%M:%S%.f").to_string())
+            })?
         }
         ArrowDataType::Time64(TimeUnit::Nanosecond) => {
-            serde_json::to_string(&s.time64_nanosecond().unwrap().data_views())
+            json_values(s.time64_nanosecond()?.into_iter())
         }
         ArrowDataType::Duration(TimeUnit::Nanosecond) => {
-            serde_json::to_string(&s.duration_nanosecond().unwrap().data_views())
+            json_values(s.duration_nanosecond()?.into_iter())
         }
         ArrowDataType::Duration(TimeUnit::Millisecond) => {
-            serde_json::to_string(&s.duration_millisecond().unwrap().data_views())
+            json_values(s.duration_millisecond()?.into_iter())
+        }
+        ArrowDataType::List(_) => {
+            let mut v = Vec::with_capacity(s.len());
+            for item in s.list()?.into_iter() {
+                v.push(match item {
+                    Some(inner) => Value::Array(to_json_values(&inner, iso_dates)?),
+                    None => Value::Null,
+                });
+            }
+            v
         }
         ArrowDataType::Binary => {
             let mut v = Vec::with_capacity(s.len());
             for i in 0..s.len() {
-                let val = s.get_as_any(i).downcast_ref::<Vec<u8>>().unwrap();
-                v.push(val);
+                match s.get_as_any(i).downcast_ref::<Vec<u8>>() {
+                    Some(bytes) => v.push(Value::from(bytes.as_slice())),
+                    None => {
+                        return Err(ExPolarsError::Other(format!(
+                            "object series {} cannot be converted to json",
+                            s.name()
+                        )))
+                    }
+                }
             }
-            serde_json::to_string(&v)
+            v
+        }
+        dt => {
+            return Err(ExPolarsError::Other(format!(
+                "json conversion not implemented for {:?}",
+                dt
+            )))
         }
-        dt => panic!(format!("to_list() not implemented for {:?}", dt)),
     };
+    Ok(values)
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn s_to_json(data: ExSeries) -> Result<String, ExPolarsError> {
+    let s = &data.inner.0;
+    Ok(serde_json::to_string(&to_json_values(s, false)?)?)
+}
 
-    Ok(st?)
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn s_to_list(env: Env, data: ExSeries) -> Result<Vec<Term>, ExPolarsError> {
+    let s = &data.inner.0;
+    to_terms(env, s)
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_drop_nulls(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     Ok(ExSeries::new(s.drop_nulls()))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_fill_none(data: ExSeries, strategy: &str) -> Result<ExSeries, ExPolarsError> {
     let strat = match strategy {
         "backward" => FillNoneStrategy::Backward,
@@ -438,7 +576,7 @@ pub fn s_fill_none(data: ExSeries, strategy: &str) -> Result<ExSeries, ExPolarsE
         "min" => FillNoneStrategy::Min,
         "max" => FillNoneStrategy::Max,
         "mean" => FillNoneStrategy::Mean,
-        s => return Err(ExPolarsError::Other(format!("Strategy {} not supported", s)).into()),
+        s => return Err(ExPolarsError::Other(format!("Strategy {} not supported", s))),
     };
 
     let s = &data.inner.0;
@@ -452,14 +590,14 @@ pub fn s_clone(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     Ok(ExSeries::new(s.clone()))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_shift(data: ExSeries, periods: i32) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let s1 = s.shift(periods)?;
     Ok(ExSeries::new(s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_zip_with(
     data: ExSeries,
     mask: ExSeries,
@@ -473,7 +611,7 @@ pub fn s_zip_with(
     Ok(ExSeries::new(s2))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_str_lengths(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let ca = s.utf8()?;
@@ -481,7 +619,7 @@ pub fn s_str_lengths(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     Ok(ExSeries::new(s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_str_contains(data: ExSeries, pat: &str) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let ca = s.utf8()?;
@@ -489,7 +627,7 @@ pub fn s_str_contains(data: ExSeries, pat: &str) -> Result<ExSeries, ExPolarsErr
     Ok(ExSeries::new(s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_str_replace(data: ExSeries, pat: &str, val: &str) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let ca = s.utf8()?;
@@ -497,7 +635,7 @@ pub fn s_str_replace(data: ExSeries, pat: &str, val: &str) -> Result<ExSeries, E
     Ok(ExSeries::new(s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_str_replace_all(data: ExSeries, pat: &str, val: &str) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let ca = s.utf8()?;
@@ -505,7 +643,7 @@ pub fn s_str_replace_all(data: ExSeries, pat: &str, val: &str) -> Result<ExSerie
     Ok(ExSeries::new(s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_str_to_uppercase(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let ca = s.utf8()?;
@@ -513,7 +651,7 @@ pub fn s_str_to_uppercase(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     Ok(ExSeries::new(s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_str_to_lowercase(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let ca = s.utf8()?;
@@ -521,36 +659,36 @@ pub fn s_str_to_lowercase(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     Ok(ExSeries::new(s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_str_parse_date32(data: ExSeries, fmt: Option<&str>) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     if let Ok(ca) = s.utf8() {
         let ca = ca.as_date32(fmt)?;
         Ok(ExSeries::new(ca.into_series()))
     } else {
-        Err(ExPolarsError::Other("cannot parse date32 expected utf8 type".into()).into())
+        Err(ExPolarsError::Other("cannot parse date32 expected utf8 type".into()))
     }
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_str_parse_date64(data: ExSeries, fmt: Option<&str>) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     if let Ok(ca) = s.utf8() {
         let ca = ca.as_date64(fmt)?;
         Ok(ExSeries::new(ca.into_series()))
     } else {
-        Err(ExPolarsError::Other("cannot parse date64 expected utf8 type".into()).into())
+        Err(ExPolarsError::Other("cannot parse date64 expected utf8 type".into()))
     }
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_datetime_str_fmt(data: ExSeries, fmt: &str) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let s1 = s.datetime_str_fmt(fmt)?;
     Ok(ExSeries::new(s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_as_duration(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     match s.dtype() {
@@ -564,12 +702,11 @@ pub fn s_as_duration(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
         }
         _ => Err(ExPolarsError::Other(
             "Only date32 and date64 can be transformed as duration".into(),
-        )
-        .into()),
+        )),
     }
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_to_dummies(data: ExSeries) -> Result<ExDataFrame, ExPolarsError> {
     let s = &data.inner.0;
     let df = s.to_dummies()?;
@@ -581,13 +718,13 @@ pub fn s_get_list(data: ExSeries, index: usize) -> Option<ExSeries> {
     let s = &data.inner.0;
     if let Ok(ca) = s.list() {
         let s = ca.get(index);
-        s.map(|s| ExSeries::new(s))
+        s.map(ExSeries::new)
     } else {
         None
     }
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_rolling_sum(
     data: ExSeries,
     window_size: usize,
@@ -599,7 +736,7 @@ pub fn s_rolling_sum(
     Ok(ExSeries::new(s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_rolling_mean(
     data: ExSeries,
     window_size: usize,
@@ -611,7 +748,7 @@ pub fn s_rolling_mean(
     Ok(ExSeries::new(s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_rolling_max(
     data: ExSeries,
     window_size: usize,
@@ -623,7 +760,7 @@ pub fn s_rolling_max(
     Ok(ExSeries::new(s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_rolling_min(
     data: ExSeries,
     window_size: usize,
@@ -635,56 +772,56 @@ pub fn s_rolling_min(
     Ok(ExSeries::new(s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_year(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let s1 = s.year()?;
     Ok(ExSeries::new(s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_month(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let s1 = s.month()?;
     Ok(ExSeries::new(s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_day(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let s1 = s.day()?;
     Ok(ExSeries::new(s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_ordinal_day(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let s1 = s.ordinal_day()?;
     Ok(ExSeries::new(s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_hour(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let s1 = s.hour()?;
     Ok(ExSeries::new(s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_minute(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let s1 = s.minute()?;
     Ok(ExSeries::new(s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_second(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let s1 = s.second()?;
     Ok(ExSeries::new(s1))
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn s_nanosecond(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
     let s = &data.inner.0;
     let s1 = s.nanosecond()?;
@@ -693,7 +830,7 @@ pub fn s_nanosecond(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
 
 macro_rules! impl_set_with_mask {
     ($name:ident, $native:ty, $cast:ident, $variant:ident) => {
-        #[rustler::nif]
+        #[rustler::nif(schedule = "DirtyCpu")]
         pub fn $name(
             data: ExSeries,
             filter: ExSeries,
@@ -753,7 +890,7 @@ impl_get!(s_get_date64, date64, i64);
 
 macro_rules! impl_cast {
     ($name:ident, $type:ty) => {
-        #[rustler::nif]
+        #[rustler::nif(schedule = "DirtyCpu")]
         pub fn $name(data: ExSeries) -> Result<ExSeries, ExPolarsError> {
             let s = &data.inner.0;
             let s1 = s.cast::<$type>()?;
@@ -780,7 +917,7 @@ impl_cast!(s_cast_str, Utf8Type);
 
 macro_rules! impl_op_i64 {
     ($name:ident, $operand:tt) => {
-        #[rustler::nif]
+        #[rustler::nif(schedule = "DirtyCpu")]
         pub fn $name(data: ExSeries, other: i64) -> Result<ExSeries, ExPolarsError> {
             let s = &data.inner.0;
             Ok(ExSeries::new(s $operand other))
@@ -790,7 +927,7 @@ macro_rules! impl_op_i64 {
 
 macro_rules! impl_op_f64 {
     ($name:ident, $operand:tt) => {
-        #[rustler::nif]
+        #[rustler::nif(schedule = "DirtyCpu")]
         pub fn $name(data: ExSeries, other: f64) -> Result<ExSeries, ExPolarsError> {
             let s = &data.inner.0;
             Ok(ExSeries::new(s $operand other))
@@ -810,7 +947,7 @@ impl_op_f64!(s_div_f64, /);
 
 macro_rules! impl_rhs_i64 {
     ($name:ident, $operand:ident) => {
-        #[rustler::nif]
+        #[rustler::nif(schedule = "DirtyCpu")]
         pub fn $name(data: ExSeries, other: i64) -> Result<ExSeries, ExPolarsError> {
             let s = &data.inner.0;
             Ok(ExSeries::new(other.$operand(s)))
@@ -820,7 +957,7 @@ macro_rules! impl_rhs_i64 {
 
 macro_rules! impl_rhs_f64 {
     ($name:ident, $operand:ident) => {
-        #[rustler::nif]
+        #[rustler::nif(schedule = "DirtyCpu")]
         pub fn $name(data: ExSeries, other: f64) -> Result<ExSeries, ExPolarsError> {
             let s = &data.inner.0;
             Ok(ExSeries::new(other.$operand(s)))
@@ -840,7 +977,7 @@ impl_rhs_f64!(s_div_f64_rhs, div);
 
 macro_rules! impl_agg {
     ($name:ident, $type:ty, $operand:ident) => {
-        #[rustler::nif]
+        #[rustler::nif(schedule = "DirtyCpu")]
         pub fn $name(data: ExSeries) -> Result<Option<$type>, ExPolarsError> {
             let s = &data.inner.0;
             Ok(s.$operand())
@@ -894,7 +1031,7 @@ impl_agg!(s_mean_f64, f64, mean);
 
 macro_rules! impl_cmp_u8 {
     ($name:ident, $operand:ident) => {
-        #[rustler::nif]
+        #[rustler::nif(schedule = "DirtyCpu")]
         pub fn $name(data: ExSeries, rhs: u8) -> Result<ExSeries, ExPolarsError> {
             let s = &data.inner.0;
             Ok(ExSeries::new(s.$operand(rhs).into_series()))
@@ -904,7 +1041,7 @@ macro_rules! impl_cmp_u8 {
 
 macro_rules! impl_cmp_u16 {
     ($name:ident, $operand:ident) => {
-        #[rustler::nif]
+        #[rustler::nif(schedule = "DirtyCpu")]
         pub fn $name(data: ExSeries, rhs: u16) -> Result<ExSeries, ExPolarsError> {
             let s = &data.inner.0;
             Ok(ExSeries::new(s.$operand(rhs).into_series()))
@@ -914,7 +1051,7 @@ macro_rules! impl_cmp_u16 {
 
 macro_rules! impl_cmp_u32 {
     ($name:ident, $operand:ident) => {
-        #[rustler::nif]
+        #[rustler::nif(schedule = "DirtyCpu")]
         pub fn $name(data: ExSeries, rhs: u32) -> Result<ExSeries, ExPolarsError> {
             let s = &data.inner.0;
             Ok(ExSeries::new(s.$operand(rhs).into_series()))
@@ -924,7 +1061,7 @@ macro_rules! impl_cmp_u32 {
 
 macro_rules! impl_cmp_u64 {
     ($name:ident, $operand:ident) => {
-        #[rustler::nif]
+        #[rustler::nif(schedule = "DirtyCpu")]
         pub fn $name(data: ExSeries, rhs: u64) -> Result<ExSeries, ExPolarsError> {
             let s = &data.inner.0;
             Ok(ExSeries::new(s.$operand(rhs).into_series()))
@@ -934,7 +1071,7 @@ macro_rules! impl_cmp_u64 {
 
 macro_rules! impl_cmp_i8 {
     ($name:ident, $operand:ident) => {
-        #[rustler::nif]
+        #[rustler::nif(schedule = "DirtyCpu")]
         pub fn $name(data: ExSeries, rhs: i8) -> Result<ExSeries, ExPolarsError> {
             let s = &data.inner.0;
             Ok(ExSeries::new(s.$operand(rhs).into_series()))
@@ -944,7 +1081,7 @@ macro_rules! impl_cmp_i8 {
 
 macro_rules! impl_cmp_i16 {
     ($name:ident, $operand:ident) => {
-        #[rustler::nif]
+        #[rustler::nif(schedule = "DirtyCpu")]
         pub fn $name(data: ExSeries, rhs: i16) -> Result<ExSeries, ExPolarsError> {
             let s = &data.inner.0;
             Ok(ExSeries::new(s.$operand(rhs).into_series()))
@@ -954,7 +1091,7 @@ macro_rules! impl_cmp_i16 {
 
 macro_rules! impl_cmp_i32 {
     ($name:ident, $operand:ident) => {
-        #[rustler::nif]
+        #[rustler::nif(schedule = "DirtyCpu")]
         pub fn $name(data: ExSeries, rhs: i32) -> Result<ExSeries, ExPolarsError> {
             let s = &data.inner.0;
             Ok(ExSeries::new(s.$operand(rhs).into_series()))
@@ -964,7 +1101,7 @@ macro_rules! impl_cmp_i32 {
 
 macro_rules! impl_cmp_i64 {
     ($name:ident, $operand:ident) => {
-        #[rustler::nif]
+        #[rustler::nif(schedule = "DirtyCpu")]
         pub fn $name(data: ExSeries, rhs: i64) -> Result<ExSeries, ExPolarsError> {
             let s = &data.inner.0;
             Ok(ExSeries::new(s.$operand(rhs).into_series()))
@@ -974,7 +1111,7 @@ macro_rules! impl_cmp_i64 {
 
 macro_rules! impl_cmp_f32 {
     ($name:ident, $operand:ident) => {
-        #[rustler::nif]
+        #[rustler::nif(schedule = "DirtyCpu")]
         pub fn $name(data: ExSeries, rhs: f32) -> Result<ExSeries, ExPolarsError> {
             let s = &data.inner.0;
             Ok(ExSeries::new(s.$operand(rhs).into_series()))
@@ -984,7 +1121,7 @@ macro_rules! impl_cmp_f32 {
 
 macro_rules! impl_cmp_f64 {
     ($name:ident, $operand:ident) => {
-        #[rustler::nif]
+        #[rustler::nif(schedule = "DirtyCpu")]
         pub fn $name(data: ExSeries, rhs: f64) -> Result<ExSeries, ExPolarsError> {
             let s = &data.inner.0;
             Ok(ExSeries::new(s.$operand(rhs).into_series()))
@@ -994,7 +1131,7 @@ macro_rules! impl_cmp_f64 {
 
 macro_rules! impl_cmp_str {
     ($name:ident, $operand:ident) => {
-        #[rustler::nif]
+        #[rustler::nif(schedule = "DirtyCpu")]
         pub fn $name(data: ExSeries, rhs: &str) -> Result<ExSeries, ExPolarsError> {
             let s = &data.inner.0;
             Ok(ExSeries::new(s.$operand(rhs).into_series()))
@@ -1078,3 +1215,44 @@ impl_cmp_str!(s_gt_str, gt);
 impl_cmp_str!(s_lt_str, lt);
 impl_cmp_str!(s_gt_eq_str, gt_eq);
 impl_cmp_str!(s_lt_eq_str, lt_eq);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn out_of_range_dates_are_errors() {
+        assert_eq!(
+            date32_to_naive(-1).unwrap(),
+            NaiveDate::from_ymd(1969, 12, 31)
+        );
+        assert_eq!(
+            date64_to_naive(-1).unwrap(),
+            NaiveDate::from_ymd(1969, 12, 31).and_hms_milli(23, 59, 59, 999)
+        );
+        assert!(date32_to_naive(i32::MAX).is_err());
+        assert!(date32_to_naive(i32::MIN).is_err());
+        assert!(date64_to_naive(i64::MIN).is_err());
+        assert!(date64_to_naive(i64::MAX).is_err());
+    }
+
+    #[test]
+    fn to_json_values_rejects_out_of_range_dates() {
+        let s = Date32Chunked::new_from_opt_slice("d", &[Some(0), None, Some(i32::MAX)]);
+        assert!(to_json_values(&s.into_series(), true).is_err());
+        let s = Date64Chunked::new_from_opt_slice("d", &[Some(i64::MIN)]);
+        assert!(to_json_values(&s.into_series(), true).is_err());
+
+        // without iso dates they're written as the integers they're stored as
+        let s = Date32Chunked::new_from_opt_slice("d", &[Some(0), None, Some(i32::MAX)]);
+        let values = to_json_values(&s.into_series(), false).unwrap();
+        assert_eq!(
+            values,
+            vec![Value::from(0), Value::Null, Value::from(i32::MAX)]
+        );
+
+        let s = Date32Chunked::new_from_opt_slice("d", &[Some(0), None]);
+        let values = to_json_values(&s.into_series(), true).unwrap();
+        assert_eq!(values, vec![Value::from("1970-01-01"), Value::Null]);
+    }
+}
@@ -0,0 +1,252 @@
+// Stable multi-column sorting of data frames.
+//
+// Every sort column is turned into a vector of comparable keys once, then the row indices are
+// sorted with a stable sort comparing the columns in order.
+
+use polars::prelude::*;
+use std::cmp::Ordering;
+use std::result::Result;
+
+use crate::ExPolarsError;
+
+enum Keys<'a> {
+    Int(Vec<Option<i64>>),
+    UInt(Vec<Option<u64>>),
+    Float(Vec<Option<f64>>),
+    Str(Vec<Option<&'a str>>),
+    Bool(Vec<Option<bool>>),
+}
+
+macro_rules! int_keys {
+    ($s:expr, $cast:ident) => {
+        Keys::Int(
+            $s.$cast()?
+                .into_iter()
+                .map(|v| v.map(|v| v as i64))
+                .collect(),
+        )
+    };
+}
+
+impl<'a> Keys<'a> {
+    fn new(s: &'a Series) -> Result<Self, ExPolarsError> {
+        let keys = match s.dtype() {
+            ArrowDataType::Int8 => int_keys!(s, i8),
+            ArrowDataType::Int16 => int_keys!(s, i16),
+            ArrowDataType::Int32 => int_keys!(s, i32),
+            ArrowDataType::Int64 => int_keys!(s, i64),
+            ArrowDataType::UInt8 => int_keys!(s, u8),
+            ArrowDataType::UInt16 => int_keys!(s, u16),
+            ArrowDataType::UInt32 => int_keys!(s, u32),
+            ArrowDataType::UInt64 => Keys::UInt(s.u64()?.into_iter().collect()),
+            ArrowDataType::Date32(_) => int_keys!(s, date32),
+            ArrowDataType::Date64(_) => int_keys!(s, date64),
+            ArrowDataType::Time64(TimeUnit::Nanosecond) => int_keys!(s, time64_nanosecond),
+            ArrowDataType::Duration(TimeUnit::Nanosecond) => int_keys!(s, duration_nanosecond),
+            ArrowDataType::Duration(TimeUnit::Millisecond) => int_keys!(s, duration_millisecond),
+            ArrowDataType::Float32 => {
+                Keys::Float(s.f32()?.into_iter().map(|v| v.map(|v| v as f64)).collect())
+            }
+            ArrowDataType::Float64 => Keys::Float(s.f64()?.into_iter().collect()),
+            ArrowDataType::Utf8 => Keys::Str(s.utf8()?.into_iter().collect()),
+            ArrowDataType::Boolean => Keys::Bool(s.bool()?.into_iter().collect()),
+            dt => {
+                return Err(ExPolarsError::Other(format!(
+                    "cannot sort by column {} of {:?}",
+                    s.name(),
+                    dt
+                )))
+            }
+        };
+        Ok(keys)
+    }
+}
+
+/// NaN sorts after every other float.
+fn cmp_f64(a: &f64, b: &f64) -> Ordering {
+    a.partial_cmp(b)
+        .unwrap_or_else(|| a.is_nan().cmp(&b.is_nan()))
+}
+
+/// A sort column: the sort direction only applies to values, nulls are placed by `nulls_last`.
+pub(crate) struct SortColumn<'a> {
+    keys: Keys<'a>,
+    descending: bool,
+    nulls_last: bool,
+}
+
+impl<'a> SortColumn<'a> {
+    pub fn new(s: &'a Series, descending: bool, nulls_last: bool) -> Result<Self, ExPolarsError> {
+        Ok(Self {
+            keys: Keys::new(s)?,
+            descending,
+            nulls_last,
+        })
+    }
+
+    fn cmp_values<T, F>(&self, a: &Option<T>, b: &Option<T>, f: F) -> Ordering
+    where
+        F: Fn(&T, &T) -> Ordering,
+    {
+        match (a, b) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) if self.nulls_last => Ordering::Greater,
+            (None, Some(_)) => Ordering::Less,
+            (Some(_), None) if self.nulls_last => Ordering::Less,
+            (Some(_), None) => Ordering::Greater,
+            (Some(a), Some(b)) if self.descending => f(a, b).reverse(),
+            (Some(a), Some(b)) => f(a, b),
+        }
+    }
+
+    /// Compare row `a` with row `b`.
+    pub fn cmp(&self, a: usize, b: usize) -> Ordering {
+        match &self.keys {
+            Keys::Int(v) => self.cmp_values(&v[a], &v[b], Ord::cmp),
+            Keys::UInt(v) => self.cmp_values(&v[a], &v[b], Ord::cmp),
+            Keys::Float(v) => self.cmp_values(&v[a], &v[b], cmp_f64),
+            Keys::Str(v) => self.cmp_values(&v[a], &v[b], Ord::cmp),
+            Keys::Bool(v) => self.cmp_values(&v[a], &v[b], Ord::cmp),
+        }
+    }
+}
+
+pub(crate) fn cmp_rows(columns: &[SortColumn], a: usize, b: usize) -> Ordering {
+    columns
+        .iter()
+        .map(|c| c.cmp(a, b))
+        .find(|o| *o != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Build the sort columns. `descending` and `nulls_last` hold one flag per column in `by`.
+pub(crate) fn sort_columns<'a>(
+    df: &'a DataFrame,
+    by: &[&str],
+    descending: &[bool],
+    nulls_last: &[bool],
+) -> Result<Vec<SortColumn<'a>>, ExPolarsError> {
+    if descending.len() != by.len() || nulls_last.len() != by.len() {
+        return Err(ExPolarsError::Other(format!(
+            "expected {} descending and nulls_last flags, got {} and {}",
+            by.len(),
+            descending.len(),
+            nulls_last.len()
+        )));
+    }
+    by.iter()
+        .zip(descending.iter().zip(nulls_last.iter()))
+        .map(|(name, (desc, nl))| SortColumn::new(df.column(name)?, *desc, *nl))
+        .collect()
+}
+
+/// Indices of the rows in sorted order. The sort is stable: rows with equal keys keep their
+/// original order.
+pub(crate) fn arg_sort_by(
+    df: &DataFrame,
+    by: &[&str],
+    descending: &[bool],
+    nulls_last: &[bool],
+) -> Result<Vec<usize>, ExPolarsError> {
+    let columns = sort_columns(df, by, descending, nulls_last)?;
+    let mut idx: Vec<usize> = (0..df.height()).collect();
+    idx.sort_by(|a, b| cmp_rows(&columns, *a, *b));
+    Ok(idx)
+}
+
+pub(crate) fn sort_by(
+    df: &DataFrame,
+    by: &[&str],
+    descending: &[bool],
+    nulls_last: &[bool],
+) -> Result<DataFrame, ExPolarsError> {
+    let idx = arg_sort_by(df, by, descending, nulls_last)?;
+    Ok(df.take(&idx))
+}
+
+/// Indices of the values of `s` in sorted order, stable like `arg_sort_by`.
+pub(crate) fn arg_sort_series(
+    s: &Series,
+    descending: bool,
+    nulls_last: bool,
+) -> Result<Vec<usize>, ExPolarsError> {
+    let column = SortColumn::new(s, descending, nulls_last)?;
+    let mut idx: Vec<usize> = (0..s.len()).collect();
+    idx.sort_by(|a, b| column.cmp(*a, *b));
+    Ok(idx)
+}
+
+/// Indices of the first `k` rows in sorted order, without sorting all rows: the `k` rows are
+/// selected first and only those are sorted. Ties are broken by row index, so the result is the
+/// same as the first `k` rows of `arg_sort_by`.
+pub(crate) fn arg_top_k(columns: &[SortColumn], height: usize, k: usize) -> Vec<usize> {
+    if k == 0 {
+        return vec![];
+    }
+    let cmp = |a: &usize, b: &usize| cmp_rows(columns, *a, *b).then(a.cmp(b));
+    let mut idx: Vec<usize> = (0..height).collect();
+    if k < height {
+        idx.select_nth_unstable_by(k - 1, cmp);
+        idx.truncate(k);
+    }
+    idx.sort_by(cmp);
+    idx
+}
+
+/// The `k` largest rows of `df` by `by`, or the `k` smallest if `bottom` is set, in sorted
+/// order. Nulls are never selected ahead of values.
+pub(crate) fn top_k(
+    df: &DataFrame,
+    k: usize,
+    by: &[&str],
+    bottom: bool,
+) -> Result<DataFrame, ExPolarsError> {
+    let columns = sort_columns(df, by, &vec![!bottom; by.len()], &vec![true; by.len()])?;
+    let idx = arg_top_k(&columns, df.height(), k);
+    Ok(df.take(&idx))
+}
+
+/// Same as `top_k` for a single series.
+pub(crate) fn top_k_series(s: &Series, k: usize, bottom: bool) -> Result<Series, ExPolarsError> {
+    let column = SortColumn::new(s, !bottom, true)?;
+    let idx = arg_top_k(&[column], s.len(), k);
+    Ok(s.take(&idx))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sort_by_is_stable_with_nulls_last() {
+        let region = Series::new(
+            "region",
+            &[Some("b"), Some("a"), None, Some("a"), Some("b")],
+        );
+        let revenue = Series::new("revenue", &[Some(1), Some(2), Some(3), None, Some(1)]);
+        let id = Series::new("id", &[0, 1, 2, 3, 4]);
+        let df = DataFrame::new(vec![region, revenue, id]).unwrap();
+
+        let idx = arg_sort_by(&df, &["region", "revenue"], &[false, true], &[true, true]).unwrap();
+        assert_eq!(idx, vec![1, 3, 0, 4, 2]);
+
+        let idx = arg_sort_by(&df, &["region"], &[true], &[false]).unwrap();
+        assert_eq!(idx, vec![2, 0, 4, 1, 3]);
+    }
+
+    #[test]
+    fn top_k_matches_sort_prefix() {
+        let score = Series::new(
+            "score",
+            &[Some(3), None, Some(5), Some(3), Some(1), Some(5)],
+        );
+        let df = DataFrame::new(vec![score]).unwrap();
+        let columns = sort_columns(&df, &["score"], &[true], &[true]).unwrap();
+        assert_eq!(arg_top_k(&columns, df.height(), 3), vec![2, 5, 0]);
+        assert_eq!(arg_top_k(&columns, df.height(), 10), vec![2, 5, 0, 3, 4, 1]);
+
+        let columns = sort_columns(&df, &["score"], &[false], &[true]).unwrap();
+        assert_eq!(arg_top_k(&columns, df.height(), 2), vec![4, 0]);
+        assert_eq!(arg_top_k(&columns, df.height(), 0), Vec::<usize>::new());
+    }
+}
@@ -0,0 +1,509 @@
+// Convert between series values and elixir terms.
+//
+// Nulls become `nil`, dates and times become `Date`, `NaiveDateTime` and `Time` structs and
+// list series become nested lists. Non-finite floats become `:nan`, `:infinity` and
+// `:neg_infinity` since the BEAM has no representation for them.
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use polars::prelude::*;
+use rustler::types::map::MapIterator;
+use rustler::{Atom, Decoder, Encoder, Env, Term};
+use std::collections::HashMap;
+use std::result::Result;
+
+use crate::dataframe::to_binary;
+use crate::series::{date32_to_naive, date64_to_naive, time64ns_to_naive};
+use crate::{DataType, ExPolarsError};
+
+rustler::atoms! {
+    nil,
+    nan,
+    infinity,
+    neg_infinity,
+    struct_ = "__struct__",
+    calendar,
+    year,
+    month,
+    day,
+    hour,
+    minute,
+    second,
+    microsecond,
+    calendar_iso = "Elixir.Calendar.ISO",
+    date = "Elixir.Date",
+    naive_datetime = "Elixir.NaiveDateTime",
+    time = "Elixir.Time",
+}
+
+pub(crate) fn make_map<'a>(
+    env: Env<'a>,
+    keys: &[Term<'a>],
+    values: &[Term<'a>],
+) -> Result<Term<'a>, ExPolarsError> {
+    let mut map = Term::map_new(env);
+    for (k, v) in keys.iter().zip(values.iter()) {
+        map = map
+            .map_put(*k, *v)
+            .map_err(|_| ExPolarsError::Internal("Failed to build map".into()))?;
+    }
+    Ok(map)
+}
+
+fn make_struct<'a>(
+    env: Env<'a>,
+    module: Atom,
+    fields: &[(Atom, Term<'a>)],
+) -> Result<Term<'a>, ExPolarsError> {
+    let mut keys = vec![struct_().encode(env), calendar().encode(env)];
+    let mut values = vec![module.encode(env), calendar_iso().encode(env)];
+    for (k, v) in fields {
+        keys.push(k.encode(env));
+        values.push(*v);
+    }
+    make_map(env, &keys, &values)
+}
+
+fn microseconds(env: Env, nanos: u32) -> Term {
+    // leap seconds are represented by chrono as nanos >= 1_000_000_000
+    ((nanos / 1_000).min(999_999), 6).encode(env)
+}
+
+fn date_term(env: Env, d: NaiveDate) -> Result<Term, ExPolarsError> {
+    make_struct(
+        env,
+        date(),
+        &[
+            (year(), d.year().encode(env)),
+            (month(), d.month().encode(env)),
+            (day(), d.day().encode(env)),
+        ],
+    )
+}
+
+fn naive_datetime_term(env: Env, dt: NaiveDateTime) -> Result<Term, ExPolarsError> {
+    make_struct(
+        env,
+        naive_datetime(),
+        &[
+            (year(), dt.year().encode(env)),
+            (month(), dt.month().encode(env)),
+            (day(), dt.day().encode(env)),
+            (hour(), dt.hour().encode(env)),
+            (minute(), dt.minute().encode(env)),
+            (second(), dt.second().encode(env)),
+            (microsecond(), microseconds(env, dt.nanosecond())),
+        ],
+    )
+}
+
+fn time_term(env: Env, t: NaiveTime) -> Result<Term, ExPolarsError> {
+    make_struct(
+        env,
+        time(),
+        &[
+            (hour(), t.hour().encode(env)),
+            (minute(), t.minute().encode(env)),
+            (second(), t.second().encode(env)),
+            (microsecond(), microseconds(env, t.nanosecond())),
+        ],
+    )
+}
+
+fn float_term(env: Env, v: f64) -> Term {
+    if v.is_nan() {
+        nan().encode(env)
+    } else if v.is_infinite() && v > 0.0 {
+        infinity().encode(env)
+    } else if v.is_infinite() {
+        neg_infinity().encode(env)
+    } else {
+        v.encode(env)
+    }
+}
+
+fn encode_values<T, I>(env: Env, iter: I) -> Vec<Term>
+where
+    T: Encoder,
+    I: Iterator<Item = Option<T>>,
+{
+    iter.map(|v| v.encode(env)).collect()
+}
+
+fn encode_with<'a, T, I, F>(env: Env<'a>, iter: I, f: F) -> Result<Vec<Term<'a>>, ExPolarsError>
+where
+    I: Iterator<Item = Option<T>>,
+    F: Fn(T) -> Result<Term<'a>, ExPolarsError>,
+{
+    iter.map(|v| match v {
+        Some(v) => f(v),
+        None => Ok(nil().encode(env)),
+    })
+    .collect()
+}
+
+/// Encode every value of the series as an elixir term.
+pub(crate) fn to_terms<'a>(env: Env<'a>, s: &Series) -> Result<Vec<Term<'a>>, ExPolarsError> {
+    let terms = match s.dtype() {
+        ArrowDataType::Boolean => encode_values(env, s.bool()?.into_iter()),
+        ArrowDataType::Utf8 => encode_values(env, s.utf8()?.into_iter()),
+        ArrowDataType::UInt8 => encode_values(env, s.u8()?.into_iter()),
+        ArrowDataType::UInt16 => encode_values(env, s.u16()?.into_iter()),
+        ArrowDataType::UInt32 => encode_values(env, s.u32()?.into_iter()),
+        ArrowDataType::UInt64 => encode_values(env, s.u64()?.into_iter()),
+        ArrowDataType::Int8 => encode_values(env, s.i8()?.into_iter()),
+        ArrowDataType::Int16 => encode_values(env, s.i16()?.into_iter()),
+        ArrowDataType::Int32 => encode_values(env, s.i32()?.into_iter()),
+        ArrowDataType::Int64 => encode_values(env, s.i64()?.into_iter()),
+        ArrowDataType::Float32 => {
+            encode_with(env, s.f32()?.into_iter(), |v| Ok(float_term(env, v as f64)))?
+        }
+        ArrowDataType::Float64 => {
+            encode_with(env, s.f64()?.into_iter(), |v| Ok(float_term(env, v)))?
+        }
+        ArrowDataType::Date32(DateUnit::Day) => encode_with(env, s.date32()?.into_iter(), |v| {
+            date_term(env, date32_to_naive(v)?)
+        })?,
+        ArrowDataType::Date64(DateUnit::Millisecond) => {
+            encode_with(env, s.date64()?.into_iter(), |v| {
+                naive_datetime_term(env, date64_to_naive(v)?)
+            })?
+        }
+        ArrowDataType::Time64(TimeUnit::Nanosecond) => {
+            encode_with(env, s.time64_nanosecond()?.into_iter(), |v| {
+                time_term(env, time64ns_to_naive(v))
+            })?
+        }
+        ArrowDataType::Duration(TimeUnit::Nanosecond) => {
+            encode_values(env, s.duration_nanosecond()?.into_iter())
+        }
+        ArrowDataType::Duration(TimeUnit::Millisecond) => {
+            encode_values(env, s.duration_millisecond()?.into_iter())
+        }
+        ArrowDataType::List(_) => encode_with(env, s.list()?.into_iter(), |inner| {
+            Ok(to_terms(env, &inner)?.encode(env))
+        })?,
+        ArrowDataType::Binary => {
+            let mut v = Vec::with_capacity(s.len());
+            for i in 0..s.len() {
+                match s.get_as_any(i).downcast_ref::<Vec<u8>>() {
+                    Some(bytes) => v.push(to_binary(env, bytes)?.encode(env)),
+                    None => {
+                        return Err(ExPolarsError::Other(format!(
+                            "object series {} cannot be converted to terms",
+                            s.name()
+                        )))
+                    }
+                }
+            }
+            v
+        }
+        dt => {
+            return Err(ExPolarsError::Other(format!(
+                "term conversion not implemented for {:?}",
+                dt
+            )))
+        }
+    };
+    Ok(terms)
+}
+
+fn is_nil(term: Term) -> bool {
+    term.decode::<Atom>().map_or(false, |a| a == nil())
+}
+
+fn field<'a, T: Decoder<'a>>(term: Term<'a>, key: Atom) -> Option<T> {
+    let env = term.get_env();
+    term.map_get(key.encode(env)).ok()?.decode().ok()
+}
+
+fn struct_name(term: Term) -> Option<Atom> {
+    if term.is_map() {
+        field(term, struct_())
+    } else {
+        None
+    }
+}
+
+fn naive_date(term: Term) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(
+        field(term, year())?,
+        field(term, month())?,
+        field(term, day())?,
+    )
+}
+
+fn naive_time(term: Term) -> Option<NaiveTime> {
+    let (us, _precision): (u32, u32) = field(term, microsecond())?;
+    NaiveTime::from_hms_micro_opt(
+        field(term, hour())?,
+        field(term, minute())?,
+        field(term, second())?,
+        us,
+    )
+}
+
+/// Days since epoch from a `Date` or an integer.
+fn decode_date32(term: Term) -> Option<i32> {
+    match struct_name(term) {
+        Some(s) if s == date() => {
+            let epoch = NaiveDate::from_ymd(1970, 1, 1);
+            Some((naive_date(term)? - epoch).num_days() as i32)
+        }
+        Some(_) => None,
+        None => term.decode().ok(),
+    }
+}
+
+/// Milliseconds since epoch from a `NaiveDateTime`, a `Date` or an integer.
+fn decode_date64(term: Term) -> Option<i64> {
+    match struct_name(term) {
+        Some(s) if s == naive_datetime() => {
+            Some(NaiveDateTime::new(naive_date(term)?, naive_time(term)?).timestamp_millis())
+        }
+        Some(s) if s == date() => Some(naive_date(term)?.and_hms(0, 0, 0).timestamp_millis()),
+        Some(_) => None,
+        None => term.decode().ok(),
+    }
+}
+
+/// Nanoseconds since midnight from a `Time` or an integer.
+fn decode_time64(term: Term) -> Option<i64> {
+    match struct_name(term) {
+        Some(s) if s == time() => {
+            let t = naive_time(term)?;
+            Some(t.num_seconds_from_midnight() as i64 * 1_000_000_000 + t.nanosecond() as i64)
+        }
+        Some(_) => None,
+        None => term.decode().ok(),
+    }
+}
+
+fn decode<'a, T: Decoder<'a>>(term: Term<'a>) -> Option<T> {
+    term.decode().ok()
+}
+
+fn decode_f64(term: Term) -> Option<f64> {
+    term.decode::<f64>()
+        .ok()
+        .or_else(|| term.decode::<i64>().ok().map(|v| v as f64))
+}
+
+fn decode_f32(term: Term) -> Option<f32> {
+    decode_f64(term).map(|v| v as f32)
+}
+
+fn decode_column<'a, T, F>(
+    name: &str,
+    values: &[Option<Term<'a>>],
+    f: F,
+) -> Result<Vec<Option<T>>, ExPolarsError>
+where
+    F: Fn(Term<'a>) -> Option<T>,
+{
+    values
+        .iter()
+        .map(|v| match v.filter(|t| !is_nil(*t)) {
+            None => Ok(None),
+            Some(t) => f(t).map(Some).ok_or_else(|| {
+                ExPolarsError::Other(format!("column {}: cannot convert {:?}", name, t))
+            }),
+        })
+        .collect()
+}
+
+macro_rules! column {
+    ($ca:ty, $name:expr, $values:expr, $f:expr) => {
+        <$ca>::new_from_opt_slice($name, &decode_column($name, $values, $f)?).into_series()
+    };
+}
+
+/// Build a series of the given dtype. Missing values and `nil` become nulls.
+pub(crate) fn terms_to_series(
+    name: &str,
+    values: &[Option<Term>],
+    dtype: DataType,
+) -> Result<Series, ExPolarsError> {
+    use DataType::*;
+    let s = match dtype {
+        Int8 => column!(Int8Chunked, name, values, decode::<i8>),
+        Int16 => column!(Int16Chunked, name, values, decode::<i16>),
+        Int32 => column!(Int32Chunked, name, values, decode::<i32>),
+        Int64 => column!(Int64Chunked, name, values, decode::<i64>),
+        UInt8 => column!(UInt8Chunked, name, values, decode::<u8>),
+        UInt16 => column!(UInt16Chunked, name, values, decode::<u16>),
+        UInt32 => column!(UInt32Chunked, name, values, decode::<u32>),
+        UInt64 => column!(UInt64Chunked, name, values, decode::<u64>),
+        Float32 => column!(Float32Chunked, name, values, decode_f32),
+        Float64 => column!(Float64Chunked, name, values, decode_f64),
+        Bool => column!(BooleanChunked, name, values, decode::<bool>),
+        Utf8 => column!(Utf8Chunked, name, values, decode::<&str>),
+        Date32 => column!(Date32Chunked, name, values, decode_date32),
+        Date64 => column!(Date64Chunked, name, values, decode_date64),
+        Time64Nanosecond => column!(Time64NanosecondChunked, name, values, decode_time64),
+        DurationNanosecond => column!(DurationNanosecondChunked, name, values, decode::<i64>),
+        DurationMillisecond => column!(DurationMillisecondChunked, name, values, decode::<i64>),
+        dt => {
+            return Err(ExPolarsError::Other(format!(
+                "column {}: cannot build {:?} series from terms",
+                name, dt
+            )))
+        }
+    };
+    Ok(s)
+}
+
+fn value_dtype(term: Term) -> Option<DataType> {
+    if term.is_binary() {
+        Some(DataType::Utf8)
+    } else if term.decode::<bool>().is_ok() {
+        Some(DataType::Bool)
+    } else if term.decode::<i64>().is_ok() {
+        Some(DataType::Int64)
+    } else if term.decode::<f64>().is_ok() {
+        Some(DataType::Float64)
+    } else {
+        match struct_name(term) {
+            Some(s) if s == date() => Some(DataType::Date32),
+            Some(s) if s == naive_datetime() => Some(DataType::Date64),
+            Some(s) if s == time() => Some(DataType::Time64Nanosecond),
+            _ => None,
+        }
+    }
+}
+
+/// Infer the dtype of a column: integers widen to floats and dates to datetimes.
+/// A column with only nulls is a string column.
+pub(crate) fn infer_dtype(name: &str, values: &[Option<Term>]) -> Result<DataType, ExPolarsError> {
+    use DataType::*;
+    let mut dtype = None;
+    for t in values.iter().flatten().filter(|t| !is_nil(**t)) {
+        let current = value_dtype(*t).ok_or_else(|| {
+            ExPolarsError::Other(format!("column {}: cannot infer dtype of {:?}", name, t))
+        })?;
+        dtype = Some(match (dtype, current) {
+            (None, b) => b,
+            (Some(Int64), Float64) | (Some(Float64), Int64) => Float64,
+            (Some(Date32), Date64) | (Some(Date64), Date32) => Date64,
+            (Some(a), b) if a == b => a,
+            (Some(a), b) => {
+                return Err(ExPolarsError::Other(format!(
+                    "column {}: mixed values of {:?} and {:?}",
+                    name, a, b
+                )))
+            }
+        });
+    }
+    Ok(dtype.unwrap_or(Utf8))
+}
+
+fn key_to_string(term: Term) -> Result<String, ExPolarsError> {
+    if term.is_atom() {
+        Ok(term
+            .atom_to_string()
+            .map_err(|_| ExPolarsError::Other(format!("cannot use {:?} as column name", term)))?)
+    } else {
+        term.decode::<String>()
+            .map_err(|_| ExPolarsError::Other(format!("cannot use {:?} as column name", term)))
+    }
+}
+
+pub(crate) fn decode_schema(
+    schema: Vec<(String, u8)>,
+) -> Result<Vec<(String, DataType)>, ExPolarsError> {
+    schema
+        .into_iter()
+        .map(|(name, dtype)| match DataType::from_u8(dtype) {
+            Some(dt) => Ok((name, dt)),
+            None => Err(ExPolarsError::Other(format!(
+                "column {}: unknown dtype {}",
+                name, dtype
+            ))),
+        })
+        .collect()
+}
+
+fn build_df(
+    columns: Vec<(String, Vec<Option<Term>>)>,
+    height: usize,
+    schema: Option<Vec<(String, u8)>>,
+) -> Result<DataFrame, ExPolarsError> {
+    let columns = match schema {
+        None => columns
+            .iter()
+            .map(|(name, values)| terms_to_series(name, values, infer_dtype(name, values)?))
+            .collect::<Result<Vec<_>, _>>()?,
+        Some(schema) => {
+            let mut by_name: HashMap<String, Vec<Option<Term>>> = columns.into_iter().collect();
+            decode_schema(schema)?
+                .into_iter()
+                .map(|(name, dtype)| {
+                    let values = by_name.remove(&name).unwrap_or_else(|| vec![None; height]);
+                    terms_to_series(&name, &values, dtype)
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        }
+    };
+    Ok(DataFrame::new(columns)?)
+}
+
+/// Build a frame from a list of maps with atom or string keys. Columns are ordered by first
+/// appearance in map iteration (term) order, keys missing from a row are null, and the
+/// `__struct__` and Ecto `__meta__` fields are skipped. With a schema only its columns are kept.
+pub(crate) fn rows_to_df(
+    rows: Vec<Term>,
+    schema: Option<Vec<(String, u8)>>,
+) -> Result<DataFrame, ExPolarsError> {
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut columns: Vec<(String, Vec<Option<Term>>)> = Vec::new();
+    for (i, row) in rows.iter().enumerate() {
+        let entries = MapIterator::new(*row)
+            .ok_or_else(|| ExPolarsError::Other(format!("row {} is not a map", i)))?;
+        for (k, v) in entries {
+            let name = key_to_string(k)?;
+            if name == "__struct__" || name == "__meta__" {
+                continue;
+            }
+            let idx = match index.get(&name) {
+                Some(idx) => *idx,
+                None => {
+                    index.insert(name.clone(), columns.len());
+                    columns.push((name.clone(), vec![None; i]));
+                    columns.len() - 1
+                }
+            };
+            let values = &mut columns[idx].1;
+            if values.len() > i {
+                return Err(ExPolarsError::Other(format!(
+                    "row {}: duplicated column {}",
+                    i, name
+                )));
+            }
+            values.push(Some(v));
+        }
+        for (_, values) in columns.iter_mut() {
+            if values.len() == i {
+                values.push(None);
+            }
+        }
+    }
+    build_df(columns, rows.len(), schema)
+}
+
+/// Build a frame from a map of column name to a list of values.
+pub(crate) fn columns_to_df(
+    data: Term,
+    schema: Option<Vec<(String, u8)>>,
+) -> Result<DataFrame, ExPolarsError> {
+    let entries =
+        MapIterator::new(data).ok_or_else(|| ExPolarsError::Other("expected a map".into()))?;
+    let mut columns = Vec::new();
+    for (k, v) in entries {
+        let name = key_to_string(k)?;
+        let values: Vec<Term> = v.decode().map_err(|_| {
+            ExPolarsError::Other(format!("column {}: expected a list of values", name))
+        })?;
+        columns.push((name, values.into_iter().map(Some).collect::<Vec<_>>()));
+    }
+    let height = columns.first().map_or(0, |(_, values)| values.len());
+    build_df(columns, height, schema)
+}
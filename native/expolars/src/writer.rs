@@ -0,0 +1,333 @@
+// Writers that append data frame batches to one CSV or parquet file.
+//
+// The parquet writer can't be sent between threads (its schema is reference counted with `Rc`),
+// so every writer owns its file on a thread of its own and the NIFs talk to it over a channel.
+
+use parquet::basic::Compression;
+use polars::prelude::*;
+use std::fs::File;
+use std::result::Result;
+use std::sync::mpsc::{channel, Sender};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::compression::{self, Output};
+use crate::parquet_file::FrameWriter;
+use crate::ExPolarsError;
+
+pub(crate) enum SinkOptions {
+    Csv {
+        has_header: bool,
+        delimiter: u8,
+        batch_size: usize,
+        compression: compression::Compression,
+    },
+    Parquet {
+        compression: Compression,
+        statistics: bool,
+    },
+}
+
+enum Sink {
+    Csv(Output),
+    Parquet(Option<File>, Option<FrameWriter<File>>),
+}
+
+struct BatchSink {
+    sink: Sink,
+    options: SinkOptions,
+    schema: Option<Schema>,
+}
+
+fn describe(schema: &Schema) -> String {
+    let fields: Vec<String> = schema
+        .fields()
+        .iter()
+        .map(|f| format!("{}: {:?}", f.name(), f.data_type()))
+        .collect();
+    format!("[{}]", fields.join(", "))
+}
+
+impl BatchSink {
+    fn new(file: File, options: SinkOptions) -> Result<Self, ExPolarsError> {
+        let sink = match options {
+            SinkOptions::Csv { compression, .. } => Sink::Csv(Output::new(file, compression)?),
+            SinkOptions::Parquet { .. } => Sink::Parquet(Some(file), None),
+        };
+        Ok(Self {
+            sink,
+            options,
+            schema: None,
+        })
+    }
+
+    fn write(&mut self, mut df: DataFrame) -> Result<(), ExPolarsError> {
+        let schema = df.schema();
+        let first = match &self.schema {
+            Some(expected) if *expected != schema => {
+                return Err(ExPolarsError::SchemaMismatch(format!(
+                    "batch schema {} differs from {}",
+                    describe(&schema),
+                    describe(expected)
+                )))
+            }
+            Some(_) => false,
+            None => true,
+        };
+
+        match (&mut self.sink, &self.options) {
+            (
+                Sink::Csv(file),
+                SinkOptions::Csv {
+                    has_header,
+                    delimiter,
+                    batch_size,
+                    ..
+                },
+            ) => {
+                CsvWriter::new(file)
+                    .has_headers(*has_header && first)
+                    .with_delimiter(*delimiter)
+                    .with_batch_size(*batch_size)
+                    .finish(&mut df)?;
+            }
+            (
+                Sink::Parquet(file, writer),
+                SinkOptions::Parquet {
+                    compression,
+                    statistics,
+                },
+            ) => {
+                if writer.is_none() {
+                    let file = file.take().ok_or_else(|| {
+                        ExPolarsError::Internal("parquet writer lost its file".into())
+                    })?;
+                    *writer = Some(FrameWriter::try_new(
+                        file,
+                        &schema,
+                        *compression,
+                        *statistics,
+                    )?);
+                }
+                // every batch becomes one row group
+                if let Some(writer) = writer {
+                    writer.write(&df)?;
+                }
+            }
+            _ => return Err(ExPolarsError::Internal("invalid writer state".into())),
+        }
+        self.schema = Some(schema);
+        Ok(())
+    }
+
+    fn close(self) -> Result<(), ExPolarsError> {
+        match self.sink {
+            Sink::Csv(output) => output.finish()?,
+            Sink::Parquet(_, Some(writer)) => writer.close()?,
+            Sink::Parquet(Some(file), None) => {
+                // nothing was written, leave a valid file without columns
+                let schema = Schema::new(vec![]);
+                FrameWriter::try_new(file, &schema, Compression::UNCOMPRESSED, false)?.close()?
+            }
+            Sink::Parquet(None, None) => {}
+        }
+        Ok(())
+    }
+}
+
+enum Command {
+    Write(DataFrame, Sender<Result<(), ExPolarsError>>),
+    Close(Sender<Result<(), ExPolarsError>>),
+}
+
+pub struct BatchWriter {
+    // `None` once the writer is closed
+    commands: Mutex<Option<Sender<Command>>>,
+}
+
+impl BatchWriter {
+    pub(crate) fn new(file: File, options: SinkOptions) -> Result<Self, ExPolarsError> {
+        let (tx, rx) = channel();
+        let (started, start) = channel();
+        thread::spawn(move || {
+            let mut sink = match BatchSink::new(file, options) {
+                Ok(sink) => {
+                    let _ = started.send(Ok(()));
+                    sink
+                }
+                Err(e) => {
+                    let _ = started.send(Err(e));
+                    return;
+                }
+            };
+            for command in rx {
+                match command {
+                    Command::Write(df, reply) => {
+                        let _ = reply.send(sink.write(df));
+                    }
+                    Command::Close(reply) => {
+                        let _ = reply.send(sink.close());
+                        return;
+                    }
+                }
+            }
+            // the writer was garbage collected without being closed
+            let _ = sink.close();
+        });
+        start
+            .recv()
+            .map_err(|_| ExPolarsError::Internal("writer thread stopped".into()))??;
+        Ok(Self {
+            commands: Mutex::new(Some(tx)),
+        })
+    }
+
+    fn send<F>(&self, close: bool, command: F) -> Result<(), ExPolarsError>
+    where
+        F: FnOnce(Sender<Result<(), ExPolarsError>>) -> Command,
+    {
+        let mut commands = self
+            .commands
+            .lock()
+            .map_err(|_| ExPolarsError::Internal("Failed to take lock for writer".into()))?;
+        let (reply, result) = channel();
+        match commands.as_ref() {
+            Some(tx) => tx
+                .send(command(reply))
+                .map_err(|_| ExPolarsError::Internal("writer thread stopped".into()))?,
+            None => return Err(ExPolarsError::Other("writer is closed".into())),
+        }
+        if close {
+            *commands = None;
+        }
+        result
+            .recv()
+            .map_err(|_| ExPolarsError::Internal("writer thread stopped".into()))?
+    }
+
+    /// Append `df` to the file. Its schema must be the same as that of the first batch.
+    pub fn write(&self, df: DataFrame) -> Result<(), ExPolarsError> {
+        self.send(false, |reply| Command::Write(df, reply))
+    }
+
+    /// Flush and finalize the file. Writing after closing is an error.
+    pub fn close(&self) -> Result<(), ExPolarsError> {
+        self.send(true, Command::Close)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parquet_file::read_parquet;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    fn temp_file(name: &str) -> (String, File) {
+        let name = format!("expolars-writer-{}-{}", std::process::id(), name);
+        let path = std::env::temp_dir().join(name);
+        let path = path.to_str().unwrap().to_string();
+        let file = File::create(&path).unwrap();
+        (path, file)
+    }
+
+    fn batch(ids: &[i64]) -> DataFrame {
+        let names: Vec<String> = ids.iter().map(|i| format!("n{}", i)).collect();
+        DataFrame::new(vec![
+            Series::new("id", ids),
+            Series::new("name", names.as_slice()),
+        ])
+        .unwrap()
+    }
+
+    fn csv_options() -> SinkOptions {
+        SinkOptions::Csv {
+            has_header: true,
+            delimiter: b',',
+            batch_size: 64,
+            compression: compression::Compression::None,
+        }
+    }
+
+    fn parquet_options() -> SinkOptions {
+        SinkOptions::Parquet {
+            compression: Compression::SNAPPY,
+            statistics: true,
+        }
+    }
+
+    #[test]
+    fn csv_batches_are_appended_with_one_header() {
+        let (path, file) = temp_file("batches.csv");
+        let writer = BatchWriter::new(file, csv_options()).unwrap();
+        writer.write(batch(&[1, 2])).unwrap();
+        writer.write(batch(&[3])).unwrap();
+        writer.close().unwrap();
+
+        let data = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(data, "id,name\n1,n1\n2,n2\n3,n3\n");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn parquet_batches_become_row_groups() {
+        let (path, file) = temp_file("batches.parquet");
+        let writer = BatchWriter::new(file, parquet_options()).unwrap();
+        writer.write(batch(&[1, 2])).unwrap();
+        writer.write(batch(&[3])).unwrap();
+        writer.close().unwrap();
+
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        assert_eq!(reader.metadata().num_row_groups(), 2);
+        let df = read_parquet(File::open(&path).unwrap()).unwrap();
+        assert!(df.frame_equal(&batch(&[1, 2, 3])));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn a_batch_with_another_schema_is_rejected() {
+        for (name, options) in vec![
+            ("schema.csv", csv_options()),
+            ("schema.parquet", parquet_options()),
+        ] {
+            let (path, file) = temp_file(name);
+            let writer = BatchWriter::new(file, options).unwrap();
+            writer.write(batch(&[1])).unwrap();
+
+            let mut renamed = batch(&[2]);
+            renamed.rename("name", "other").unwrap();
+            let retyped = DataFrame::new(vec![
+                Series::new("id", &[2.5f64]),
+                Series::new("name", &["n2"]),
+            ])
+            .unwrap();
+            for df in vec![renamed, retyped] {
+                match writer.write(df) {
+                    Err(ExPolarsError::SchemaMismatch(_)) => {}
+                    other => panic!("expected a schema mismatch, got {:?}", other.err()),
+                }
+            }
+
+            // the file is still usable after a rejected batch
+            writer.write(batch(&[3])).unwrap();
+            writer.close().unwrap();
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn writing_after_close_is_an_error() {
+        for (name, options) in vec![
+            ("closed.csv", csv_options()),
+            ("closed.parquet", parquet_options()),
+        ] {
+            let (path, file) = temp_file(name);
+            let writer = BatchWriter::new(file, options).unwrap();
+            writer.write(batch(&[1])).unwrap();
+            writer.close().unwrap();
+
+            assert!(writer.write(batch(&[2])).is_err());
+            assert!(writer.close().is_err());
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+}
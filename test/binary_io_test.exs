@@ -0,0 +1,59 @@
+defmodule ExPolars.BinaryIoTest do
+  use ExUnit.Case, async: true
+
+  alias ExPolars.DataFrame, as: DF
+  alias ExPolars.Series, as: S
+
+  setup do
+    data = %{"id" => [1, 2, 3], "name" => ["a", nil, "c"], "score" => [0.5, nil, 2.5]}
+    {:ok, df} = DF.from_columns(data, id: "i64", name: "str", score: "f64")
+
+    {:ok, df: df}
+  end
+
+  test "read_csv_binary parses the output of to_csv", %{df: df} do
+    {:ok, csv} = DF.to_csv(df)
+    assert {:ok, read} = DF.read_csv_binary(csv)
+    assert {:ok, [1, 2, 3]} = read |> DF.column("id") |> S.to_list()
+
+    assert {:ok, read} =
+             DF.read_csv_binary(csv, 100, 64, true, false, nil, 0, nil, ",", true, ["score"])
+
+    assert {:ok, ["score"]} = DF.columns(read)
+  end
+
+  test "read_parquet_binary reads the output of to_parquet", %{df: df} do
+    for compression <- ["uncompressed", "snappy", "gzip", "lz4", "zstd"] do
+      {:ok, parquet} = DF.to_parquet(df, compression, 2)
+      assert {:ok, read} = DF.read_parquet_binary(parquet)
+      assert {:ok, true} = DF.equal(df, read, true)
+    end
+  end
+
+  test "read_ipc_binary reads the output of to_ipc, with a projection", %{df: df} do
+    {:ok, ipc} = DF.to_ipc(df)
+    assert {:ok, read} = DF.read_ipc_binary(ipc)
+    assert {:ok, true} = DF.equal(df, read, true)
+
+    assert {:ok, read} = DF.read_ipc_binary(ipc, [2, 0])
+    assert {:ok, ["score", "id"]} = DF.columns(read)
+  end
+
+  test "from_binary reads the output of to_binary", %{df: df} do
+    {:ok, data} = DF.to_binary(df)
+    assert {:ok, read} = DF.from_binary(data)
+    assert {:ok, true} = DF.equal(df, read, true)
+  end
+
+  test "read_json_binary reads the output of to_json", %{df: df} do
+    {:ok, json} = DF.to_json(df)
+    assert {:ok, read} = DF.read_json_binary(json)
+    assert {:ok, %{"id" => [1, 2, 3], "name" => ["a", nil, "c"]}} = DF.to_columns_map(read)
+  end
+
+  test "binary readers return an error for malformed data" do
+    assert {:error, %ExPolars.Error{}} = DF.read_parquet_binary("not parquet")
+    assert {:error, %ExPolars.Error{}} = DF.read_ipc_binary("not ipc")
+    assert {:error, %ExPolars.Error{}} = DF.from_binary("not an ipc stream")
+  end
+end
@@ -0,0 +1,31 @@
+defmodule ExPolars.ErrorTest do
+  use ExUnit.Case, async: true
+
+  alias ExPolars.DataFrame, as: DF
+
+  test "io errors carry the reason as details" do
+    path = Path.join(System.tmp_dir!(), "ex_polars_missing_#{System.unique_integer([:positive])}")
+
+    assert {:error, %ExPolars.Error{kind: :io, details: :not_found, message: message}} =
+             DF.read_csv(path)
+
+    assert is_binary(message)
+  end
+
+  test "polars errors carry their kind" do
+    {:ok, df} = DF.from_columns(%{"a" => [1, 2]})
+
+    assert {:error, %ExPolars.Error{kind: :not_found, message: message, details: details}} =
+             DF.drop(df, "b")
+
+    assert message =~ "b"
+    assert is_binary(details)
+  end
+
+  test "errors are exceptions" do
+    {:ok, df} = DF.from_columns(%{"a" => [1, 2]})
+    assert {:error, %ExPolars.Error{kind: :other} = error} = DF.to_parquet(df, "brotli9")
+    assert Exception.message(error) =~ "brotli9"
+    assert_raise ExPolars.Error, fn -> raise error end
+  end
+end
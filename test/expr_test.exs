@@ -0,0 +1,70 @@
+defmodule ExPolars.ExprTest do
+  use ExUnit.Case, async: true
+
+  import Kernel, except: [+: 2, -: 2, *: 2, /: 2, ==: 2, !=: 2, >: 2, >=: 2, <: 2, <=: 2]
+  import ExPolars.Expr
+
+  alias ExPolars.DataFrame, as: DF
+
+  setup do
+    {:ok, df} =
+      DF.from_columns(%{
+        "region" => ["eu", "us", "eu", "us", "eu"],
+        "status" => ["ok", "ok", "failed", "ok", "ok"],
+        "qty" => [1, 0, 3, 4, 0],
+        "price" => [10.0, 20.0, 30.0, 40.0, 50.0]
+      })
+
+    {:ok, df: df}
+  end
+
+  test "operators fall back to Kernel without an expression operand" do
+    assert 1 + 2 == 3
+    assert 1 != 2
+    assert "ex" <> "polars" == "expolars"
+  end
+
+  test "filter with an expression", %{df: df} do
+    result = DF.filter(df, and_(col("price") > 10, col("qty") != 0))
+    assert {:ok, %{"price" => [30.0, 40.0], "qty" => [3, 4]}} = DF.to_columns_map(result)
+  end
+
+  test "select with an expression", %{df: df} do
+    result = DF.select(df, [col("region"), as(col("price") * col("qty"), "revenue")])
+
+    assert {:ok, %{"region" => ["eu", "us", "eu", "us", "eu"], "revenue" => revenue}} =
+             DF.to_columns_map(result)
+
+    assert revenue == [10.0, 0.0, 90.0, 160.0, 0.0]
+  end
+
+  test "filtered count in groupby_exprs", %{df: df} do
+    result =
+      DF.groupby_exprs(
+        df,
+        ["region"],
+        [orders: count(), ok_orders: filter(count(), col("status") == "ok")],
+        sort: true
+      )
+
+    assert {:ok, %{"region" => ["eu", "us"], "orders" => [3, 2], "ok_orders" => [2, 2]}} =
+             DF.to_columns_map(result)
+  end
+
+  test "column aggregations in groupby_agg keep working next to expressions", %{df: df} do
+    pairs = [{"price", ["std", "var", "agg_list"]}]
+
+    {:ok, expected} =
+      df |> DF.groupby_agg(["region"], pairs) |> DF.sort("region") |> DF.to_columns_map()
+
+    result = DF.groupby_agg(df, ["region"], pairs ++ [as(sum("qty"), "qty")])
+    assert {:ok, ["region" | columns]} = DF.columns(result)
+    assert List.last(columns) == "qty"
+    assert {:ok, %{"region" => ["eu", "us"], "qty" => [4, 4]} = map} = DF.to_columns_map(result)
+    assert Map.delete(map, "qty") == expected
+  end
+
+  test "count() outside a groupby is an error", %{df: df} do
+    assert {:error, %ExPolars.Error{kind: :other}} = DF.select(df, [count()])
+  end
+end
@@ -0,0 +1,66 @@
+defmodule ExPolars.FromRowsTest do
+  use ExUnit.Case, async: true
+
+  alias ExPolars.DataFrame, as: DF
+  alias ExPolars.Series, as: S
+
+  defmodule User do
+    defstruct [:__meta__, :id, :name]
+  end
+
+  test "missing keys and nils are nulls" do
+    rows = [%{id: 1, name: "a"}, %{id: nil}, %{name: "c", score: 1.5}]
+    assert {:ok, df} = DF.from_rows(rows)
+    assert {:ok, {3, 3}} = DF.shape(df)
+    assert {:ok, columns} = DF.to_columns_map(df)
+    assert columns["id"] == [1, nil, nil]
+    assert columns["name"] == ["a", nil, "c"]
+    assert columns["score"] == [nil, nil, 1.5]
+
+    assert {:ok, df} = DF.from_columns(%{"id" => [1, nil], "flag" => [nil, true]})
+    assert {:ok, %{"id" => [1, nil], "flag" => [nil, true]}} = DF.to_columns_map(df)
+  end
+
+  test "a column of only nils is a string column" do
+    assert {:ok, df} = DF.from_rows([%{"x" => nil}, %{"x" => nil}])
+    assert DF.dtypes(df) == {:ok, [S.dtype_id("str")]}
+  end
+
+  test "integers mixed with floats become floats" do
+    assert {:ok, df} = DF.from_rows([%{"x" => 1}, %{"x" => 2.5}])
+    assert {:ok, %{"x" => [1.0, 2.5]}} = DF.to_columns_map(df)
+
+    assert {:ok, df} = DF.from_columns(%{"x" => [1.5, 2]})
+    assert {:ok, %{"x" => [1.5, 2.0]}} = DF.to_columns_map(df)
+  end
+
+  test "dates mixed with datetimes become datetimes" do
+    assert {:ok, df} = DF.from_columns(%{"t" => [~D[2021-01-01], ~N[2021-01-02 03:04:05]]})
+    assert DF.dtypes(df) == {:ok, [S.dtype_id("date64")]}
+
+    assert {:ok, %{"t" => [~N[2021-01-01 00:00:00.000000], ~N[2021-01-02 03:04:05.000000]]}} =
+             DF.to_columns_map(df)
+
+    assert {:ok, df} = DF.from_rows([%{d: ~D[2021-01-01]}, %{d: nil}])
+    assert {:ok, [%{"d" => ~D[2021-01-01]}, %{"d" => nil}]} = DF.to_rows(df)
+  end
+
+  test "other mixed values are an error" do
+    assert {:error, %ExPolars.Error{kind: :other}} = DF.from_rows([%{"x" => 1}, %{"x" => "a"}])
+  end
+
+  test "the __struct__ and __meta__ fields of structs are skipped" do
+    rows = [%User{__meta__: :loaded, id: 1, name: "a"}, %User{id: 2}]
+    assert {:ok, df} = DF.from_rows(rows)
+    assert {:ok, columns} = DF.columns(df)
+    assert Enum.sort(columns) == ["id", "name"]
+    assert {:ok, %{"id" => [1, 2], "name" => ["a", nil]}} = DF.to_columns_map(df)
+  end
+
+  test "a schema picks, orders and converts the columns" do
+    rows = [%{id: 1, name: "a", extra: true}, %{id: 2, name: "b"}]
+    assert {:ok, df} = DF.from_rows(rows, name: "str", id: "f64")
+    assert {:ok, ["name", "id"]} = DF.columns(df)
+    assert {:ok, %{"id" => [1.0, 2.0]}} = DF.to_columns_map(df)
+  end
+end
@@ -0,0 +1,62 @@
+defmodule ExPolars.GroupbyApplyTest do
+  use ExUnit.Case, async: true
+
+  alias ExPolars.DataFrame, as: DF
+
+  setup do
+    {:ok, df} =
+      DF.from_columns(%{"cohort" => ["b", "a", "b", "a", "b"], "value" => [1, 2, 3, 4, 5]})
+
+    {:ok, df: df}
+  end
+
+  test "concatenates the results in order of first appearance", %{df: df} do
+    result = DF.groupby_apply(df, ["cohort"], fn group -> DF.head(group, 2) end)
+
+    assert {:ok, %{"cohort" => ["b", "b", "a", "a"], "value" => [1, 3, 2, 4]}} =
+             DF.to_columns_map(result)
+  end
+
+  test "calls the callback once per group with the rows of the group", %{df: df} do
+    parent = self()
+
+    {:ok, _} =
+      DF.groupby_apply(df, ["cohort"], fn group ->
+        send(parent, {:group, DF.to_columns_map(group)})
+        group
+      end)
+
+    assert_received {:group, {:ok, %{"cohort" => ["b", "b", "b"], "value" => [1, 3, 5]}}}
+    assert_received {:group, {:ok, %{"cohort" => ["a", "a"], "value" => [2, 4]}}}
+    refute_received {:group, _}
+  end
+
+  test "stops at the first error", %{df: df} do
+    parent = self()
+
+    result =
+      DF.groupby_apply(df, ["cohort"], fn _group ->
+        send(parent, :called)
+        {:error, :boom}
+      end)
+
+    assert result == {:error, :boom}
+    assert_received :called
+    refute_received :called
+  end
+
+  test "other callback results are an invalid_argument error", %{df: df} do
+    assert {:error, %ExPolars.Error{kind: :invalid_argument, details: :nope}} =
+             DF.groupby_apply(df, ["cohort"], fn _group -> :nope end)
+  end
+
+  test "an empty frame is returned without calling the callback", %{df: df} do
+    {:ok, empty} = DF.head(df, 0)
+    assert {:ok, result} = DF.groupby_apply(empty, ["cohort"], fn _ -> raise "called" end)
+    assert {:ok, {0, 2}} = DF.shape(result)
+  end
+
+  test "an unknown key column is an error", %{df: df} do
+    assert {:error, %ExPolars.Error{}} = DF.groupby_apply(df, ["missing"], & &1)
+  end
+end
@@ -0,0 +1,46 @@
+defmodule ExPolars.LazyFrameTest do
+  use ExUnit.Case, async: true
+
+  import Kernel, except: [+: 2, -: 2, *: 2, /: 2, ==: 2, !=: 2, >: 2, >=: 2, <: 2, <=: 2]
+  import ExPolars.Expr
+
+  alias ExPolars.DataFrame, as: DF
+  alias ExPolars.LazyFrame, as: LF
+
+  setup do
+    {:ok, df} = DF.from_columns(%{"name" => ["a", "b", "c", "d"], "price" => [5, 12, 30, 8]})
+    {:ok, df: df}
+  end
+
+  test "from_df, filter and select round trip through collect", %{df: df} do
+    result =
+      df
+      |> LF.from_df()
+      |> LF.filter(col("price") > 10)
+      |> LF.select([col("name"), as(col("price") * 2, "double")])
+      |> LF.collect()
+
+    assert {:ok, %{"name" => ["b", "c"], "double" => [24, 60]}} = DF.to_columns_map(result)
+  end
+
+  test "collecting a plan without steps returns the frame", %{df: df} do
+    assert {:ok, collected} = df |> LF.from_df() |> LF.collect()
+    assert {:ok, true} = DF.equal(df, collected)
+  end
+
+  test "sort and limit", %{df: df} do
+    result = df |> LF.from_df() |> LF.sort("price", true) |> LF.limit(2) |> LF.collect()
+    assert {:ok, %{"price" => [30, 12]}} = DF.to_columns_map(result)
+  end
+
+  test "scanning a missing file or a directory is an error" do
+    assert {:error, %ExPolars.Error{kind: :io}} = LF.scan_csv("no-such-file.csv")
+    assert {:error, %ExPolars.Error{kind: :io}} = LF.scan_csv(System.tmp_dir!())
+    assert {:error, %ExPolars.Error{}} = LF.scan_parquet(System.tmp_dir!())
+  end
+
+  test "unsupported join types are an error", %{df: df} do
+    {:ok, ldf} = LF.from_df(df)
+    assert {:error, %ExPolars.Error{}} = LF.join(ldf, ldf, col("name"), col("name"), "cross")
+  end
+end
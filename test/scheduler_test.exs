@@ -0,0 +1,60 @@
+defmodule ExPolars.SchedulerTest do
+  @moduledoc """
+  Heavy NIFs run on dirty schedulers, so reading large files on every scheduler at once must not
+  stop other processes from running. Run with `mix test --include stress`.
+  """
+  use ExUnit.Case, async: false
+
+  alias ExPolars.DataFrame, as: DF
+
+  @moduletag :stress
+  @moduletag timeout: 600_000
+
+  @rows 2_000_000
+  @tick_ms 10
+  @max_delay_ms 200
+
+  setup_all do
+    path =
+      Path.join(System.tmp_dir!(), "ex_polars_stress_#{System.unique_integer([:positive])}.csv")
+
+    File.open!(path, [:write], fn f ->
+      IO.binwrite(f, "id,value,name\n")
+
+      1..@rows
+      |> Stream.chunk_every(10_000)
+      |> Enum.each(fn chunk ->
+        IO.binwrite(f, Enum.map(chunk, &"#{&1},#{&1 * 0.5},name_#{rem(&1, 100)}\n"))
+      end)
+    end)
+
+    on_exit(fn -> File.rm(path) end)
+    {:ok, path: path}
+  end
+
+  test "BEAM stays responsive while large csv files are read", %{path: path} do
+    ticker = Task.async(fn -> tick(System.monotonic_time(:millisecond), 0) end)
+
+    results =
+      1..System.schedulers_online()
+      |> Enum.map(fn _ -> Task.async(fn -> DF.read_csv(path) end) end)
+      |> Task.await_many(600_000)
+
+    send(ticker.pid, :stop)
+    max_delay = Task.await(ticker)
+
+    assert Enum.all?(results, &match?({:ok, _}, &1))
+    assert max_delay < @max_delay_ms
+  end
+
+  # returns the largest delay of a tick beyond its @tick_ms timeout
+  defp tick(last, max_delay) do
+    receive do
+      :stop -> max_delay
+    after
+      @tick_ms ->
+        now = System.monotonic_time(:millisecond)
+        tick(now, max(max_delay, now - last - @tick_ms))
+    end
+  end
+end
@@ -0,0 +1,55 @@
+defmodule ExPolars.SeriesTest do
+  use ExUnit.Case, async: true
+
+  alias ExPolars.DataFrame, as: DF
+  alias ExPolars.Series, as: S
+
+  describe "new_opt" do
+    test "turns nils into nulls for every kind of dtype" do
+      for {dtype, values} <- [
+            {"i64", [1, nil, -3]},
+            {"f64", [nil, 1.5, 2.5]},
+            {"bool", [true, false, nil]},
+            {"str", ["a", nil, "c"]},
+            {"date32", [0, nil, 1]},
+            {"duration_nanosecond", [nil, 5, 10]}
+          ] do
+        assert {:ok, s} = S.new_opt("s", dtype, values)
+        assert {:ok, 1} = S.null_count(s)
+        assert {:ok, list} = S.to_list(s)
+
+        expected =
+          case dtype do
+            "date32" -> [~D[1970-01-01], nil, ~D[1970-01-02]]
+            _ -> values
+          end
+
+        assert list == expected
+      end
+    end
+
+    test "is an error for unknown dtypes" do
+      assert {:error, %ExPolars.Error{kind: :other}} = S.new_opt("s", "decimal", [1])
+    end
+  end
+
+  describe "dates beyond the range of Date and NaiveDateTime" do
+    test "are an error in to_list" do
+      assert {:error, %ExPolars.Error{}} =
+               "d" |> S.new_opt("date32", [2_147_483_647]) |> S.to_list()
+
+      assert {:error, %ExPolars.Error{}} =
+               "d" |> S.new_opt("date64", [-9_223_372_036_854_775_808]) |> S.to_list()
+    end
+
+    test "are an error in to_rows and to_columns_map" do
+      assert {:ok, s} = S.new_opt("d", "date32", [0, 2_147_483_647])
+      assert {:ok, df} = DF.from_columns(%{"id" => [1, 2]})
+      assert {:ok, df} = DF.hstack(df, [s])
+
+      assert {:error, %ExPolars.Error{}} = DF.to_rows(df)
+      assert {:error, %ExPolars.Error{}} = DF.to_columns_map(df)
+      assert {:ok, [%{"d" => ~D[1970-01-01]}]} = df |> DF.head(1) |> DF.to_rows()
+    end
+  end
+end